@@ -1,6 +1,189 @@
-use crate::state::{EventHandler, StateBase};
+use crate::{
+    battle::Battle,
+    pokemon::{PokeType, Pokemon, StatKind},
+    pokemove::{MoveCategory, PokeMove},
+    state::{ChanceBuilder, EventHandler, Node},
+    status::Status,
+    weather::Weather,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy)]
-pub enum Ability {}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Ability {
+    Berserk,
+    Blaze,
+    Chlorophyll,
+    Contrary,
+    DrySkin,
+    Guts,
+    Heatproof,
+    Infiltrator,
+    Insomnia,
+    Intimidate,
+    Levitate,
+    MagicGuard,
+    /// Does nothing, for pokemon whose ability doesn't matter.
+    NoAbility,
+    Overgrow,
+    Stamina,
+    Static,
+    StickyHold,
+    Swarm,
+    SwiftSwim,
+    ThickFat,
+    Torrent,
+}
 
-impl<S: StateBase> EventHandler<S> for Ability {}
+impl Ability {
+    pub fn speed_multiplier(&self, weather: Weather) -> f64 {
+        match (self, weather) {
+            (Ability::Chlorophyll, Weather::Sun) | (Ability::SwiftSwim, Weather::Rain) => 2.0,
+            _ => 1.0,
+        }
+    }
+
+    /// Multiplies the attacking stat the holder uses for a `category` move of `move_type`.
+    pub fn attack_multiplier(
+        &self,
+        holder: &Pokemon,
+        move_type: &PokeType,
+        category: MoveCategory,
+    ) -> f64 {
+        let pinch_type = match self {
+            Ability::Blaze => Some(PokeType::Fire),
+            Ability::Overgrow => Some(PokeType::Grass),
+            Ability::Torrent => Some(PokeType::Water),
+            Ability::Swarm => Some(PokeType::Bug),
+            _ => None,
+        };
+
+        let in_pinch = holder.current_hp * 3 <= holder.computed_stat(StatKind::Hp);
+
+        match self {
+            _ if pinch_type.as_ref() == Some(move_type) && in_pinch => 1.5,
+            Ability::Guts
+                if category == MoveCategory::Physical && holder.status != Status::Healthy =>
+            {
+                1.5
+            }
+            _ => 1.0,
+        }
+    }
+
+    /// Whether the holder's Attack isn't halved by a burn.
+    pub fn ignores_burn(&self) -> bool {
+        matches!(self, Ability::Guts)
+    }
+
+    /// Whether the holder takes no damage from moves of `move_type`, whatever its types are.
+    pub fn grants_immunity(&self, move_type: &PokeType) -> bool {
+        matches!((self, move_type), (Ability::Levitate, PokeType::Ground))
+    }
+
+    /// Multiplies the damage the holder takes from moves of `move_type`.
+    ///
+    /// A negative multiplier means the move is absorbed instead, healing that fraction of the
+    /// holder's max HP.
+    pub fn incoming_multiplier(&self, move_type: &PokeType) -> f64 {
+        match (self, move_type) {
+            (Ability::ThickFat, PokeType::Fire)
+            | (Ability::ThickFat, PokeType::Ice)
+            | (Ability::Heatproof, PokeType::Fire) => 0.5,
+            (Ability::DrySkin, PokeType::Fire) => 1.25,
+            (Ability::DrySkin, PokeType::Water) => -0.25,
+            _ => 1.0,
+        }
+    }
+
+    /// Whether the holder's moves go straight through the target's substitute and screens.
+    pub fn infiltrates(&self) -> bool {
+        matches!(self, Ability::Infiltrator)
+    }
+
+    /// Whether the holder only takes damage from direct attacks.
+    pub fn prevents_indirect_damage(&self) -> bool {
+        matches!(self, Ability::MagicGuard)
+    }
+
+    pub fn prevents_sleep(&self) -> bool {
+        matches!(self, Ability::Insomnia)
+    }
+
+    /// Whether the holder's item can't be taken by other pokemon.
+    pub fn keeps_item(&self) -> bool {
+        matches!(self, Ability::StickyHold)
+    }
+
+    /// Adjusts a stat stage change about to be applied to the holder.
+    pub fn modify_boost(&self, stages: i8) -> i8 {
+        match self {
+            Ability::Contrary => -stages,
+            _ => stages,
+        }
+    }
+}
+
+impl<S: Battle> EventHandler<S, S::Position> for Ability {
+    fn on_switch_in(&self, mut state: S, position: S::Position) -> Node<S> {
+        if let Ability::Intimidate = self {
+            for opponent in state.opponents(position) {
+                state.boost(opponent, StatKind::Attack, -1);
+            }
+        }
+
+        Node::pending(state)
+    }
+
+    fn on_damage_taken(
+        &self,
+        mut state: S,
+        defender: S::Position,
+        attacker: S::Position,
+        pokemove: &PokeMove,
+        amount: u32,
+    ) -> Node<S> {
+        match self {
+            Ability::Stamina => {
+                state.boost(defender, StatKind::Defense, 1);
+            }
+            // Physical moves are treated as making contact
+            Ability::Static => {
+                let made_contact = pokemove.category == MoveCategory::Physical;
+                let can_paralyze = state.pokemon(attacker).is_some_and(|pokemon| {
+                    !pokemon.is_fainted() && pokemon.status == Status::Healthy
+                });
+
+                if made_contact && can_paralyze {
+                    return ChanceBuilder::new("Static")
+                        .named_possibilities(vec![
+                            ("Paralyzed", 0.3, true),
+                            ("Not paralyzed", 0.7, false),
+                        ])
+                        .build(state, move |mut state, paralyzed| {
+                            if paralyzed {
+                                state.apply_status(attacker, Status::Paralysis);
+                            }
+
+                            Node::pending(state)
+                        });
+                }
+            }
+            Ability::Berserk => {
+                let pokemon = state.pokemon(defender).unwrap();
+                let half = pokemon.computed_stat(StatKind::Hp) / 2;
+
+                if pokemon.current_hp > 0
+                    && pokemon.current_hp <= half
+                    && pokemon.current_hp + amount > half
+                {
+                    state.boost(defender, StatKind::SpecialAttack, 1);
+                }
+            }
+            _ => {}
+        }
+
+        Node::pending(state)
+    }
+}
@@ -0,0 +1,118 @@
+use crate::state::{Branches, Expansion, Node, StateBase};
+
+/// Finds the best choice for `player` at `root`, looking `depth` decisions ahead.
+///
+/// `eval` scores states from `player`'s point of view. The opponent is assumed to pick whatever
+/// scores lowest, and chances are averaged by weight. Chances don't count towards `depth`.
+///
+/// Returns the index of the best choice and its value. Panics if `root` isn't a decision for
+/// `player`.
+pub fn expectimax<S, F>(root: Node<S>, player: S::Player, depth: usize, eval: F) -> (usize, f64)
+where
+    S: StateBase + 'static,
+    S::Player: PartialEq,
+    F: Fn(&S) -> f64,
+{
+    match root.expand() {
+        Expansion::Decision(deciding, choices) if deciding == player => choices
+            .into_iter()
+            .map(|(idx, node)| (idx, value(node, player, depth.saturating_sub(1), &eval)))
+            .fold(
+                None,
+                |best: Option<(usize, f64)>, (idx, value)| match best {
+                    Some((_, best_value)) if best_value >= value => best,
+                    _ => Some((idx, value)),
+                },
+            )
+            .expect("Decision with no enabled choices"),
+        _ => panic!("Expectimax needs a decision for {:?} at the root", player),
+    }
+}
+
+fn value<S, F>(node: Node<S>, player: S::Player, depth: usize, eval: &F) -> f64
+where
+    S: StateBase + 'static,
+    S::Player: PartialEq,
+    F: Fn(&S) -> f64,
+{
+    if depth == 0 {
+        if let Branches::Decision(_) = node.branches() {
+            return eval(node.state());
+        }
+    }
+
+    match node.expand() {
+        Expansion::Chance(possibilities) => {
+            let total: f64 = possibilities.iter().map(|(weight, _)| weight).sum();
+
+            possibilities
+                .into_iter()
+                .map(|(weight, node)| weight * value(node, player, depth, eval))
+                .sum::<f64>()
+                / total
+        }
+        Expansion::Decision(deciding, choices) => {
+            let values = choices
+                .into_iter()
+                .map(|(_, node)| value(node, player, depth - 1, eval));
+
+            if deciding == player {
+                values.fold(f64::NEG_INFINITY, f64::max)
+            } else {
+                values.fold(f64::INFINITY, f64::min)
+            }
+        }
+        Expansion::Leaf(state) => eval(&state),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        state::{ChanceBuilder, DecisionBuilder},
+        testing::{Counter, Side},
+    };
+
+    fn end_with(_state: Counter, score: u32) -> Node<Counter> {
+        Node::end(Counter(score))
+    }
+
+    /// A coin flip, a guaranteed win, and a choice that the opponent gets to make.
+    fn game() -> Node<Counter> {
+        DecisionBuilder::new("Play", Side::Us)
+            .named_choices(vec![("Gamble", 0), ("Win", 1), ("Let them pick", 2)])
+            .build(Counter(0), |state, choice| match choice {
+                0 => ChanceBuilder::new("Coin")
+                    .possibility(0.5, 150)
+                    .possibility(0.5, 50)
+                    .build(state, end_with),
+                1 => Node::end(Counter(200)),
+                _ => DecisionBuilder::new("Punish", Side::Them)
+                    .choices(vec![300, 20])
+                    .build(state, end_with),
+            })
+    }
+
+    fn eval(state: &Counter) -> f64 {
+        f64::from(state.0)
+    }
+
+    #[test]
+    fn expectimax_picks_the_guaranteed_win() {
+        assert_eq!(expectimax(game(), Side::Us, 2, eval), (1, 200.0));
+    }
+
+    #[test]
+    fn expectimax_averages_chances_and_assumes_the_worst_of_opponents() {
+        let values: Vec<_> = match game().expand() {
+            Expansion::Decision(_, choices) => choices
+                .into_iter()
+                .map(|(_, node)| value(node, Side::Us, 1, &eval))
+                .collect(),
+            _ => panic!("Expected a decision"),
+        };
+
+        assert_eq!(values, [100.0, 200.0, 20.0]);
+    }
+}
@@ -0,0 +1,102 @@
+use crate::state::{Branches, Expansion, Node, StateBase};
+
+/// A decision was reached while enumerating outcomes, so they can't be given probabilities.
+/// Carries the state at that decision.
+#[derive(Debug)]
+pub struct DecisionReached<S>(pub S);
+
+/// Every leaf reachable from `root` through chances alone, with the probability of ending up
+/// there.
+///
+/// Fails on the first decision reached, since picking a choice isn't down to chance.
+pub fn enumerate_outcomes<S>(root: Node<S>) -> Result<Vec<(S, f64)>, DecisionReached<S>>
+where
+    S: StateBase + 'static,
+{
+    let mut outcomes = Vec::new();
+    collect_outcomes(root, 1.0, &mut outcomes)?;
+    Ok(outcomes)
+}
+
+fn collect_outcomes<S>(
+    node: Node<S>,
+    probability: f64,
+    outcomes: &mut Vec<(S, f64)>,
+) -> Result<(), DecisionReached<S>>
+where
+    S: StateBase + 'static,
+{
+    if let Branches::Decision(_) = node.branches() {
+        return Err(DecisionReached(node.state().clone()));
+    }
+
+    match node.expand() {
+        Expansion::Chance(possibilities) => {
+            let total: f64 = possibilities.iter().map(|(weight, _)| weight).sum();
+
+            for (weight, node) in possibilities {
+                collect_outcomes(node, probability * weight / total, outcomes)?;
+            }
+        }
+        Expansion::Leaf(state) => outcomes.push((state, probability)),
+        Expansion::Decision(..) => unreachable!(),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        state::{ChanceBuilder, DecisionBuilder},
+        testing::{Counter, Side},
+    };
+
+    fn add(state: Counter, amount: u32) -> Node<Counter> {
+        Node::end(Counter(state.0 + amount))
+    }
+
+    #[test]
+    fn probabilities_multiply_down_each_path() {
+        let root = ChanceBuilder::new("First")
+            .possibility(1.0, 10)
+            .possibility(3.0, 20)
+            .build(Counter(0), |state, tens| {
+                ChanceBuilder::new("Second")
+                    .possibility(1.0, 1)
+                    .possibility(1.0, 2)
+                    .build(Counter(state.0 + tens), add)
+            });
+
+        let outcomes = enumerate_outcomes(root).unwrap();
+
+        assert_eq!(
+            outcomes,
+            [
+                (Counter(11), 0.125),
+                (Counter(12), 0.125),
+                (Counter(21), 0.375),
+                (Counter(22), 0.375)
+            ]
+        );
+        assert_eq!(outcomes.iter().map(|(_, p)| p).sum::<f64>(), 1.0);
+    }
+
+    #[test]
+    fn decisions_stop_the_enumeration() {
+        let root =
+            ChanceBuilder::new("Roll")
+                .possibility(1.0, 1)
+                .build(Counter(0), |state, amount| {
+                    DecisionBuilder::new("Add", Side::Us)
+                        .choices(vec![1, 2])
+                        .build(Counter(state.0 + amount), add)
+                });
+
+        assert!(matches!(
+            enumerate_outcomes(root),
+            Err(DecisionReached(Counter(1)))
+        ));
+    }
+}
@@ -0,0 +1,459 @@
+//! What abilities, items and effects need from a battle, so that the same handlers work in single
+//! and double battles.
+
+use std::{
+    cmp::{Ordering, Reverse},
+    fmt::{Debug, Display},
+    rc::Rc,
+};
+
+use crate::{
+    damage::{self, DamageContext},
+    log::LogEvent,
+    pokemon::{PokeType, Pokemon, StatKind, StatStages, Team, TypeEffectiveness},
+    pokemove::{MoveCategory, MoveEffect, PokeMove, Screen},
+    rules::BattleRules,
+    single::{Player, SideConditions},
+    state::{ChanceBuilder, EventHandler, Node, PlayerBase, StateBase},
+    status::Status,
+    terrain::Terrain,
+    weather::Weather,
+};
+
+/// A battle between two players, whatever its format.
+pub trait Battle: StateBase<Player = Player> + 'static {
+    /// Where an active pokemon is on the field: its player's side in single battles, or its
+    /// [`Slot`](crate::double::Slot) in doubles.
+    type Position: Copy + Debug + Display + PartialEq + 'static;
+
+    /// Whether this is a double battle, where screens are weaker.
+    const DOUBLES: bool;
+
+    /// The player whose side of the field `position` is on.
+    fn side_of(position: Self::Position) -> Player;
+
+    fn side(&self, player: Player) -> &SideConditions;
+
+    fn side_mut(&mut self, player: Player) -> &mut SideConditions;
+
+    fn rules(&self) -> &BattleRules;
+
+    /// How many turns have started so far.
+    fn turn(&self) -> u32;
+
+    fn team(&self, player: Player) -> &Team;
+
+    /// The active pokemon at `position`, fainted or not, or `None` if nobody's there.
+    fn pokemon(&self, position: Self::Position) -> Option<&Pokemon>;
+
+    fn pokemon_mut(&mut self, position: Self::Position) -> Option<&mut Pokemon>;
+
+    /// The positions of the opposing pokemon still standing, as seen from `position`.
+    fn opponents(&self, position: Self::Position) -> Vec<Self::Position>;
+
+    /// Changes a stat stage of the pokemon at `position`, taking its ability into account.
+    ///
+    /// Returns the number of stages actually changed.
+    fn boost(&mut self, position: Self::Position, kind: StatKind, stages: i8) -> i8;
+
+    /// Gives `status` to the pokemon at `position` on behalf of its opponent.
+    ///
+    /// Returns whether the status was applied.
+    fn apply_status(&mut self, position: Self::Position, status: Status) -> bool;
+
+    fn stat_stages(&self, position: Self::Position) -> &StatStages;
+
+    /// The current types of the pokemon at `position`, which can differ from its form's.
+    fn active_types(&self, position: Self::Position) -> Vec<PokeType>;
+
+    fn weather(&self) -> Weather;
+
+    fn terrain(&self) -> Terrain;
+
+    /// The effective speed of the pokemon at `position`. All speed-based ordering must go
+    /// through this.
+    fn speed(&self, position: Self::Position) -> u32;
+
+    /// Deals damage that doesn't come from a direct attack to the pokemon at `position`.
+    ///
+    /// All residual, recoil and self-inflicted damage must go through this.
+    fn deal_indirect_damage(&mut self, position: Self::Position, amount: u32);
+
+    fn log(&mut self, event: LogEvent);
+
+    /// Restores `fraction` of the max HP of the pokemon at `position`, unless it's fainted or
+    /// already at full HP.
+    ///
+    /// Returns whether any HP was restored.
+    fn restore_hp(&mut self, position: Self::Position, fraction: f64) -> bool {
+        let max_hp = self.pokemon(position).unwrap().computed_stat(StatKind::Hp);
+        self.restore_hp_by(position, ((f64::from(max_hp) * fraction) as u32).max(1))
+    }
+
+    /// Heals the pokemon at `position` by up to `amount` HP, without going over its max HP.
+    ///
+    /// Returns whether it was healed.
+    fn restore_hp_by(&mut self, position: Self::Position, amount: u32) -> bool {
+        let pokemon = self.pokemon_mut(position).unwrap();
+        let max_hp = pokemon.computed_stat(StatKind::Hp);
+
+        if pokemon.is_fainted() || pokemon.current_hp >= max_hp {
+            return false;
+        }
+
+        let amount = amount.min(max_hp - pokemon.current_hp);
+        pokemon.current_hp += amount;
+
+        let pokemon = pokemon.to_string();
+        self.log(LogEvent::Healed { pokemon, amount });
+
+        true
+    }
+
+    /// Handlers installed on the battle itself, which hear about every pokemon's events after its
+    /// own ability and item.
+    fn handlers(&self) -> Vec<Rc<dyn EventHandler<Self, Self::Position>>> {
+        Vec::new()
+    }
+
+    /// Runs `hook` for the ability and then the item of the pokemon at `position`, and then for
+    /// the battle's own [`handlers`](Battle::handlers).
+    fn run_handlers<F>(self, position: Self::Position, hook: F) -> Node<Self>
+    where
+        F: Fn(&dyn EventHandler<Self, Self::Position>, Self) -> Node<Self> + Clone + 'static,
+    {
+        let ability = match self.pokemon(position) {
+            Some(pokemon) => pokemon.ability,
+            None => return Node::pending(self),
+        };
+        let handlers = self.handlers();
+        let item_hook = hook.clone();
+
+        // The ability may have changed the item
+        let node = hook(&ability, self).then(move |state| {
+            match state
+                .pokemon(position)
+                .and_then(|pokemon| pokemon.item.clone())
+            {
+                Some(item) => item_hook(&item, state),
+                None => Node::pending(state),
+            }
+        });
+
+        handlers.into_iter().fold(node, |node, handler| {
+            let hook = hook.clone();
+            node.then(move |state| hook(&*handler, state))
+        })
+    }
+
+    /// Whether a screen protects the pokemon at `position` from moves of `category`.
+    fn is_screened(&self, position: Self::Position, category: MoveCategory) -> bool {
+        self.side(Self::side_of(position)).is_screened(category)
+    }
+
+    /// Sets up `screen` on the side of the pokemon at `position`. Fails if it's already up.
+    fn set_screen(&mut self, position: Self::Position, screen: Screen) {
+        let extended = match &self.pokemon(position).unwrap().item {
+            Some(item) => item.extends_screens(),
+            None => false,
+        };
+        let player = Self::side_of(position);
+        let turns = self.side_mut(player).screen_turns_mut(screen);
+
+        if *turns > 0 {
+            self.log(LogEvent::MoveFailed);
+            return;
+        }
+
+        *turns = if extended { 8 } else { 5 };
+        self.log(LogEvent::ScreenStarted {
+            player: player.to_string(),
+            screen,
+        });
+    }
+
+    /// Counts down the screens on `player`'s side at the end of the turn.
+    fn tick_screens(&mut self, player: Player) {
+        for &screen in &[Screen::Reflect, Screen::LightScreen] {
+            let turns = self.side_mut(player).screen_turns_mut(screen);
+
+            if *turns == 0 {
+                continue;
+            }
+
+            *turns -= 1;
+
+            if *turns == 0 {
+                self.log(LogEvent::ScreenEnded {
+                    player: player.to_string(),
+                    screen,
+                });
+            }
+        }
+    }
+
+    fn has_lost(&self, player: Player) -> bool {
+        self.team(player).iter().all(|p| p.is_fainted())
+    }
+
+    /// The player whose opponent has no pokemon left standing, unless they've both run out.
+    fn check_win(&self) -> Option<Player> {
+        Player::values()
+            .iter()
+            .copied()
+            .find(|&player| !self.has_lost(player) && self.has_lost(player.opponent()))
+    }
+
+    /// The player with the larger share of their team's total HP left, or `None` if it's even.
+    fn hp_tiebreak(&self) -> Option<Player> {
+        let hp_left = |player| {
+            self.team(player)
+                .iter()
+                .fold((0, 0), |(current, max), pokemon: &Pokemon| {
+                    (
+                        current + u64::from(pokemon.current_hp),
+                        max + u64::from(pokemon.computed_stat(StatKind::Hp)),
+                    )
+                })
+        };
+
+        let (current_1, max_1) = hp_left(Player::Player1);
+        let (current_2, max_2) = hp_left(Player::Player2);
+
+        match (current_1 * max_2).cmp(&(current_2 * max_1)) {
+            Ordering::Greater => Some(Player::Player1),
+            Ordering::Less => Some(Player::Player2),
+            Ordering::Equal => None,
+        }
+    }
+
+    /// Whether the battle has played as many turns as the rules allow, and should be decided by
+    /// `hp_tiebreak` instead of starting another.
+    fn turn_limit_reached(&self) -> bool {
+        self.rules()
+            .max_turns
+            .is_some_and(|max_turns| self.turn() >= max_turns)
+    }
+
+    /// Whether the pokemon at `position` is on the ground, and so affected by terrain.
+    fn is_grounded(&self, position: Self::Position) -> bool {
+        let ability = self.pokemon(position).unwrap().ability;
+
+        !self.active_types(position).contains(&PokeType::Flying)
+            && !ability.grants_immunity(&PokeType::Ground)
+    }
+
+    /// The types of the pokemon at `target` that matter for type matchups against `pokemove`.
+    fn hit_types(&self, target: Self::Position, pokemove: &PokeMove) -> Vec<PokeType> {
+        match pokemove.effect {
+            Some(MoveEffect::Struggle) => Vec::new(),
+            _ => self.active_types(target),
+        }
+    }
+
+    /// How much damage `pokemove`, used by the pokemon at `user`, deals to the one at `target`,
+    /// and how effective it is.
+    fn move_damage(
+        &self,
+        user: Self::Position,
+        target: Self::Position,
+        pokemove: &PokeMove,
+        spread: bool,
+        crit: bool,
+        roll: f64,
+    ) -> (u32, TypeEffectiveness) {
+        let attacker = self.pokemon(user).unwrap();
+        let defender = self.pokemon(target).unwrap();
+        let defender_types = self.hit_types(target, pokemove);
+        let poke_type = pokemove.effective_type(attacker, self.weather());
+
+        let amount = damage::calculate_damage(&DamageContext {
+            attacker,
+            attacker_stages: self.stat_stages(user),
+            defender,
+            defender_stages: self.stat_stages(target),
+            defender_types: &defender_types,
+            pokemove,
+            poke_type,
+            power: pokemove.power_against(attacker, defender),
+            screened: self.is_screened(target, pokemove.category)
+                && !attacker.ability.infiltrates(),
+            doubles: Self::DOUBLES,
+            spread,
+            crit,
+            roll,
+            weather: self.weather(),
+            terrain: self.terrain(),
+            attacker_grounded: self.is_grounded(user),
+            defender_grounded: self.is_grounded(target),
+        });
+        let effectiveness =
+            damage::effectiveness_with_ability(poke_type, &defender_types, &defender.ability);
+
+        (amount, damage::effectiveness_category(effectiveness))
+    }
+
+    /// Rolls for whether `pokemove`, used by the pokemon at `user` against the one at `target`,
+    /// connects, and calls `on_hit` if it does. Moves that don't target an opponent always
+    /// connect.
+    fn check_accuracy<F>(
+        self,
+        user: Self::Position,
+        target: Self::Position,
+        pokemove: &PokeMove,
+        on_hit: F,
+    ) -> Node<Self>
+    where
+        F: FnOnce(Self) -> Node<Self> + Clone + 'static,
+    {
+        let hit_chance = if pokemove.targets_opponent() {
+            pokemove.hit_chance(self.stat_stages(user), self.stat_stages(target))
+        } else {
+            1.0
+        };
+
+        if hit_chance >= 1.0 {
+            return on_hit(self);
+        }
+
+        ChanceBuilder::new("Accuracy")
+            .named_possibilities(vec![
+                ("Hit", hit_chance, true),
+                ("Miss", 1.0 - hit_chance, false),
+            ])
+            .build(self, move |mut state, hits| {
+                if hits {
+                    return on_hit(state);
+                }
+
+                let pokemon = state.pokemon(user).unwrap().to_string();
+                state.log(LogEvent::Missed { pokemon });
+                Node::pending(state)
+            })
+    }
+
+    /// Rolls for a critical hit if `can_crit` and the rules allow them, then for the random
+    /// factor of the damage if the rules call for one, and calls `hit` with both.
+    fn roll_hit<F>(self, can_crit: bool, hit: F) -> Node<Self>
+    where
+        F: FnOnce(Self, bool, f64) -> Node<Self> + Clone + 'static,
+    {
+        if !(can_crit && self.rules().crits) {
+            return roll_damage(self, false, hit);
+        }
+
+        ChanceBuilder::new("Critical hit")
+            .named_possibilities(vec![
+                ("Critical hit", 1.0 / 24.0, true),
+                ("Regular hit", 23.0 / 24.0, false),
+            ])
+            .build(self, move |state, crit| roll_damage(state, crit, hit))
+    }
+
+    /// Deals a quarter of the max HP of the pokemon at `position` to it, after it struggled.
+    fn struggle_recoil(&mut self, position: Self::Position) {
+        let max_hp = self.pokemon(position).unwrap().computed_stat(StatKind::Hp);
+        self.deal_indirect_damage(position, (max_hp / 4).max(1));
+    }
+
+    /// Runs `f` for each of `positions` in ascending order of `key`.
+    ///
+    /// Positions with equal keys are in a tie, which is broken by a `Chance` over every order
+    /// they could go in.
+    fn in_order_by_key<K, G, F>(self, positions: Vec<Self::Position>, key: G, f: F) -> Node<Self>
+    where
+        K: Ord,
+        G: Fn(&Self, Self::Position) -> K,
+        F: Fn(Self, Self::Position) -> Node<Self> + Copy + 'static,
+    {
+        let mut keyed: Vec<_> = positions
+            .into_iter()
+            .map(|position| (key(&self, position), position))
+            .collect();
+        keyed.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut orders = vec![Vec::new()];
+
+        for tie in keyed.chunk_by(|(a, _), (b, _)| a == b) {
+            let tie: Vec<_> = tie.iter().map(|&(_, position)| position).collect();
+
+            orders = orders
+                .into_iter()
+                .flat_map(|order: Vec<_>| {
+                    permutations(&tie)
+                        .into_iter()
+                        .map(move |permutation| order.iter().copied().chain(permutation).collect())
+                })
+                .collect();
+        }
+
+        if orders.len() == 1 {
+            return self.fold(orders.remove(0), f);
+        }
+
+        let weight = 1.0 / orders.len() as f64;
+        let possibilities = orders.into_iter().map(|order| {
+            let name = order
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(" then ");
+
+            (name, weight, order)
+        });
+
+        ChanceBuilder::new("Speed tie")
+            .named_possibilities(possibilities)
+            .build(self, move |state, order| state.fold(order, f))
+    }
+
+    /// Runs `f` for each of `positions`, fastest first. Speed ties are broken by a `Chance`.
+    fn in_speed_order<F>(self, positions: Vec<Self::Position>, f: F) -> Node<Self>
+    where
+        F: Fn(Self, Self::Position) -> Node<Self> + Copy + 'static,
+    {
+        self.in_order_by_key(
+            positions,
+            |state, position| Reverse(state.speed(position)),
+            f,
+        )
+    }
+}
+
+/// Picks the random factor of a hit's damage, if the rules call for one.
+fn roll_damage<S, F>(state: S, crit: bool, hit: F) -> Node<S>
+where
+    S: Battle,
+    F: FnOnce(S, bool, f64) -> Node<S> + Clone + 'static,
+{
+    if !state.rules().damage_rolls {
+        return hit(state, crit, 1.0);
+    }
+
+    let rolls = (85..=100).map(|percent| (format!("{}%", percent), 1.0 / 16.0, percent));
+
+    ChanceBuilder::new("Damage roll")
+        .named_possibilities(rolls)
+        .build(state, move |state, percent| {
+            hit(state, crit, f64::from(percent) / 100.0)
+        })
+}
+
+/// Every order `items` could be put in.
+fn permutations<T: Copy>(items: &[T]) -> Vec<Vec<T>> {
+    if items.len() <= 1 {
+        return vec![items.to_vec()];
+    }
+
+    (0..items.len())
+        .flat_map(|i| {
+            let mut rest = items.to_vec();
+            let first = rest.remove(i);
+
+            permutations(&rest).into_iter().map(move |mut permutation| {
+                permutation.insert(0, first);
+                permutation
+            })
+        })
+        .collect()
+}
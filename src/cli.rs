@@ -0,0 +1,185 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    hash::Hash,
+    io::{self, BufRead, Write},
+};
+
+use rand::Rng;
+
+use crate::state::{Branches, Decision, Node, StateBase};
+
+/// Picks the index of a choice on behalf of a player who isn't at the terminal.
+pub type Policy<S> = Box<dyn FnMut(&Decision<S>) -> usize>;
+
+/// Plays `root` through to a leaf on the terminal and returns the state it ends on.
+///
+/// Players with an entry in `policies` decide through it, and everyone else is asked on stdin.
+/// Chances are sampled with `rng`, and the log is printed as the battle goes.
+pub fn run_interactive<S, R>(
+    root: Node<S>,
+    policies: HashMap<S::Player, Policy<S>>,
+    rng: &mut R,
+) -> io::Result<S>
+where
+    S: StateBase + 'static,
+    S::Player: Hash + Eq + Display,
+    R: Rng,
+{
+    let stdin = io::stdin();
+    run_interactive_with(root, policies, rng, stdin.lock(), io::stdout())
+}
+
+/// Like `run_interactive`, but reads choices from `input` and writes to `output`.
+///
+/// Fails with `UnexpectedEof` if `input` runs out while a player still has to choose. A policy
+/// that picks a choice that doesn't exist or is disabled gets the first enabled one instead.
+pub fn run_interactive_with<S, R, I, O>(
+    root: Node<S>,
+    mut policies: HashMap<S::Player, Policy<S>>,
+    rng: &mut R,
+    mut input: I,
+    mut output: O,
+) -> io::Result<S>
+where
+    S: StateBase + 'static,
+    S::Player: Hash + Eq + Display,
+    R: Rng,
+    I: BufRead,
+    O: Write,
+{
+    let mut node = root;
+
+    loop {
+        for event in node.drain_log() {
+            writeln!(output, "{}", event)?;
+        }
+
+        let next = match node.branches() {
+            Branches::Chance(_) => node.sample_chance(rng),
+            Branches::Decision(decision) => {
+                let idx = match policies.get_mut(&decision.player()) {
+                    Some(policy) => decision.enabled_or_first(policy(decision)),
+                    None => prompt(decision, &mut input, &mut output)?,
+                };
+
+                node.resolve_decision(idx)
+            }
+            Branches::Pending | Branches::End => return Ok(node.into_state()),
+        };
+
+        node = next.unwrap_or_else(|node| panic!("Can't follow {:?}", node.branches()));
+    }
+}
+
+/// Lists the choices of `decision` and reads lines until one of them is picked by number.
+fn prompt<S, I, O>(decision: &Decision<S>, input: &mut I, output: &mut O) -> io::Result<usize>
+where
+    S: StateBase,
+    S::Player: Display,
+    I: BufRead,
+    O: Write,
+{
+    writeln!(output, "{}: {}", decision.player(), decision.name())?;
+
+    for (idx, choice) in decision.choices().iter().enumerate() {
+        match choice.disabled() {
+            Some(reason) => writeln!(output, "  {}. {} ({})", idx + 1, choice.name(), reason)?,
+            None => writeln!(output, "  {}. {}", idx + 1, choice.name())?,
+        }
+    }
+
+    loop {
+        write!(output, "> ")?;
+        output.flush()?;
+
+        let mut line = String::new();
+
+        if input.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Input ended before a choice was made",
+            ));
+        }
+
+        let picked = line
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .and_then(|number| number.checked_sub(1))
+            .filter(|&idx| {
+                decision
+                    .choices()
+                    .get(idx)
+                    .is_some_and(|choice| choice.is_enabled())
+            });
+
+        match picked {
+            Some(idx) => return Ok(idx),
+            None => writeln!(output, "Pick the number of an available choice")?,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::{
+        pokemon::PokeType,
+        single::{Player, State},
+        testing,
+    };
+
+    fn battle() -> Node<State> {
+        let pikachu = testing::builder("Pikachu", &[PokeType::Electric], &["Thunderbolt"]).build();
+        let magikarp = testing::builder("Magikarp", &[PokeType::Water], &["Swords Dance"])
+            .level(5)
+            .build();
+
+        State::start(testing::team(vec![pikachu]), testing::team(vec![magikarp])).unwrap()
+    }
+
+    fn player_2_picks_first() -> HashMap<Player, Policy<State>> {
+        let mut policies: HashMap<Player, Policy<State>> = HashMap::new();
+        policies.insert(Player::Player2, Box::new(|_| 0));
+        policies
+    }
+
+    #[test]
+    fn scripted_input_plays_a_battle_to_the_end() {
+        let mut output = Vec::new();
+        let state = run_interactive_with(
+            battle(),
+            player_2_picks_first(),
+            &mut StdRng::seed_from_u64(287),
+            "pikachu\n7\n1\n1\n".as_bytes(),
+            &mut output,
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert_eq!(state.winner(), Some(Player::Player1));
+        assert_eq!(
+            output
+                .matches("Pick the number of an available choice")
+                .count(),
+            2
+        );
+        assert!(output.contains("  1. Thunderbolt"));
+    }
+
+    #[test]
+    fn running_out_of_input_is_an_error() {
+        let result = run_interactive_with(
+            battle(),
+            player_2_picks_first(),
+            &mut StdRng::seed_from_u64(287),
+            "1\n".as_bytes(),
+            io::sink(),
+        );
+
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
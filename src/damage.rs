@@ -0,0 +1,328 @@
+use crate::{
+    ability::Ability,
+    pokemon::{PokeType, Pokemon, PokemonForm, StatKind, StatStages, TypeEffectiveness},
+    pokemove::{MoveEffect, PokeMove},
+    terrain::Terrain,
+    weather::Weather,
+};
+
+/// Everything needed to work out how much damage a single hit deals.
+#[derive(Debug, Clone)]
+pub struct DamageContext<'a> {
+    pub attacker: &'a Pokemon,
+    pub attacker_stages: &'a StatStages,
+    pub defender: &'a Pokemon,
+    pub defender_stages: &'a StatStages,
+    /// The defender's current types, which can differ from its form's (Roost).
+    pub defender_types: &'a [PokeType],
+    pub pokemove: &'a PokeMove,
+    /// The type of the move, as given by `PokeMove::effective_type`.
+    pub poke_type: PokeType,
+    /// The base power of the move, as given by `PokeMove::power_against`.
+    pub power: u32,
+    /// Whether a screen on the defender's side weakens the move, to 0.5x damage in single battles
+    /// and 2/3 in doubles. Critical hits ignore screens.
+    pub screened: bool,
+    pub doubles: bool,
+    /// Whether the move hits more than one target at once, which deals 0.75x damage to each.
+    pub spread: bool,
+    /// Critical hits deal 1.5x damage, ignoring stat stages that would lower it.
+    pub crit: bool,
+    /// The random factor of the damage formula, from 0.85 to 1.0.
+    pub roll: f64,
+    pub weather: Weather,
+    pub terrain: Terrain,
+    pub attacker_grounded: bool,
+    pub defender_grounded: bool,
+}
+
+pub fn calculate_damage(ctx: &DamageContext) -> u32 {
+    let (attack_kind, defense_kind) =
+        match (ctx.pokemove.attacking_stat(), ctx.pokemove.defending_stat()) {
+            (Some(attack_kind), Some(defense_kind)) => (attack_kind, defense_kind),
+            _ => return 0,
+        };
+
+    // Moves the defender's ability absorbs deal no damage, and are handled by the caller
+    let effectiveness =
+        effectiveness_with_ability(ctx.poke_type, ctx.defender_types, &ctx.defender.ability)
+            * ctx
+                .defender
+                .ability
+                .incoming_multiplier(&ctx.poke_type)
+                .max(0.0);
+
+    if effectiveness == 0.0 {
+        return 0;
+    }
+
+    let mut attacker_stages = ctx.attacker_stages.clone();
+    let mut defender_stages = ctx.defender_stages.clone();
+
+    // Critical hits ignore the attacker's drops and the defender's boosts
+    if ctx.crit {
+        attacker_stages.boost(attack_kind, -attacker_stages.get(attack_kind).min(0));
+        defender_stages.boost(defense_kind, -defender_stages.get(defense_kind).max(0));
+    }
+
+    let attack = f64::from(ctx.attacker.effective_stat(attack_kind, &attacker_stages))
+        * ctx.attacker.ability.attack_multiplier(
+            ctx.attacker,
+            &ctx.poke_type,
+            ctx.pokemove.category,
+        )
+        * ctx
+            .attacker
+            .item
+            .as_ref()
+            .map_or(1.0, |item| item.attack_multiplier(ctx.pokemove.category));
+    let defense = f64::from(ctx.defender.effective_stat(defense_kind, &defender_stages));
+
+    let base = base_damage(ctx.attacker.level, ctx.power, attack, defense);
+    // Struggle is typeless, so it never gets a same-type bonus
+    let stab = match ctx.pokemove.effect {
+        Some(MoveEffect::Struggle) => 1.0,
+        _ => ctx.attacker.form.stab_multiplier(&ctx.poke_type),
+    };
+    let screen = match (ctx.screened && !ctx.crit, ctx.doubles) {
+        (false, _) => 1.0,
+        (true, false) => 0.5,
+        (true, true) => 2.0 / 3.0,
+    };
+    let spread = if ctx.spread { 0.75 } else { 1.0 };
+    let crit = if ctx.crit { 1.5 } else { 1.0 };
+    let weather = ctx.weather.damage_multiplier(ctx.poke_type);
+    let terrain =
+        ctx.terrain
+            .damage_multiplier(ctx.poke_type, ctx.attacker_grounded, ctx.defender_grounded);
+
+    let item = ctx
+        .attacker
+        .item
+        .as_ref()
+        .map_or(1.0, |item| item.damage_multiplier());
+
+    let multiplier = stab * effectiveness * screen * spread * crit * weather * terrain * item;
+
+    ((base * ctx.roll * multiplier) as u32).max(1)
+}
+
+/// The damage `pokemove` would deal from `attacker` to `defender` outside of a battle, with no
+/// stat stages, screens, weather or terrain in play.
+///
+/// `roll` is the random factor of the damage formula, from 0.85 to 1.0, which lets callers get
+/// the minimum, maximum or any damage in between.
+pub fn compute_damage(
+    attacker: &Pokemon,
+    defender: &Pokemon,
+    pokemove: &PokeMove,
+    roll: f64,
+) -> u32 {
+    let stages = StatStages::default();
+
+    calculate_damage(&DamageContext {
+        attacker,
+        attacker_stages: &stages,
+        defender,
+        defender_stages: &stages,
+        defender_types: &defender.form.types,
+        pokemove,
+        poke_type: pokemove.effective_type(attacker, Weather::Clear),
+        power: pokemove.power_against(attacker, defender),
+        screened: false,
+        doubles: false,
+        spread: false,
+        crit: false,
+        roll,
+        weather: Weather::Clear,
+        terrain: Terrain::None,
+        attacker_grounded: true,
+        defender_grounded: true,
+    })
+}
+
+/// Whether `pokemove` hits, given a `roll` from 0.0 to 1.0 with lower rolls being luckier.
+///
+/// A move with a 100% hit chance hits even on a roll of exactly 1.0.
+pub fn accuracy_check(
+    pokemove: &PokeMove,
+    attacker_stages: &StatStages,
+    defender_stages: &StatStages,
+    roll: f64,
+) -> bool {
+    let hit_chance = pokemove.hit_chance(attacker_stages, defender_stages);
+    hit_chance >= 1.0 || roll < hit_chance
+}
+
+/// The damage a confused pokemon deals to itself: a typeless 40 power physical hit.
+pub fn confusion_damage(pokemon: &Pokemon, stages: &StatStages) -> u32 {
+    let attack =
+        f64::from(pokemon.computed_stat(StatKind::Attack)) * stages.multiplier(StatKind::Attack);
+    let defense =
+        f64::from(pokemon.computed_stat(StatKind::Defense)) * stages.multiplier(StatKind::Defense);
+
+    (base_damage(pokemon.level, 40, attack, defense) as u32).max(1)
+}
+
+/// The level, power and stat part of the damage formula, before any multipliers.
+fn base_damage(level: u8, power: u32, attack: f64, defense: f64) -> f64 {
+    let level = f64::from(level);
+
+    (((2.0 * level / 5.0 + 2.0) * f64::from(power) * attack / defense.max(1.0)).floor() / 50.0
+        + 2.0)
+        .floor()
+}
+
+/// The combined type multiplier of an attack of type `poke_type` against every type of `defender`.
+pub fn effectiveness_against(poke_type: PokeType, defender: &PokemonForm) -> f64 {
+    effectiveness_against_types(poke_type, &defender.types)
+}
+
+/// The combined type multiplier of an attack of type `poke_type` against all of `types`.
+pub fn effectiveness_against_types(poke_type: PokeType, types: &[PokeType]) -> f64 {
+    types
+        .iter()
+        .map(|t| poke_type.effectiveness_on(t).multiplier())
+        .product()
+}
+
+/// Like `effectiveness_against_types`, but 0 if the defender's `ability` makes it immune to
+/// `poke_type`.
+pub fn effectiveness_with_ability(
+    poke_type: PokeType,
+    types: &[PokeType],
+    ability: &Ability,
+) -> f64 {
+    if ability.grants_immunity(&poke_type) {
+        0.0
+    } else {
+        effectiveness_against_types(poke_type, types)
+    }
+}
+
+/// Buckets a combined multiplier back into the message shown to players.
+pub fn effectiveness_category(multiplier: f64) -> TypeEffectiveness {
+    if multiplier == 0.0 {
+        TypeEffectiveness::NoEffect
+    } else if multiplier < 1.0 {
+        TypeEffectiveness::NotVeryEffective
+    } else if multiplier > 1.0 {
+        TypeEffectiveness::SuperEffective
+    } else {
+        TypeEffectiveness::Regular
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{pokemove::MoveCategory, status::Status, testing};
+
+    #[test]
+    fn sure_hits_hit_on_the_highest_roll() {
+        let stages = StatStages::default();
+        let pokemove = PokeMove::new("Tackle", PokeType::Normal, MoveCategory::Physical, 40, 35);
+
+        assert!(accuracy_check(&pokemove, &stages, &stages, 1.0));
+        assert!(accuracy_check(
+            &pokemove.never_misses(),
+            &stages,
+            &stages,
+            1.0
+        ));
+    }
+
+    #[test]
+    fn inaccurate_moves_miss_at_their_hit_chance() {
+        let stages = StatStages::default();
+        let pokemove = PokeMove::new(
+            "Thunder",
+            PokeType::Electric,
+            MoveCategory::Special,
+            110,
+            10,
+        )
+        .with_accuracy(70);
+
+        assert!(accuracy_check(&pokemove, &stages, &stages, 0.69));
+        assert!(!accuracy_check(&pokemove, &stages, &stages, 0.7));
+    }
+
+    #[test]
+    fn screens_are_weaker_in_doubles() {
+        let attacker = testing::builder("Machamp", &[PokeType::Fighting], &["Tackle"])
+            .level(100)
+            .build();
+        let defender = testing::builder("Snorlax", &[PokeType::Normal], &["Tackle"]).build();
+        let stages = StatStages::default();
+        let pokemove = &attacker.moves[0];
+        let damage = |screened, doubles| {
+            calculate_damage(&DamageContext {
+                attacker: &attacker,
+                attacker_stages: &stages,
+                defender: &defender,
+                defender_stages: &stages,
+                defender_types: &defender.form.types,
+                pokemove,
+                poke_type: pokemove.poke_type,
+                power: pokemove.power,
+                screened,
+                doubles,
+                spread: doubles,
+                crit: false,
+                roll: 1.0,
+                weather: Weather::Clear,
+                terrain: Terrain::None,
+                attacker_grounded: true,
+                defender_grounded: true,
+            })
+        };
+
+        assert_eq!(damage(true, false), damage(false, false) / 2);
+        assert_eq!(damage(true, true), damage(false, true) * 2 / 3);
+    }
+
+    #[test]
+    fn burns_only_weaken_physical_moves() {
+        let healthy =
+            testing::builder("Charizard", &[PokeType::Fire], &["Tackle", "Flamethrower"]).build();
+        let burned = Pokemon {
+            status: Status::Burn,
+            ..healthy.clone()
+        };
+        let defender = testing::builder("Snorlax", &[PokeType::Normal], &[]).build();
+        let damage = |attacker: &Pokemon, idx: usize| {
+            compute_damage(attacker, &defender, &attacker.moves[idx], 1.0)
+        };
+
+        // The burn halves Attack rather than the damage, so the damage isn't exactly halved
+        assert_eq!(damage(&healthy, 0), 19);
+        assert_eq!(damage(&burned, 0), 10);
+        assert_eq!(damage(&burned, 1), damage(&healthy, 1));
+    }
+
+    #[test]
+    fn computes_a_hand_calculated_example() {
+        // Both sides have 100 Attack and Defense, so the base damage of a level 50 Tackle is
+        // floor(floor(22 * 40 * 100 / 100) / 50 + 2) = 19, then 1.5x for the same-type bonus
+        let attacker = testing::builder("Snorlax", &[PokeType::Normal], &["Tackle"]).build();
+        let defender = testing::builder("Charizard", &[PokeType::Fire], &[]).build();
+        let tackle = &attacker.moves[0];
+
+        assert_eq!(attacker.computed_stat(StatKind::Attack), 100);
+        assert_eq!(defender.computed_stat(StatKind::Defense), 100);
+        assert_eq!(compute_damage(&attacker, &defender, tackle, 1.0), 28);
+        assert_eq!(compute_damage(&attacker, &defender, tackle, 0.85), 24);
+    }
+
+    #[test]
+    fn immune_defenders_take_no_damage() {
+        let attacker = testing::builder("Snorlax", &[PokeType::Normal], &["Tackle"]).build();
+        let defender = testing::builder("Gengar", &[PokeType::Ghost], &[]).build();
+
+        assert_eq!(
+            compute_damage(&attacker, &defender, &attacker.moves[0], 1.0),
+            0
+        );
+    }
+}
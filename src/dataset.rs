@@ -0,0 +1,329 @@
+//! Loading species data from JSON.
+//!
+//! A dataset is a list of species, each with its forms:
+//!
+//! ```json
+//! [{
+//!     "national_dex_no": 1,
+//!     "name": "Bulbasaur",
+//!     "forms": [{
+//!         "types": ["Grass", "Poison"],
+//!         "female_eighths": 1,
+//!         "base_stats": {
+//!             "hp": 45, "attack": 49, "defense": 49,
+//!             "special_attack": 65, "special_defense": 65, "speed": 45
+//!         },
+//!         "weight_hg": 69,
+//!         "evolutions": [{ "species": "Ivysaur", "condition": { "Level": 16 } }]
+//!     }]
+//! }]
+//! ```
+//!
+//! Forms without a `name` are the base form, and forms without `female_eighths` are genderless.
+//! Evolutions refer to a species by name and optionally to one of its forms by name.
+
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+    rc::Rc,
+};
+
+use serde::Deserialize;
+
+use crate::pokemon::{
+    AllowedGenders, Evolution, EvolutionCondition, PokeType, PokemonForm, PokemonSpecies, Stats,
+};
+
+#[derive(Deserialize)]
+struct SpeciesData {
+    national_dex_no: u32,
+    name: String,
+    forms: Vec<FormData>,
+}
+
+#[derive(Deserialize)]
+struct FormData {
+    #[serde(default)]
+    name: Option<String>,
+    types: Vec<PokeType>,
+    #[serde(default)]
+    female_eighths: Option<u8>,
+    base_stats: Stats,
+    weight_hg: u32,
+    #[serde(default)]
+    evolutions: Vec<EvolutionData>,
+}
+
+#[derive(Deserialize)]
+struct EvolutionData {
+    species: String,
+    #[serde(default)]
+    form: Option<String>,
+    condition: EvolutionCondition,
+}
+
+/// A species name and optional form name, identifying a form within the dataset.
+type FormKey = (String, Option<String>);
+
+/// Parses a JSON dataset into species, ready to be used with `Pokemon::resolve_form`.
+///
+/// The species each form points back at carries no forms of its own, since forms and their
+/// species can't own each other.
+pub fn load_species(json: &str) -> Result<Vec<Rc<PokemonSpecies>>, DatasetError> {
+    let data: Vec<SpeciesData> = serde_json::from_str(json).map_err(DatasetError::Json)?;
+
+    let mut raw_forms = HashMap::new();
+    for species in &data {
+        let shell = Rc::new(PokemonSpecies {
+            national_dex_no: species.national_dex_no,
+            name: species.name.clone(),
+            forms: Vec::new(),
+        });
+
+        for form in &species.forms {
+            let key = (species.name.clone(), form.name.clone());
+            raw_forms.insert(key, (shell.clone(), form));
+        }
+    }
+
+    let mut built = HashMap::new();
+    let mut species = Vec::with_capacity(data.len());
+    for s in &data {
+        let mut forms = Vec::with_capacity(s.forms.len());
+        for form in &s.forms {
+            let key = (s.name.clone(), form.name.clone());
+            forms.push(build_form(&key, &raw_forms, &mut built, &mut Vec::new())?);
+        }
+
+        species.push(Rc::new(PokemonSpecies {
+            national_dex_no: s.national_dex_no,
+            name: s.name.clone(),
+            forms,
+        }));
+    }
+
+    Ok(species)
+}
+
+/// Builds the form at `key`, building the forms it evolves into first since it points at them.
+///
+/// `path` holds the forms being built further up, to catch evolutions that loop back.
+fn build_form(
+    key: &FormKey,
+    raw_forms: &HashMap<FormKey, (Rc<PokemonSpecies>, &FormData)>,
+    built: &mut HashMap<FormKey, Rc<PokemonForm>>,
+    path: &mut Vec<FormKey>,
+) -> Result<Rc<PokemonForm>, DatasetError> {
+    if let Some(form) = built.get(key) {
+        return Ok(form.clone());
+    }
+
+    if path.contains(key) {
+        return Err(DatasetError::EvolutionCycle(display_key(key)));
+    }
+
+    let (species, data) = &raw_forms[key];
+
+    if let Some(female_eighths) = data.female_eighths.filter(|&f| f > 8) {
+        return Err(DatasetError::InvalidGenderRatio {
+            form: display_key(key),
+            female_eighths,
+        });
+    }
+
+    path.push(key.clone());
+    let mut evolutions = Vec::with_capacity(data.evolutions.len());
+    for evolution in &data.evolutions {
+        let into_key = (evolution.species.clone(), evolution.form.clone());
+
+        if !raw_forms.contains_key(&into_key) {
+            return Err(DatasetError::UnknownEvolution {
+                from: display_key(key),
+                into: display_key(&into_key),
+            });
+        }
+
+        evolutions.push(Evolution {
+            into: build_form(&into_key, raw_forms, built, path)?,
+            condition: evolution.condition.clone(),
+        });
+    }
+    path.pop();
+
+    let form = Rc::new(PokemonForm {
+        species: species.clone(),
+        name: data.name.clone(),
+        types: data.types.clone(),
+        genders: data.female_eighths.map_or(
+            AllowedGenders::NoGender,
+            AllowedGenders::from_female_eighths,
+        ),
+        base_stats: data.base_stats.clone(),
+        weight_hg: data.weight_hg,
+        evolutions,
+    });
+
+    built.insert(key.clone(), form.clone());
+    Ok(form)
+}
+
+fn display_key((species, form): &FormKey) -> String {
+    match form {
+        Some(form) => format!("{} - {}", species, form),
+        None => species.clone(),
+    }
+}
+
+#[derive(Debug)]
+pub enum DatasetError {
+    Json(serde_json::Error),
+    /// A form evolves into a species or form that isn't in the dataset.
+    UnknownEvolution {
+        from: String,
+        into: String,
+    },
+    /// A form eventually evolves back into itself.
+    EvolutionCycle(String),
+    /// More than 8 out of 8 pokemon of a form would be female.
+    InvalidGenderRatio {
+        form: String,
+        female_eighths: u8,
+    },
+}
+
+impl Display for DatasetError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            DatasetError::Json(e) => write!(f, "Invalid dataset: {}", e),
+            DatasetError::UnknownEvolution { from, into } => {
+                write!(f, "{} evolves into unknown form {}", from, into)
+            }
+            DatasetError::EvolutionCycle(form) => write!(f, "{} evolves into itself", form),
+            DatasetError::InvalidGenderRatio {
+                form,
+                female_eighths,
+            } => write!(f, "{} can't be {}/8 female", form, female_eighths),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pokemon::Gender;
+
+    const STARTERS: &str = r#"[
+        {
+            "national_dex_no": 1,
+            "name": "Bulbasaur",
+            "forms": [{
+                "types": ["Grass", "Poison"],
+                "female_eighths": 1,
+                "base_stats": {
+                    "hp": 45, "attack": 49, "defense": 49,
+                    "special_attack": 65, "special_defense": 65, "speed": 45
+                },
+                "weight_hg": 69,
+                "evolutions": [{ "species": "Ivysaur", "condition": { "Level": 16 } }]
+            }]
+        },
+        {
+            "national_dex_no": 2,
+            "name": "Ivysaur",
+            "forms": [{
+                "types": ["Grass", "Poison"],
+                "female_eighths": 1,
+                "base_stats": {
+                    "hp": 60, "attack": 62, "defense": 63,
+                    "special_attack": 80, "special_defense": 80, "speed": 60
+                },
+                "weight_hg": 130,
+                "evolutions": [{ "species": "Venusaur", "condition": { "Level": 32 } }]
+            }]
+        },
+        {
+            "national_dex_no": 3,
+            "name": "Venusaur",
+            "forms": [{
+                "types": ["Grass", "Poison"],
+                "female_eighths": 1,
+                "base_stats": {
+                    "hp": 80, "attack": 82, "defense": 83,
+                    "special_attack": 100, "special_defense": 100, "speed": 80
+                },
+                "weight_hg": 1000
+            }]
+        }
+    ]"#;
+
+    #[test]
+    fn loads_forms() {
+        let species = load_species(STARTERS).unwrap();
+        let bulbasaur = species[0].form_by_name(None).unwrap();
+
+        assert_eq!(species.len(), 3);
+        assert_eq!(bulbasaur.species.name, "Bulbasaur");
+        assert_eq!(bulbasaur.bst(), 318);
+        assert_eq!(bulbasaur.weight_hg, 69);
+        assert_eq!(
+            bulbasaur.genders,
+            AllowedGenders::MaleOrFemale { female_eighths: 1 }
+        );
+    }
+
+    #[test]
+    fn missing_gender_ratio_is_genderless() {
+        let json = STARTERS.replace(r#""female_eighths": 1,"#, "");
+        let species = load_species(&json).unwrap();
+
+        assert_eq!(species[2].forms[0].genders.as_slice(), &[Gender::None]);
+    }
+
+    #[test]
+    fn evolutions_point_at_the_loaded_forms() {
+        let species = load_species(STARTERS).unwrap();
+        let ivysaur = species[1].form_by_name(None).unwrap();
+        let venusaur = ivysaur.evolve(&EvolutionCondition::Level(32)).unwrap();
+
+        assert!(ivysaur.can_evolve());
+        assert!(!venusaur.can_evolve());
+        assert!(Rc::ptr_eq(&venusaur, &species[2].forms[0]));
+    }
+
+    #[test]
+    fn rejects_unknown_evolutions() {
+        let json = STARTERS.replace(r#""species": "Venusaur""#, r#""species": "Missingno""#);
+
+        assert!(matches!(
+            load_species(&json),
+            Err(DatasetError::UnknownEvolution { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_evolution_cycles() {
+        let json = STARTERS.replace(
+            r#""weight_hg": 1000"#,
+            r#""weight_hg": 1000,
+                "evolutions": [{ "species": "Bulbasaur", "condition": "Trade" }]"#,
+        );
+
+        assert!(matches!(
+            load_species(&json),
+            Err(DatasetError::EvolutionCycle(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_impossible_gender_ratios() {
+        let json = STARTERS.replace(r#""female_eighths": 1,"#, r#""female_eighths": 9,"#);
+
+        assert!(matches!(
+            load_species(&json),
+            Err(DatasetError::InvalidGenderRatio {
+                female_eighths: 9,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,854 @@
+//! Double battles, where each player has two pokemon in battle at once.
+//!
+//! This mirrors [`single`](crate::single) but only covers the core of a battle so far: moves hit
+//! the slots they target, screens go up and abilities and items react to switching in and to
+//! hits, but weather, terrain, most other status moves and end of turn effects have no effect
+//! yet.
+
+use std::{cmp::Reverse, fmt};
+
+use arrayvec::ArrayVec;
+
+use crate::{
+    battle::Battle,
+    log::LogEvent,
+    pokemon::{PokeType, Pokemon, StatKind, StatStages, Team, TypeEffectiveness},
+    pokemove::{MoveCategory, MoveEffect, MoveTarget, PokeMove},
+    rules::{BattleRules, RulesViolation},
+    single::{Player, SideConditions},
+    state::{self, DecisionBuilder, PlayerBase, PlayerStateBase, RngBehavior, StateBase},
+    status::Status,
+    terrain::Terrain,
+    weather::Weather,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// How many pokemon each player has in battle at once.
+pub const ACTIVE_SLOTS: usize = 2;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct State {
+    player_1: PlayerState,
+    player_2: PlayerState,
+    pub rules: BattleRules,
+    turn: u32,
+    winner: Option<Player>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    log_events: Vec<LogEvent>,
+}
+
+impl StateBase for State {
+    type Player = Player;
+    type PlayerState = PlayerState;
+
+    fn player(&self, player: Player) -> &PlayerState {
+        match player {
+            Player::Player1 => &self.player_1,
+            Player::Player2 => &self.player_2,
+        }
+    }
+
+    fn player_mut(&mut self, player: Player) -> &mut PlayerState {
+        match player {
+            Player::Player1 => &mut self.player_1,
+            Player::Player2 => &mut self.player_2,
+        }
+    }
+
+    fn drain_log(&mut self) -> Vec<LogEvent> {
+        self.log_events.drain(..).collect()
+    }
+
+    fn rng_behavior(&self) -> RngBehavior {
+        self.rules.rng_behavior
+    }
+}
+
+impl Battle for State {
+    type Position = Slot;
+
+    const DOUBLES: bool = true;
+
+    fn side_of(slot: Slot) -> Player {
+        slot.player
+    }
+
+    fn side(&self, player: Player) -> &SideConditions {
+        &self.player(player).side
+    }
+
+    fn side_mut(&mut self, player: Player) -> &mut SideConditions {
+        &mut self.player_mut(player).side
+    }
+
+    fn rules(&self) -> &BattleRules {
+        &self.rules
+    }
+
+    fn turn(&self) -> u32 {
+        self.turn
+    }
+
+    fn team(&self, player: Player) -> &Team {
+        &self.player(player).team
+    }
+
+    fn pokemon(&self, slot: Slot) -> Option<&Pokemon> {
+        self.player(slot.player).active_pokemon(slot.index)
+    }
+
+    fn pokemon_mut(&mut self, slot: Slot) -> Option<&mut Pokemon> {
+        self.player_mut(slot.player).active_pokemon_mut(slot.index)
+    }
+
+    fn opponents(&self, slot: Slot) -> Vec<Slot> {
+        Slot::all()
+            .filter(|&other| other.player != slot.player && self.standing_pokemon(other).is_some())
+            .collect()
+    }
+
+    fn boost(&mut self, slot: Slot, kind: StatKind, stages: i8) -> i8 {
+        let player_state = self.player_mut(slot.player);
+        let pokemon = player_state.active_pokemon(slot.index).unwrap();
+        let stages = pokemon.ability.modify_boost(stages);
+        let name = pokemon.to_string();
+
+        let changed = player_state.stat_stages[slot.index].boost(kind, stages);
+
+        self.log(LogEvent::StatChanged {
+            pokemon: name,
+            stat: kind,
+            stages: changed,
+        });
+
+        changed
+    }
+
+    fn apply_status(&mut self, slot: Slot, status: Status) -> bool {
+        let pokemon = match self.player_mut(slot.player).active_pokemon_mut(slot.index) {
+            Some(pokemon) if !pokemon.is_fainted() && pokemon.status == Status::Healthy => pokemon,
+            _ => return false,
+        };
+
+        if let Status::Sleep { .. } = status {
+            if pokemon.ability.prevents_sleep() {
+                return false;
+            }
+        }
+
+        pokemon.status = status;
+
+        let pokemon = pokemon.to_string();
+        self.log(LogEvent::StatusApplied { pokemon, status });
+
+        true
+    }
+
+    fn stat_stages(&self, slot: Slot) -> &StatStages {
+        &self.player(slot.player).stat_stages[slot.index]
+    }
+
+    fn active_types(&self, slot: Slot) -> Vec<PokeType> {
+        self.pokemon(slot).unwrap().form.types.clone()
+    }
+
+    fn weather(&self) -> Weather {
+        Weather::Clear
+    }
+
+    fn terrain(&self) -> Terrain {
+        Terrain::None
+    }
+
+    fn speed(&self, slot: Slot) -> u32 {
+        let pokemon = self
+            .pokemon(slot)
+            .unwrap_or_else(|| panic!("No active pokemon in {}", slot));
+
+        pokemon.effective_speed(
+            self.stat_stages(slot),
+            pokemon.item.as_ref(),
+            self.weather(),
+        )
+    }
+
+    fn deal_indirect_damage(&mut self, slot: Slot, amount: u32) {
+        let pokemon = self
+            .player_mut(slot.player)
+            .active_pokemon_mut(slot.index)
+            .unwrap();
+        let ability = pokemon.ability;
+        let amount = pokemon.take_indirect_damage(amount, ability);
+
+        if amount > 0 {
+            self.log_damage(slot, amount, TypeEffectiveness::Regular);
+        }
+    }
+
+    fn log(&mut self, event: LogEvent) {
+        self.log_events.push(event);
+    }
+}
+
+/// A place on the field for one of a player's active pokemon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Slot {
+    pub player: Player,
+    /// Which of the player's active pokemon, from 0 to `ACTIVE_SLOTS - 1`.
+    pub index: usize,
+}
+
+impl Slot {
+    pub fn new(player: Player, index: usize) -> Self {
+        Self { player, index }
+    }
+
+    /// The other slot on the same side of the field.
+    pub fn ally(self) -> Slot {
+        Slot::new(self.player, ACTIVE_SLOTS - 1 - self.index)
+    }
+
+    /// Every slot on the field, Player1's first.
+    pub fn all() -> impl Iterator<Item = Slot> {
+        Player::values()
+            .iter()
+            .flat_map(|&player| (0..ACTIVE_SLOTS).map(move |index| Slot::new(player, index)))
+    }
+}
+
+impl fmt::Display for Slot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} slot {}", self.player, self.index + 1)
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PlayerState {
+    /// The team index of the pokemon in each slot, or `None` if the slot is empty.
+    pub active_pokemon_idx: [Option<usize>; ACTIVE_SLOTS],
+    pub turn_actions: [Option<Action>; ACTIVE_SLOTS],
+    pub team: Team,
+    pub stat_stages: [StatStages; ACTIVE_SLOTS],
+    pub side: SideConditions,
+}
+
+impl PlayerState {
+    fn new(team: Team) -> Self {
+        Self {
+            team,
+            active_pokemon_idx: [None; ACTIVE_SLOTS],
+            turn_actions: [None; ACTIVE_SLOTS],
+            stat_stages: Default::default(),
+            side: SideConditions::default(),
+        }
+    }
+
+    /// The pokemon in the given slot, or `None` if it's empty.
+    pub fn active_pokemon(&self, index: usize) -> Option<&Pokemon> {
+        self.active_pokemon_idx[index].map(|idx| &self.team[idx])
+    }
+
+    /// The pokemon in the given slot, or `None` if it's empty.
+    pub fn active_pokemon_mut(&mut self, index: usize) -> Option<&mut Pokemon> {
+        let team = &mut self.team;
+        self.active_pokemon_idx[index].map(move |idx| &mut team[idx])
+    }
+
+    /// Whether the pokemon at `idx` in the team is in either slot.
+    pub fn is_active(&self, idx: usize) -> bool {
+        self.active_pokemon_idx.contains(&Some(idx))
+    }
+
+    /// The team indices of pokemon that could be sent into a slot.
+    fn bench(&self) -> impl Iterator<Item = (usize, &Pokemon)> {
+        self.team
+            .iter()
+            .enumerate()
+            .filter(move |&(i, p)| !self.is_active(i) && !p.is_fainted())
+    }
+}
+
+impl PlayerStateBase for PlayerState {
+    fn describe_changes(&self, before: &Self) -> Vec<String> {
+        let mut changes = Vec::new();
+
+        for (old, new) in before.team.iter().zip(&self.team) {
+            if old.current_hp != new.current_hp {
+                changes.push(format!(
+                    "{}: HP {} \u{2192} {}",
+                    new, old.current_hp, new.current_hp
+                ));
+            }
+        }
+
+        for index in 0..ACTIVE_SLOTS {
+            if before.active_pokemon_idx[index] != self.active_pokemon_idx[index] {
+                if let Some(pokemon) = self.active_pokemon(index) {
+                    changes.push(format!("{}: switched in", pokemon));
+                }
+            }
+        }
+
+        changes
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Action {
+    /// Moves that only affect the user, and spread moves, target its own slot.
+    UsePokeMove {
+        move_idx: usize,
+        target: Slot,
+    },
+    SwitchPokemon(usize),
+    /// Only offered when none of the pokemon's moves can be used.
+    Struggle {
+        target: Slot,
+    },
+}
+
+pub type Node = state::Node<State>;
+
+impl State {
+    /// Fails if either team breaks the default rules.
+    pub fn start(player_1_team: Team, player_2_team: Team) -> Result<Node, RulesViolation> {
+        Self::start_with_rules(player_1_team, player_2_team, BattleRules::default())
+    }
+
+    /// Fails if either team breaks `rules`.
+    pub fn start_with_rules(
+        player_1_team: Team,
+        player_2_team: Team,
+        rules: BattleRules,
+    ) -> Result<Node, RulesViolation> {
+        Ok(Self::new(player_1_team, player_2_team, rules)?
+            .choose_starting_pokemon()
+            .then(Self::initial_etb)
+            .then(Self::main_turn))
+    }
+
+    /// Fails if either team breaks `rules`.
+    fn new(
+        mut player_1_team: Team,
+        mut player_2_team: Team,
+        rules: BattleRules,
+    ) -> Result<Self, RulesViolation> {
+        for team in &[&player_1_team, &player_2_team] {
+            rules.check_team(team)?;
+        }
+
+        for pokemon in player_1_team.iter_mut().chain(player_2_team.iter_mut()) {
+            rules.prepare(pokemon);
+            pokemon.reset_for_battle();
+        }
+
+        Ok(State {
+            player_1: PlayerState::new(player_1_team),
+            player_2: PlayerState::new(player_2_team),
+            rules,
+            turn: 0,
+            winner: None,
+            log_events: Vec::new(),
+        })
+    }
+
+    /// Has each player pick a pokemon for each of their slots in turn. A player with a single
+    /// pokemon leaves their second slot empty.
+    fn choose_starting_pokemon(self) -> Node {
+        self.fold(Slot::all(), |state, slot| {
+            let choices = state
+                .player(slot.player)
+                .bench()
+                .map(|(i, p)| (p.to_string(), i));
+
+            DecisionBuilder::new("Choose your active pokemon", slot.player)
+                .named_choices(choices)
+                .build(state, move |mut state, idx| {
+                    state.player_mut(slot.player).active_pokemon_idx[slot.index] = Some(idx);
+                    state.log_switch_in(slot);
+                    Node::pending(state)
+                })
+        })
+    }
+
+    fn initial_etb(self) -> Node {
+        let leads = self.filled_slots();
+        self.in_speed_order(leads, Self::pokemon_etb)
+    }
+
+    fn main_turn(mut self) -> Node {
+        if self.turn_limit_reached() {
+            self.winner = self.hp_tiebreak();
+            return Node::end(self);
+        }
+
+        self.turn += 1;
+        self.choose_actions()
+            .then(Self::execute_actions)
+            .then(Self::end_turn)
+            .then(Self::handle_faints)
+            .then(Self::main_turn)
+    }
+
+    /// The winner of a finished battle, or `None` if it's still going or ended in a tie.
+    pub fn winner(&self) -> Option<Player> {
+        self.winner
+    }
+
+    /// The pokemon in `slot`, if there is one and it's still standing.
+    pub fn standing_pokemon(&self, slot: Slot) -> Option<&Pokemon> {
+        self.player(slot.player)
+            .active_pokemon(slot.index)
+            .filter(|pokemon| !pokemon.is_fainted())
+    }
+
+    /// Ends the battle if a side has run out of pokemon, and otherwise has each fainted pokemon
+    /// replaced from the bench. Slots nobody is left to fill are emptied.
+    fn handle_faints(mut self) -> Node {
+        if Player::values().iter().any(|&player| self.has_lost(player)) {
+            self.winner = self.check_win();
+            return Node::end(self);
+        }
+
+        self.fold(Slot::all(), |state, slot| {
+            let player_state = state.player(slot.player);
+
+            match player_state.active_pokemon(slot.index) {
+                Some(pokemon) if pokemon.is_fainted() => {}
+                _ => return Node::pending(state),
+            }
+
+            let switch_choices = player_state.bench().map(|(i, p)| (p.to_string(), i));
+
+            DecisionBuilder::new("Choose a replacement", slot.player)
+                .named_choices(switch_choices)
+                .build_or(
+                    state,
+                    move |state, idx| state.switch_in(slot, idx),
+                    move |mut state| {
+                        state.player_mut(slot.player).active_pokemon_idx[slot.index] = None;
+                        Node::pending(state)
+                    },
+                )
+        })
+    }
+
+    fn choose_actions(self) -> Node {
+        self.fold(Slot::all(), |state, slot| {
+            let pokemon = match state.standing_pokemon(slot) {
+                Some(pokemon) => pokemon,
+                None => return Node::pending(state),
+            };
+
+            let targets: ArrayVec<Slot, 3> = Slot::all()
+                .filter(|&target| target != slot && state.standing_pokemon(target).is_some())
+                .collect();
+            let target_name = |target: Slot| state.standing_pokemon(target).unwrap().to_string();
+
+            let mut builder =
+                DecisionBuilder::new(format!("Choose an action for {}", pokemon), slot.player);
+            let mut can_move = false;
+
+            for (move_idx, pokemove) in pokemon.moves.iter().enumerate() {
+                if pokemove.current_pp == 0 {
+                    builder = builder.named_choice_disabled_with_meta(
+                        pokemove.to_string(),
+                        "No PP left".to_owned(),
+                        pokemove.clone(),
+                    );
+                } else if pokemove.target == MoveTarget::Opponent {
+                    can_move = true;
+
+                    for &target in &targets {
+                        builder = builder.named_choice_with_meta(
+                            format!("{} \u{2192} {}", pokemove, target_name(target)),
+                            Action::UsePokeMove { move_idx, target },
+                            pokemove.clone(),
+                        );
+                    }
+                } else {
+                    can_move = true;
+                    builder = builder.named_choice_with_meta(
+                        pokemove.to_string(),
+                        Action::UsePokeMove {
+                            move_idx,
+                            target: slot,
+                        },
+                        pokemove.clone(),
+                    );
+                }
+            }
+
+            if !can_move {
+                builder = builder.named_choices(targets.iter().map(|&target| {
+                    (
+                        format!("Struggle \u{2192} {}", target_name(target)),
+                        Action::Struggle { target },
+                    )
+                }));
+            }
+
+            // The ally may already be switching to one of the bench pokemon
+            let ally_switch = match state.player(slot.player).turn_actions[slot.ally().index] {
+                Some(Action::SwitchPokemon(idx)) => Some(idx),
+                _ => None,
+            };
+
+            let switch_choices = state
+                .player(slot.player)
+                .bench()
+                .filter(|&(i, _)| Some(i) != ally_switch)
+                .map(|(i, p)| (p.to_string(), Action::SwitchPokemon(i)));
+
+            builder
+                .named_choices(switch_choices)
+                .build(state, move |mut state, choice| {
+                    state.player_mut(slot.player).turn_actions[slot.index] = Some(choice);
+                    Node::pending(state)
+                })
+        })
+    }
+
+    /// Carries out the chosen actions in the order given by `action_key`.
+    fn execute_actions(self) -> Node {
+        let acting = Slot::all()
+            .filter(|&slot| self.player(slot.player).turn_actions[slot.index].is_some())
+            .collect();
+
+        self.in_order_by_key(acting, Self::action_key, Self::execute_action)
+    }
+
+    fn end_turn(mut self) -> Node {
+        for &player in Player::values() {
+            self.tick_screens(player);
+        }
+
+        Node::pending(self)
+    }
+
+    fn execute_action(mut self, slot: Slot) -> Node {
+        match self.player_mut(slot.player).turn_actions[slot.index].take() {
+            Some(Action::SwitchPokemon(idx)) => self.switch_in(slot, idx),
+            Some(Action::UsePokeMove { move_idx, target }) => {
+                self.use_move(slot, Some(move_idx), target)
+            }
+            Some(Action::Struggle { target }) => self.use_move(slot, None, target),
+            None => Node::pending(self),
+        }
+    }
+
+    /// Sends the pokemon at `idx` in the team into `slot`.
+    fn switch_in(self, slot: Slot, idx: usize) -> Node {
+        let node = if self.standing_pokemon(slot).is_some() {
+            self.run_handlers(slot, move |handler, state| {
+                handler.on_switch_out(state, slot)
+            })
+        } else {
+            Node::pending(self)
+        };
+
+        node.then(move |mut state| {
+            let player_state = state.player_mut(slot.player);
+            player_state.stat_stages[slot.index] = StatStages::default();
+            player_state.active_pokemon_idx[slot.index] = Some(idx);
+
+            state.log_switch_in(slot);
+            state.pokemon_etb(slot)
+        })
+    }
+
+    /// Uses the move at `move_idx` of the pokemon in `slot`, or Struggle if it's `None`.
+    fn use_move(mut self, slot: Slot, move_idx: Option<usize>, target: Slot) -> Node {
+        let pokemon = match self.player_mut(slot.player).active_pokemon_mut(slot.index) {
+            Some(pokemon) if !pokemon.is_fainted() => pokemon,
+            _ => return Node::pending(self),
+        };
+
+        let pokemove = match move_idx {
+            Some(move_idx) => {
+                pokemon.moves[move_idx].use_pp();
+                pokemon.moves[move_idx].clone()
+            }
+            None => PokeMove::struggle(),
+        };
+
+        let event = LogEvent::MoveUsed {
+            pokemon: pokemon.to_string(),
+            pokemove: pokemove.to_string(),
+        };
+        self.log(event);
+
+        if pokemove.category == MoveCategory::Status {
+            match pokemove.effect {
+                Some(MoveEffect::SelfBoost(kind, stages)) => {
+                    self.boost(slot, kind, stages);
+                }
+                Some(MoveEffect::Screen(screen)) => self.set_screen(slot, screen),
+                _ => self.log(LogEvent::MoveFailed),
+            }
+
+            return Node::pending(self);
+        }
+
+        let targets = self.move_targets(slot, pokemove.target, target);
+
+        if targets.is_empty() {
+            self.log(LogEvent::MoveFailed);
+            return Node::pending(self);
+        }
+
+        let spread = targets.len() > 1;
+        self.strike_all(slot, pokemove, targets, spread)
+    }
+
+    /// The slots a move with `move_target` aimed at `target` by the pokemon in `slot` hits.
+    fn move_targets(&self, slot: Slot, move_target: MoveTarget, target: Slot) -> ArrayVec<Slot, 3> {
+        match move_target {
+            MoveTarget::Opponent => self.retarget(slot, target).into_iter().collect(),
+            MoveTarget::SelfTarget => Some(slot).into_iter().collect(),
+            MoveTarget::AllOpponents => Slot::all()
+                .filter(|&other| {
+                    other.player != slot.player && self.standing_pokemon(other).is_some()
+                })
+                .collect(),
+            MoveTarget::AllAdjacent => Slot::all()
+                .filter(|&other| other != slot && self.standing_pokemon(other).is_some())
+                .collect(),
+        }
+    }
+
+    /// Strikes each of `targets` in turn, rolling accuracy separately for each.
+    fn strike_all(
+        self,
+        slot: Slot,
+        pokemove: PokeMove,
+        mut targets: ArrayVec<Slot, 3>,
+        spread: bool,
+    ) -> Node {
+        if targets.is_empty() {
+            return Node::pending(self);
+        }
+
+        let target = targets.remove(0);
+
+        self.strike(slot, pokemove.clone(), target, spread)
+            .then(move |state| state.strike_all(slot, pokemove, targets, spread))
+    }
+
+    fn strike(self, slot: Slot, pokemove: PokeMove, target: Slot, spread: bool) -> Node {
+        let hit_chance_move = pokemove.clone();
+
+        self.check_accuracy(slot, target, &hit_chance_move, move |state| {
+            state.roll_hit(true, move |state, crit, roll| {
+                state.hit(slot, pokemove, target, spread, crit, roll)
+            })
+        })
+    }
+
+    /// The slot a move aimed at `target` ends up hitting. A move aimed at an opponent that's no
+    /// longer standing goes to the other opponent instead.
+    fn retarget(&self, user: Slot, target: Slot) -> Option<Slot> {
+        if self.standing_pokemon(target).is_some() {
+            return Some(target);
+        }
+
+        Some(target.ally())
+            .filter(|&other| other.player != user.player && self.standing_pokemon(other).is_some())
+    }
+
+    fn hit(
+        mut self,
+        slot: Slot,
+        pokemove: PokeMove,
+        target: Slot,
+        spread: bool,
+        crit: bool,
+        roll: f64,
+    ) -> Node {
+        if crit {
+            self.log(LogEvent::Crit);
+        }
+
+        let (amount, effectiveness) = self.move_damage(slot, target, &pokemove, spread, crit, roll);
+
+        let defender = self
+            .player_mut(target.player)
+            .active_pokemon_mut(target.index)
+            .unwrap();
+        let amount = defender.take_damage(amount);
+        self.log_damage(target, amount, effectiveness);
+
+        if let Some(MoveEffect::Struggle) = pokemove.effect {
+            self.struggle_recoil(slot);
+        }
+
+        if amount == 0 {
+            return Node::pending(self);
+        }
+
+        let dealt_move = pokemove.clone();
+
+        self.run_handlers(slot, move |handler, state| {
+            handler.on_damage_dealt(state, slot, target, &dealt_move, amount)
+        })
+        .then(move |state| {
+            state.run_handlers(target, move |handler, state| {
+                handler.on_damage_taken(state, target, slot, &pokemove, amount)
+            })
+        })
+    }
+
+    fn log_damage(&mut self, target: Slot, amount: u32, effectiveness: TypeEffectiveness) {
+        let defender = self.pokemon(target).unwrap();
+        let pokemon = defender.to_string();
+        let fainted = amount > 0 && defender.is_fainted();
+
+        self.log(LogEvent::Damage {
+            pokemon: pokemon.clone(),
+            amount,
+            effectiveness,
+        });
+
+        if fainted {
+            self.log(LogEvent::Fainted { pokemon });
+        }
+    }
+
+    /// Sorts actions so that switches go first, then moves in descending order of priority and
+    /// then effective speed.
+    fn action_key(&self, slot: Slot) -> (bool, Reverse<Option<i8>>, Reverse<u32>) {
+        let player_state = self.player(slot.player);
+        let pokemon = player_state.active_pokemon(slot.index).unwrap();
+        let priority = match player_state.turn_actions[slot.index] {
+            Some(Action::UsePokeMove { move_idx, .. }) => Some(pokemon.moves[move_idx].priority),
+            Some(Action::Struggle { .. }) => Some(PokeMove::struggle().priority),
+            Some(Action::SwitchPokemon(_)) | None => None,
+        };
+
+        (
+            priority.is_some(),
+            Reverse(priority),
+            Reverse(self.speed(slot)),
+        )
+    }
+
+    fn log_switch_in(&mut self, slot: Slot) {
+        let pokemon = self
+            .player(slot.player)
+            .active_pokemon(slot.index)
+            .unwrap()
+            .to_string();
+
+        self.log(LogEvent::SwitchedIn {
+            player: slot.player.to_string(),
+            pokemon,
+        });
+    }
+
+    /// Runs the switch-in and then the ETB hooks of the pokemon in `slot`.
+    fn pokemon_etb(self, slot: Slot) -> Node {
+        self.run_handlers(slot, move |handler, state| {
+            handler.on_switch_in(state, slot)
+        })
+        .then(move |state| {
+            state.run_handlers(slot, move |handler, state| handler.on_etb(state, slot))
+        })
+    }
+
+    /// The slots with a pokemon in them, fainted or not.
+    fn filled_slots(&self) -> Vec<Slot> {
+        Slot::all()
+            .filter(|&slot| self.pokemon(slot).is_some())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{state::Branches, testing};
+
+    fn team(names: &[&str]) -> Team {
+        testing::team(
+            names
+                .iter()
+                .map(|name| testing::builder(name, &[PokeType::Normal], &["Tackle"]).build())
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn each_player_picks_two_leads() {
+        let mut node = State::start(
+            team(&["Rattata", "Pidgey", "Meowth"]),
+            team(&["Eevee", "Ditto", "Snorlax"]),
+        )
+        .unwrap();
+        let mut pickers = Vec::new();
+
+        while let Branches::Decision(d) = node.branches() {
+            if d.name().to_string() != "Choose your active pokemon" {
+                break;
+            }
+
+            pickers.push(d.player());
+            node = node.resolve_decision(0).unwrap();
+        }
+
+        assert_eq!(
+            pickers,
+            vec![
+                Player::Player1,
+                Player::Player1,
+                Player::Player2,
+                Player::Player2
+            ]
+        );
+
+        for slot in Slot::all() {
+            assert!(node.state().standing_pokemon(slot).is_some());
+        }
+    }
+
+    #[test]
+    fn speed_ties_try_every_order() {
+        let node = State::start(team(&["Rattata", "Pidgey"]), team(&["Eevee", "Ditto"])).unwrap();
+        let node = testing::play(
+            node,
+            &[
+                "Rattata",
+                "Pidgey",
+                "Eevee",
+                "Ditto",
+                "Tackle \u{2192} Eevee",
+                "Tackle \u{2192} Eevee",
+                "Tackle \u{2192} Rattata",
+            ],
+        );
+        let last_choice = match node.branches() {
+            Branches::Decision(d) => d
+                .choices()
+                .iter()
+                .position(|c| c.name().to_string() == "Tackle \u{2192} Rattata")
+                .unwrap(),
+            _ => panic!("Expected Ditto's action in {:?}", node),
+        };
+        let node = node.resolve_decision(last_choice).unwrap();
+
+        match node.branches() {
+            Branches::Chance(c) => {
+                assert_eq!(c.possibilities().len(), 24);
+                assert_eq!(
+                    c.possibilities()[0].name().to_string(),
+                    "Player1 slot 1 then Player1 slot 2 then Player2 slot 1 then Player2 slot 2"
+                );
+            }
+            _ => panic!("No speed tie in {:?}", node),
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use rand::{rngs::StdRng, seq::IteratorRandom, Rng, SeedableRng};
+
+use crate::state::{Decision, DecisionPolicy, Node, StateBase};
+
+/// Picks uniformly among the enabled choices of every decision.
+#[derive(Debug, Clone)]
+pub struct RandomPolicy<R> {
+    rng: R,
+}
+
+impl<R> RandomPolicy<R>
+where
+    R: Rng,
+{
+    pub fn new(rng: R) -> Self {
+        Self { rng }
+    }
+}
+
+impl<S, R> DecisionPolicy<S> for RandomPolicy<R>
+where
+    S: StateBase,
+    R: Rng,
+{
+    fn choose(&mut self, _state: &S, decision: &Decision<S>) -> usize {
+        decision
+            .enabled_choices()
+            .map(|(idx, _)| idx)
+            .choose(&mut self.rng)
+            .expect("Decision with no enabled choices")
+    }
+}
+
+/// Plays `root` through to a leaf with random decisions and chances, and returns the state it
+/// ends on.
+///
+/// Everything is drawn from `rng`, so the same seed always gives the same playthrough.
+pub fn play_random<S, R>(root: Node<S>, rng: &mut R) -> S
+where
+    S: StateBase + 'static,
+    R: Rng,
+{
+    let mut policy = RandomPolicy::new(StdRng::seed_from_u64(rng.gen()));
+    root.play(&mut policy, rng)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        pokemon::PokeType,
+        single::State,
+        state::{BranchSelection, ReplayError},
+        testing,
+    };
+
+    fn battle() -> Node<State> {
+        let pokemon =
+            |name| testing::builder(name, &[PokeType::Normal], &["Tackle", "Swords Dance"]).build();
+
+        State::start(
+            testing::team(vec![pokemon("Rattata"), pokemon("Raticate")]),
+            testing::team(vec![pokemon("Pidgey")]),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn replaying_a_recording_reproduces_the_battle() {
+        let mut policy = RandomPolicy::new(StdRng::seed_from_u64(4));
+
+        let (state, replay) = battle().play_recorded(&mut policy, 3);
+        let replayed = battle().try_replay(&replay).unwrap();
+
+        assert_eq!(format!("{:?}", replayed), format!("{:?}", state));
+    }
+
+    #[test]
+    fn replaying_the_wrong_kind_of_branch_fails() {
+        let mut policy = RandomPolicy::new(StdRng::seed_from_u64(4));
+
+        let (_, mut replay) = battle().play_recorded(&mut policy, 3);
+        replay.selections[0] = match replay.selections[0] {
+            BranchSelection::Decision(idx) => BranchSelection::Chance(idx),
+            BranchSelection::Chance(idx) => BranchSelection::Decision(idx),
+        };
+
+        assert!(matches!(
+            battle().try_replay(&replay),
+            Err(ReplayError::Mismatch { step: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn replaying_half_a_recording_fails() {
+        let mut policy = RandomPolicy::new(StdRng::seed_from_u64(4));
+
+        let (_, mut replay) = battle().play_recorded(&mut policy, 3);
+        replay.selections.truncate(replay.selections.len() / 2);
+
+        assert!(matches!(
+            battle().try_replay(&replay),
+            Err(ReplayError::Unfinished(_))
+        ));
+    }
+
+    #[test]
+    fn random_playthroughs_are_reproducible_from_a_seed() {
+        let play = |seed| play_random(battle(), &mut StdRng::seed_from_u64(seed));
+
+        assert_eq!(format!("{:?}", play(7)), format!("{:?}", play(7)));
+    }
+}
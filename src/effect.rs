@@ -1,6 +1,55 @@
-use crate::state::{EventHandler, StateBase};
+use crate::{battle::Battle, state::EventHandler};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone)]
-pub enum Effect {}
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Effect {
+    /// Each turn the pokemon tries to move, `turns` goes down by one, and it snaps out of
+    /// confusion when it reaches 0.
+    Confusion { turns: u8 },
+    /// At the end of each turn, the pokemon loses an eighth of its max HP and the opposing active
+    /// pokemon heals by as much.
+    LeechSeed,
+    /// The pokemon can't use status moves. `turns` goes down by one at the end of each turn, and
+    /// the taunt ends when it reaches 0.
+    Taunt { turns: u8 },
+}
 
-impl<S: StateBase> EventHandler<S> for Effect {}
+impl Effect {
+    pub fn confusion_turns_mut(&mut self) -> Option<&mut u8> {
+        match self {
+            Effect::Confusion { turns } => Some(turns),
+            _ => None,
+        }
+    }
+
+    /// Counts down an effect that wears off at the end of a turn.
+    ///
+    /// Returns whether the effect is still active.
+    pub fn tick(&mut self) -> bool {
+        match self {
+            Effect::Taunt { turns } => {
+                *turns = turns.saturating_sub(1);
+                *turns > 0
+            }
+            Effect::Confusion { .. } | Effect::LeechSeed => true,
+        }
+    }
+}
+
+impl<S: Battle> EventHandler<S, S::Position> for Effect {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn taunts_wear_off_after_their_turns() {
+        let mut taunt = Effect::Taunt { turns: 3 };
+
+        assert!(taunt.tick());
+        assert!(taunt.tick());
+        assert!(!taunt.tick());
+    }
+}
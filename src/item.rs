@@ -1,2 +1,141 @@
-#[derive(Debug, Clone)]
-pub enum Item {}
+use std::fmt::{self, Display, Formatter};
+
+use crate::{
+    battle::Battle,
+    log::LogEvent,
+    pokemon::StatKind,
+    pokemove::{MoveCategory, PokeMove},
+    state::{EventHandler, Node},
+    weather::Weather,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Item {
+    ChoiceBand,
+    ChoiceScarf,
+    DampRock,
+    /// Lets the holder survive being knocked out from full HP with 1 HP left, and is used up.
+    FocusSash,
+    HeatRock,
+    IcyRock,
+    LeppaBerry,
+    Leftovers,
+    LifeOrb,
+    LightClay,
+    SmoothRock,
+}
+
+impl Item {
+    /// Whether weather started by the holder lasts 8 turns instead of 5.
+    pub fn extends_weather(&self, weather: Weather) -> bool {
+        matches!(
+            (self, weather),
+            (Item::DampRock, Weather::Rain)
+                | (Item::HeatRock, Weather::Sun)
+                | (Item::IcyRock, Weather::Hail)
+                | (Item::SmoothRock, Weather::Sandstorm)
+        )
+    }
+
+    /// Multiplies the damage of the holder's moves.
+    pub fn damage_multiplier(&self) -> f64 {
+        match self {
+            Item::LifeOrb => 1.3,
+            _ => 1.0,
+        }
+    }
+
+    /// Multiplies the attacking stat the holder uses for moves of `category`.
+    pub fn attack_multiplier(&self, category: MoveCategory) -> f64 {
+        match (self, category) {
+            (Item::ChoiceBand, MoveCategory::Physical) => 1.5,
+            _ => 1.0,
+        }
+    }
+
+    /// Whether the holder is locked into the first move it uses.
+    pub fn is_choice(&self) -> bool {
+        matches!(self, Item::ChoiceBand | Item::ChoiceScarf)
+    }
+
+    /// Whether screens set up by the holder last 8 turns instead of 5.
+    pub fn extends_screens(&self) -> bool {
+        matches!(self, Item::LightClay)
+    }
+}
+
+impl<S: Battle> EventHandler<S, S::Position> for Item {
+    fn on_turn_end(&self, mut state: S, position: S::Position) -> Node<S> {
+        if let Item::Leftovers = self {
+            state.restore_hp(position, 1.0 / 16.0);
+        }
+
+        Node::pending(state)
+    }
+
+    fn acts_on_turn_end(&self, state: &S, position: S::Position) -> bool {
+        let pokemon = state.pokemon(position).unwrap();
+
+        matches!(self, Item::Leftovers)
+            && !pokemon.is_fainted()
+            && pokemon.current_hp < pokemon.computed_stat(StatKind::Hp)
+    }
+
+    fn on_faint(&self, mut state: S, fainted: S::Position, amount: u32) -> Node<S> {
+        let pokemon = state.pokemon_mut(fainted).unwrap();
+
+        if let Item::FocusSash = self {
+            if amount == pokemon.computed_stat(StatKind::Hp) {
+                pokemon.current_hp = 1;
+                pokemon.item = None;
+
+                let event = LogEvent::HungOn {
+                    pokemon: pokemon.to_string(),
+                    item: self.to_string(),
+                };
+                state.log(event);
+            }
+        }
+
+        Node::pending(state)
+    }
+
+    fn on_damage_dealt(
+        &self,
+        mut state: S,
+        attacker: S::Position,
+        _target: S::Position,
+        _pokemove: &PokeMove,
+        _amount: u32,
+    ) -> Node<S> {
+        if let Item::LifeOrb = self {
+            let max_hp = state.pokemon(attacker).unwrap().computed_stat(StatKind::Hp);
+            state.deal_indirect_damage(attacker, (max_hp / 10).max(1));
+        }
+
+        Node::pending(state)
+    }
+}
+
+impl Display for Item {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let name = match self {
+            Item::ChoiceBand => "Choice Band",
+            Item::ChoiceScarf => "Choice Scarf",
+            Item::DampRock => "Damp Rock",
+            Item::FocusSash => "Focus Sash",
+            Item::HeatRock => "Heat Rock",
+            Item::IcyRock => "Icy Rock",
+            Item::LeppaBerry => "Leppa Berry",
+            Item::Leftovers => "Leftovers",
+            Item::LifeOrb => "Life Orb",
+            Item::LightClay => "Light Clay",
+            Item::SmoothRock => "Smooth Rock",
+        };
+
+        write!(f, "{}", name)
+    }
+}
@@ -1,9 +1,26 @@
-#![feature(try_trait)]
-
 pub mod ability;
+pub mod ai;
+pub mod analysis;
+pub mod battle;
+pub mod cli;
+pub mod damage;
+#[cfg(feature = "dataset")]
+pub mod dataset;
+pub mod double;
+pub mod driver;
 pub mod effect;
 pub mod item;
+pub mod log;
+pub mod nature;
 pub mod pokemon;
 pub mod pokemove;
+pub mod rules;
+pub mod simulation;
 pub mod single;
 pub mod state;
+pub mod status;
+pub mod teambuilder;
+pub mod terrain;
+#[cfg(test)]
+mod testing;
+pub mod weather;
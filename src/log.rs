@@ -0,0 +1,297 @@
+use std::fmt::{self, Display, Formatter};
+
+use crate::{
+    pokemon::{StatKind, TypeEffectiveness},
+    pokemove::Screen,
+    status::Status,
+    terrain::Terrain,
+    weather::Weather,
+};
+
+/// Everything that happened over the course of a battle, in order.
+#[derive(Debug, Clone, Default)]
+pub struct BattleLog {
+    pub events: Vec<LogEvent>,
+}
+
+impl BattleLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn extend<I>(&mut self, events: I)
+    where
+        I: IntoIterator<Item = LogEvent>,
+    {
+        self.events.extend(events);
+    }
+}
+
+impl Display for BattleLog {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for event in &self.events {
+            writeln!(f, "{}", event)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum LogEvent {
+    MoveUsed {
+        pokemon: String,
+        pokemove: String,
+    },
+    Damage {
+        pokemon: String,
+        amount: u32,
+        effectiveness: TypeEffectiveness,
+    },
+    Fainted {
+        pokemon: String,
+    },
+    Healed {
+        pokemon: String,
+        amount: u32,
+    },
+    StatusApplied {
+        pokemon: String,
+        status: Status,
+    },
+    SwitchedIn {
+        player: String,
+        pokemon: String,
+    },
+    FastAsleep {
+        pokemon: String,
+    },
+    WokeUp {
+        pokemon: String,
+    },
+    Missed {
+        pokemon: String,
+    },
+    MoveFailed,
+    Flinched {
+        pokemon: String,
+    },
+    Confused {
+        pokemon: String,
+    },
+    ConfusionEnded {
+        pokemon: String,
+    },
+    HurtInConfusion {
+        pokemon: String,
+    },
+    Seeded {
+        pokemon: String,
+    },
+    Taunted {
+        pokemon: String,
+    },
+    TauntEnded {
+        pokemon: String,
+    },
+    Crit,
+    WeatherStarted(Weather),
+    WeatherEnded(Weather),
+    TerrainStarted(Terrain),
+    TerrainEnded(Terrain),
+    StatChanged {
+        pokemon: String,
+        stat: StatKind,
+        stages: i8,
+    },
+    SubstituteCreated {
+        pokemon: String,
+    },
+    SubstituteDamaged {
+        pokemon: String,
+    },
+    SubstituteFaded {
+        pokemon: String,
+    },
+    ItemsSwapped {
+        pokemon: String,
+    },
+    ItemObtained {
+        pokemon: String,
+        item: String,
+    },
+    ItemKnockedOff {
+        pokemon: String,
+        item: String,
+    },
+    PpRestored {
+        pokemon: String,
+        pokemove: String,
+    },
+    HungOn {
+        pokemon: String,
+        item: String,
+    },
+    QuickGuard {
+        player: String,
+    },
+    ScreenStarted {
+        player: String,
+        screen: Screen,
+    },
+    ScreenEnded {
+        player: String,
+        screen: Screen,
+    },
+}
+
+impl Display for LogEvent {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            LogEvent::MoveUsed { pokemon, pokemove } => write!(f, "{} used {}!", pokemon, pokemove),
+            LogEvent::Damage {
+                pokemon,
+                amount,
+                effectiveness,
+            } => {
+                match effectiveness {
+                    TypeEffectiveness::NoEffect => {
+                        return write!(f, "It doesn't affect {}...", pokemon)
+                    }
+                    TypeEffectiveness::NotVeryEffective => {
+                        write!(f, "It's not very effective... ")?
+                    }
+                    TypeEffectiveness::Regular => {}
+                    TypeEffectiveness::SuperEffective => write!(f, "It's super effective! ")?,
+                }
+
+                write!(f, "{} lost {} HP!", pokemon, amount)
+            }
+            LogEvent::Fainted { pokemon } => write!(f, "{} fainted!", pokemon),
+            LogEvent::Healed { pokemon, amount } => {
+                write!(f, "{} regained {} HP!", pokemon, amount)
+            }
+            LogEvent::StatusApplied { pokemon, status } => match status {
+                Status::Healthy => write!(f, "{} was cured!", pokemon),
+                Status::Burn => write!(f, "{} was burned!", pokemon),
+                Status::Poison => write!(f, "{} was poisoned!", pokemon),
+                Status::BadlyPoisoned { .. } => write!(f, "{} was badly poisoned!", pokemon),
+                Status::Paralysis => {
+                    write!(f, "{} is paralyzed! It may be unable to move!", pokemon)
+                }
+                Status::Sleep { .. } => write!(f, "{} fell asleep!", pokemon),
+                Status::Freeze => write!(f, "{} was frozen solid!", pokemon),
+            },
+            LogEvent::SwitchedIn { player, pokemon } => {
+                write!(f, "{} sent out {}!", player, pokemon)
+            }
+            LogEvent::FastAsleep { pokemon } => write!(f, "{} is fast asleep.", pokemon),
+            LogEvent::WokeUp { pokemon } => write!(f, "{} woke up!", pokemon),
+            LogEvent::Missed { pokemon } => write!(f, "{}'s attack missed!", pokemon),
+            LogEvent::MoveFailed => write!(f, "But it failed!"),
+            LogEvent::Flinched { pokemon } => write!(f, "{} flinched and couldn't move!", pokemon),
+            LogEvent::Confused { pokemon } => write!(f, "{} is confused!", pokemon),
+            LogEvent::ConfusionEnded { pokemon } => {
+                write!(f, "{} snapped out of its confusion!", pokemon)
+            }
+            LogEvent::HurtInConfusion { pokemon } => {
+                write!(f, "{} hurt itself in its confusion!", pokemon)
+            }
+            LogEvent::Seeded { pokemon } => write!(f, "{} was seeded!", pokemon),
+            LogEvent::Taunted { pokemon } => write!(f, "{} fell for the taunt!", pokemon),
+            LogEvent::TauntEnded { pokemon } => {
+                write!(f, "{} shook off the taunt!", pokemon)
+            }
+            LogEvent::Crit => write!(f, "A critical hit!"),
+            LogEvent::WeatherStarted(weather) => match weather {
+                Weather::Clear => write!(f, "The weather cleared up."),
+                Weather::Rain => write!(f, "It started to rain!"),
+                Weather::Sun => write!(f, "The sunlight turned harsh!"),
+                Weather::Sandstorm => write!(f, "A sandstorm kicked up!"),
+                Weather::Hail => write!(f, "It started to hail!"),
+            },
+            LogEvent::WeatherEnded(weather) => match weather {
+                Weather::Clear => Ok(()),
+                Weather::Rain => write!(f, "The rain stopped."),
+                Weather::Sun => write!(f, "The harsh sunlight faded."),
+                Weather::Sandstorm => write!(f, "The sandstorm subsided."),
+                Weather::Hail => write!(f, "The hail stopped."),
+            },
+            LogEvent::TerrainStarted(terrain) => match terrain {
+                Terrain::None => Ok(()),
+                Terrain::Electric => write!(f, "An electric current ran across the battlefield!"),
+                Terrain::Grassy => write!(f, "Grass grew to cover the battlefield!"),
+                Terrain::Misty => write!(f, "Mist swirled around the battlefield!"),
+                Terrain::Psychic => write!(f, "The battlefield got weird!"),
+            },
+            LogEvent::TerrainEnded(terrain) => match terrain {
+                Terrain::None => Ok(()),
+                _ => write!(f, "The {} disappeared from the battlefield.", terrain),
+            },
+            LogEvent::StatChanged {
+                pokemon,
+                stat,
+                stages,
+            } => {
+                let stat = match stat {
+                    StatKind::Hp => "HP",
+                    StatKind::Attack => "Attack",
+                    StatKind::Defense => "Defense",
+                    StatKind::SpecialAttack => "Sp. Atk",
+                    StatKind::SpecialDefense => "Sp. Def",
+                    StatKind::Speed => "Speed",
+                };
+
+                match stages {
+                    i8::MIN..=-3 => write!(f, "{}'s {} severely fell!", pokemon, stat),
+                    -2 => write!(f, "{}'s {} harshly fell!", pokemon, stat),
+                    -1 => write!(f, "{}'s {} fell!", pokemon, stat),
+                    0 => write!(f, "{}'s {} won't go any further!", pokemon, stat),
+                    1 => write!(f, "{}'s {} rose!", pokemon, stat),
+                    2 => write!(f, "{}'s {} rose sharply!", pokemon, stat),
+                    _ => write!(f, "{}'s {} rose drastically!", pokemon, stat),
+                }
+            }
+            LogEvent::SubstituteCreated { pokemon } => {
+                write!(f, "{} put in a substitute!", pokemon)
+            }
+            LogEvent::SubstituteDamaged { pokemon } => {
+                write!(f, "The substitute took damage for {}!", pokemon)
+            }
+            LogEvent::SubstituteFaded { pokemon } => {
+                write!(f, "{}'s substitute faded!", pokemon)
+            }
+            LogEvent::ItemsSwapped { pokemon } => {
+                write!(f, "{} switched items with its target!", pokemon)
+            }
+            LogEvent::ItemObtained { pokemon, item } => write!(f, "{} obtained {}.", pokemon, item),
+            LogEvent::ItemKnockedOff { pokemon, item } => {
+                write!(f, "{}'s {} was knocked off!", pokemon, item)
+            }
+            LogEvent::HungOn { pokemon, item } => {
+                write!(f, "{} hung on using its {}!", pokemon, item)
+            }
+            LogEvent::PpRestored { pokemon, pokemove } => {
+                write!(f, "{} restored PP to its {}!", pokemon, pokemove)
+            }
+            LogEvent::QuickGuard { player } => {
+                write!(f, "Quick Guard protected {}'s team!", player)
+            }
+            LogEvent::ScreenStarted { player, screen } => match screen {
+                Screen::Reflect => write!(
+                    f,
+                    "Reflect made {}'s team stronger against physical moves!",
+                    player
+                ),
+                Screen::LightScreen => write!(
+                    f,
+                    "Light Screen made {}'s team stronger against special moves!",
+                    player
+                ),
+            },
+            LogEvent::ScreenEnded { player, screen } => {
+                write!(f, "{}'s {} wore off!", player, screen)
+            }
+        }
+    }
+}
@@ -1,3 +1,154 @@
+use std::{collections::HashMap, process, rc::Rc};
+
+use rand::{seq::IteratorRandom, thread_rng};
+use tiketetaketitak::{
+    ability::Ability,
+    cli::{self, Policy},
+    pokemon::{
+        AllowedGenders, PokeType, Pokemon, PokemonBuilder, PokemonForm, PokemonSpecies, Stats, Team,
+    },
+    pokemove::PokeMove,
+    single::{Player, State},
+};
+
+/// A single-form species, which is all the sample teams need.
+fn form(
+    national_dex_no: u32,
+    name: &str,
+    types: &[PokeType],
+    base_stats: Stats,
+) -> Rc<PokemonForm> {
+    let species = Rc::new(PokemonSpecies {
+        national_dex_no,
+        name: name.to_owned(),
+        forms: Vec::new(),
+    });
+
+    Rc::new(PokemonForm {
+        species,
+        name: None,
+        types: types.to_vec(),
+        genders: AllowedGenders::from_female_eighths(4),
+        base_stats,
+        weight_hg: 500,
+        evolutions: Vec::new(),
+    })
+}
+
+fn pokemon(form: Rc<PokemonForm>, ability: Ability, moves: &[&str]) -> Pokemon {
+    moves
+        .iter()
+        .fold(PokemonBuilder::new(form).level(50), |builder, m| {
+            builder.with_move(m.parse::<PokeMove>().unwrap())
+        })
+        .ability(ability)
+        .build()
+}
+
+fn player_1_team() -> Team {
+    vec![
+        pokemon(
+            form(
+                26,
+                "Raichu",
+                &[PokeType::Electric],
+                Stats::new(60, 90, 55, 90, 80, 110),
+            ),
+            Ability::Static,
+            &["Thunderbolt", "Volt Switch", "Quick Attack", "Grass Knot"],
+        ),
+        pokemon(
+            form(
+                6,
+                "Charizard",
+                &[PokeType::Fire, PokeType::Flying],
+                Stats::new(78, 84, 78, 109, 85, 100),
+            ),
+            Ability::Blaze,
+            &["Flamethrower", "Dragon Claw", "Sunny Day", "Roost"],
+        ),
+        pokemon(
+            form(
+                143,
+                "Snorlax",
+                &[PokeType::Normal],
+                Stats::new(160, 110, 65, 65, 110, 30),
+            ),
+            Ability::ThickFat,
+            &["Return", "Earthquake", "Rest", "Crunch"],
+        ),
+    ]
+    .into_iter()
+    .collect()
+}
+
+fn player_2_team() -> Team {
+    vec![
+        pokemon(
+            form(
+                9,
+                "Blastoise",
+                &[PokeType::Water],
+                Stats::new(79, 83, 100, 85, 105, 78),
+            ),
+            Ability::Torrent,
+            &["Surf", "Ice Beam", "Rain Dance", "Knock Off"],
+        ),
+        pokemon(
+            form(
+                3,
+                "Venusaur",
+                &[PokeType::Grass, PokeType::Poison],
+                Stats::new(80, 82, 83, 100, 100, 80),
+            ),
+            Ability::Overgrow,
+            &["Energy Ball", "Leech Seed", "Synthesis", "Earthquake"],
+        ),
+        pokemon(
+            form(
+                94,
+                "Gengar",
+                &[PokeType::Ghost, PokeType::Poison],
+                Stats::new(60, 65, 60, 130, 75, 110),
+            ),
+            Ability::Levitate,
+            &["Shadow Ball", "Thunderbolt", "Psychic", "Substitute"],
+        ),
+    ]
+    .into_iter()
+    .collect()
+}
+
+/// Plays a battle between two sample teams, with Player1 at the terminal and Player2 picking at
+/// random.
 fn main() {
-    println!("Hello, world!");
+    let root = match State::start(player_1_team(), player_2_team()) {
+        Ok(root) => root,
+        Err(violation) => {
+            eprintln!("Invalid teams: {}", violation);
+            process::exit(1);
+        }
+    };
+
+    let mut opponent_rng = thread_rng();
+    let opponent: Policy<State> = Box::new(move |decision| {
+        decision
+            .enabled_choices()
+            .map(|(idx, _)| idx)
+            .choose(&mut opponent_rng)
+            .expect("Decision with no enabled choices")
+    });
+    let mut policies = HashMap::new();
+    policies.insert(Player::Player2, opponent);
+
+    match cli::run_interactive(root, policies, &mut thread_rng()) {
+        Ok(state) => match state.winner() {
+            Some(winner) => println!("{} wins!", winner),
+            None => println!("It's a tie!"),
+        },
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
+    }
 }
@@ -0,0 +1,110 @@
+use crate::pokemon::StatKind;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Raises one non-HP stat by 10% and lowers another by 10%. The five natures that would raise
+/// and lower the same stat are neutral.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Nature {
+    Hardy,
+    Lonely,
+    Brave,
+    Adamant,
+    Naughty,
+    Bold,
+    Docile,
+    Relaxed,
+    Impish,
+    Lax,
+    Timid,
+    Hasty,
+    Serious,
+    Jolly,
+    Naive,
+    Modest,
+    Mild,
+    Quiet,
+    Bashful,
+    Rash,
+    Calm,
+    Gentle,
+    Sassy,
+    Careful,
+    Quirky,
+}
+
+impl Nature {
+    /// Multiplies the given stat, as computed from base stats, IVs, EVs and level.
+    pub fn modifier(&self, stat: StatKind) -> f64 {
+        let (raised, lowered) = self.stats();
+
+        if raised == lowered {
+            1.0
+        } else if stat == raised {
+            1.1
+        } else if stat == lowered {
+            0.9
+        } else {
+            1.0
+        }
+    }
+
+    /// The stat this nature raises and the one it lowers, which are the same for neutral natures.
+    fn stats(&self) -> (StatKind, StatKind) {
+        use Nature::*;
+        use StatKind::*;
+
+        match self {
+            Hardy => (Attack, Attack),
+            Lonely => (Attack, Defense),
+            Brave => (Attack, Speed),
+            Adamant => (Attack, SpecialAttack),
+            Naughty => (Attack, SpecialDefense),
+            Bold => (Defense, Attack),
+            Docile => (Defense, Defense),
+            Relaxed => (Defense, Speed),
+            Impish => (Defense, SpecialAttack),
+            Lax => (Defense, SpecialDefense),
+            Timid => (Speed, Attack),
+            Hasty => (Speed, Defense),
+            Serious => (Speed, Speed),
+            Jolly => (Speed, SpecialAttack),
+            Naive => (Speed, SpecialDefense),
+            Modest => (SpecialAttack, Attack),
+            Mild => (SpecialAttack, Defense),
+            Quiet => (SpecialAttack, Speed),
+            Bashful => (SpecialAttack, SpecialAttack),
+            Rash => (SpecialAttack, SpecialDefense),
+            Calm => (SpecialDefense, Attack),
+            Gentle => (SpecialDefense, Defense),
+            Sassy => (SpecialDefense, Speed),
+            Careful => (SpecialDefense, SpecialAttack),
+            Quirky => (SpecialDefense, SpecialDefense),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn neutral_natures_leave_every_stat_alone() {
+        use Nature::*;
+
+        for nature in &[Hardy, Docile, Serious, Bashful, Quirky] {
+            for &stat in StatKind::values() {
+                assert_eq!(nature.modifier(stat), 1.0, "{:?} {:?}", nature, stat);
+            }
+        }
+    }
+
+    #[test]
+    fn natures_raise_one_stat_and_lower_another() {
+        assert_eq!(Nature::Adamant.modifier(StatKind::Attack), 1.1);
+        assert_eq!(Nature::Adamant.modifier(StatKind::SpecialAttack), 0.9);
+        assert_eq!(Nature::Adamant.modifier(StatKind::Speed), 1.0);
+        assert_eq!(Nature::Adamant.modifier(StatKind::Hp), 1.0);
+    }
+}
@@ -1,11 +1,18 @@
 use std::{
     fmt::{self, Display, Formatter},
+    ops::{Index, IndexMut},
     rc::Rc,
 };
 
 use arrayvec::ArrayVec;
+use rand::Rng;
 
-use crate::{ability::Ability, item::Item, pokemove::PokeMove};
+use crate::{
+    ability::Ability, damage, item::Item, nature::Nature, pokemove::PokeMove, status::Status,
+    weather::Weather,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone)]
 pub struct PokemonSpecies {
@@ -14,6 +21,30 @@ pub struct PokemonSpecies {
     pub forms: Vec<Rc<PokemonForm>>,
 }
 
+impl PokemonSpecies {
+    /// Finds the form with the given name, or the base form when `name` is `None`.
+    ///
+    /// The base form is the unnamed one, or the only form if the species has just one.
+    pub fn form_by_name(&self, name: Option<&str>) -> Option<Rc<PokemonForm>> {
+        match name {
+            Some(name) => self
+                .forms
+                .iter()
+                .find(|form| form.name.as_deref() == Some(name))
+                .cloned(),
+            None => self
+                .forms
+                .iter()
+                .find(|form| form.name.is_none())
+                .or(match self.forms.as_slice() {
+                    [form] => Some(form),
+                    _ => None,
+                })
+                .cloned(),
+        }
+    }
+}
+
 impl Display for PokemonSpecies {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         write!(f, "{}", self.name)
@@ -28,6 +59,66 @@ pub struct PokemonForm {
     pub types: Vec<PokeType>,
     pub genders: AllowedGenders,
     pub base_stats: Stats,
+    /// Weight in hectograms.
+    pub weight_hg: u32,
+    pub evolutions: Vec<Evolution>,
+}
+
+impl PokemonForm {
+    /// Base stat total.
+    pub fn bst(&self) -> u32 {
+        self.base_stats.total()
+    }
+
+    pub fn can_evolve(&self) -> bool {
+        !self.evolutions.is_empty()
+    }
+
+    /// The form this one evolves into given that `condition_met` holds, if any.
+    pub fn evolve(&self, condition_met: &EvolutionCondition) -> Option<Rc<PokemonForm>> {
+        self.evolutions
+            .iter()
+            .find(|e| e.condition.is_met_by(condition_met))
+            .map(|e| e.into.clone())
+    }
+
+    /// The same-type attack bonus this form gets on moves of `move_type`.
+    pub fn stab_multiplier(&self, move_type: &PokeType) -> f64 {
+        if self.types.contains(move_type) {
+            1.5
+        } else {
+            1.0
+        }
+    }
+
+    /// Every attacking type that hits this form for more than 1x, with its multiplier.
+    pub fn weaknesses(&self) -> Vec<(PokeType, f64)> {
+        self.matchups(|m| m > 1.0)
+    }
+
+    /// Every attacking type that hits this form for less than 1x but more than 0x.
+    pub fn resistances(&self) -> Vec<(PokeType, f64)> {
+        self.matchups(|m| m > 0.0 && m < 1.0)
+    }
+
+    /// Every attacking type that doesn't affect this form.
+    pub fn immunities(&self) -> Vec<PokeType> {
+        self.matchups(|m| m == 0.0)
+            .into_iter()
+            .map(|(t, _)| t)
+            .collect()
+    }
+
+    fn matchups<F>(&self, filter: F) -> Vec<(PokeType, f64)>
+    where
+        F: Fn(f64) -> bool,
+    {
+        PokeType::values()
+            .iter()
+            .map(|&t| (t, t.effectiveness_on_form(self)))
+            .filter(|&(_, m)| filter(m))
+            .collect()
+    }
 }
 
 impl Display for PokemonForm {
@@ -41,6 +132,36 @@ impl Display for PokemonForm {
 }
 
 #[derive(Debug, Clone)]
+pub struct Evolution {
+    pub into: Rc<PokemonForm>,
+    pub condition: EvolutionCondition,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum EvolutionCondition {
+    /// Reaching at least the given level.
+    Level(u8),
+    /// Using an evolution item, such as a Thunder Stone.
+    UseItem(String),
+    Trade,
+    Friendship,
+}
+
+impl EvolutionCondition {
+    /// Whether an evolution requiring `self` can happen when `met` holds.
+    pub fn is_met_by(&self, met: &EvolutionCondition) -> bool {
+        match (self, met) {
+            (EvolutionCondition::Level(required), EvolutionCondition::Level(level)) => {
+                level >= required
+            }
+            (required, met) => required == met,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Stats {
     pub hp: u32,
     pub attack: u32,
@@ -50,21 +171,515 @@ pub struct Stats {
     pub speed: u32,
 }
 
+impl Stats {
+    /// Stats in the usual HP, Atk, Def, SpA, SpD, Spe order.
+    pub fn new(
+        hp: u32,
+        attack: u32,
+        defense: u32,
+        special_attack: u32,
+        special_defense: u32,
+        speed: u32,
+    ) -> Self {
+        Self {
+            hp,
+            attack,
+            defense,
+            special_attack,
+            special_defense,
+            speed,
+        }
+    }
+
+    /// The same value for every stat, e.g. `Stats::uniform(31)` for perfect IVs.
+    pub fn uniform(value: u32) -> Self {
+        Self::new(value, value, value, value, value, value)
+    }
+
+    /// Every stat at 0, e.g. for a pokemon with no EVs.
+    pub fn zero() -> Self {
+        Self::uniform(0)
+    }
+
+    pub fn get(&self, kind: StatKind) -> u32 {
+        self[kind]
+    }
+
+    /// Adds EVs to a stat, staying within `MAX_EV` for the stat and `MAX_EV_TOTAL` overall.
+    ///
+    /// Returns the number of EVs actually added, which is less than `amount` if a cap was hit.
+    pub fn add_ev(&mut self, kind: StatKind, amount: u32) -> u32 {
+        let room = (MAX_EV - self[kind].min(MAX_EV)).min(MAX_EV_TOTAL.saturating_sub(self.total()));
+        let added = amount.min(room);
+        self[kind] += added;
+        added
+    }
+
+    /// Every stat with its kind, in the usual HP, Atk, Def, SpA, SpD, Spe order.
+    pub fn iter(&self) -> impl Iterator<Item = (StatKind, u32)> + '_ {
+        StatKind::values()
+            .iter()
+            .map(move |&kind| (kind, self.get(kind)))
+    }
+
+    pub fn total(&self) -> u32 {
+        self.iter().map(|(_, value)| value).sum()
+    }
+}
+
+impl Index<StatKind> for Stats {
+    type Output = u32;
+
+    fn index(&self, kind: StatKind) -> &u32 {
+        match kind {
+            StatKind::Hp => &self.hp,
+            StatKind::Attack => &self.attack,
+            StatKind::Defense => &self.defense,
+            StatKind::SpecialAttack => &self.special_attack,
+            StatKind::SpecialDefense => &self.special_defense,
+            StatKind::Speed => &self.speed,
+        }
+    }
+}
+
+impl IndexMut<StatKind> for Stats {
+    fn index_mut(&mut self, kind: StatKind) -> &mut u32 {
+        match kind {
+            StatKind::Hp => &mut self.hp,
+            StatKind::Attack => &mut self.attack,
+            StatKind::Defense => &mut self.defense,
+            StatKind::SpecialAttack => &mut self.special_attack,
+            StatKind::SpecialDefense => &mut self.special_defense,
+            StatKind::Speed => &mut self.speed,
+        }
+    }
+}
+
+/// The most EVs a single stat can have.
+pub const MAX_EV: u32 = 252;
+/// The most EVs a pokemon can have across all of its stats.
+pub const MAX_EV_TOTAL: u32 = 510;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum StatKind {
+    Hp,
+    Attack,
+    Defense,
+    SpecialAttack,
+    SpecialDefense,
+    Speed,
+}
+
+impl StatKind {
+    pub fn values() -> &'static [StatKind] {
+        use StatKind::*;
+
+        &[Hp, Attack, Defense, SpecialAttack, SpecialDefense, Speed]
+    }
+}
+
+/// In-battle stat stages, each ranging from -6 to +6.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StatStages {
+    pub attack: i8,
+    pub defense: i8,
+    pub special_attack: i8,
+    pub special_defense: i8,
+    pub speed: i8,
+    pub accuracy: i8,
+    pub evasion: i8,
+}
+
+impl StatStages {
+    pub fn get(&self, kind: StatKind) -> i8 {
+        match kind {
+            StatKind::Hp => 0,
+            StatKind::Attack => self.attack,
+            StatKind::Defense => self.defense,
+            StatKind::SpecialAttack => self.special_attack,
+            StatKind::SpecialDefense => self.special_defense,
+            StatKind::Speed => self.speed,
+        }
+    }
+
+    fn get_mut(&mut self, kind: StatKind) -> Option<&mut i8> {
+        match kind {
+            StatKind::Hp => None,
+            StatKind::Attack => Some(&mut self.attack),
+            StatKind::Defense => Some(&mut self.defense),
+            StatKind::SpecialAttack => Some(&mut self.special_attack),
+            StatKind::SpecialDefense => Some(&mut self.special_defense),
+            StatKind::Speed => Some(&mut self.speed),
+        }
+    }
+
+    /// Raises or lowers a stage, clamping it to [-6, 6].
+    ///
+    /// Returns the number of stages actually changed, which is 0 if it "won't go any higher".
+    pub fn boost(&mut self, kind: StatKind, stages: i8) -> i8 {
+        match self.get_mut(kind) {
+            Some(stage) => shift_stage(stage, stages),
+            None => 0,
+        }
+    }
+
+    pub fn multiplier(&self, kind: StatKind) -> f64 {
+        let stage = f64::from(self.get(kind));
+
+        if stage >= 0.0 {
+            (2.0 + stage) / 2.0
+        } else {
+            2.0 / (2.0 - stage)
+        }
+    }
+
+    /// How much more or less likely a move by this pokemon is to hit a pokemon with `target`'s
+    /// stages, from the user's accuracy minus the target's evasion.
+    ///
+    /// These stages use a gentler scale than the other stats, with +6 only being 3x.
+    pub fn accuracy_multiplier(&self, target: &StatStages) -> f64 {
+        let stage = f64::from((self.accuracy - target.evasion).clamp(-6, 6));
+
+        if stage >= 0.0 {
+            (3.0 + stage) / 3.0
+        } else {
+            3.0 / (3.0 - stage)
+        }
+    }
+
+    /// Like `boost`, for the accuracy stage.
+    pub fn boost_accuracy(&mut self, stages: i8) -> i8 {
+        shift_stage(&mut self.accuracy, stages)
+    }
+
+    /// Like `boost`, for the evasion stage.
+    pub fn boost_evasion(&mut self, stages: i8) -> i8 {
+        shift_stage(&mut self.evasion, stages)
+    }
+}
+
+/// Moves a stage by `stages` within [-6, 6], returning how far it actually moved.
+fn shift_stage(stage: &mut i8, stages: i8) -> i8 {
+    let old = *stage;
+    *stage = old.saturating_add(stages).clamp(-6, 6);
+    *stage - old
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Pokemon {
+    #[cfg_attr(feature = "serde", serde(with = "form_key"))]
     pub form: Rc<PokemonForm>,
 
     pub nickname: Option<String>,
 
     pub gender: Gender,
-    pub moves: ArrayVec<[PokeMove; 4]>,
+    pub level: u8,
+    pub moves: ArrayVec<PokeMove, 4>,
 
     pub ev: Stats,
     pub iv: Stats,
+    pub nature: Nature,
     pub ability: Ability,
     pub item: Option<Item>,
 
     pub current_hp: u32,
+    pub status: Status,
+
+    pub is_shiny: bool,
+    pub happiness: u8,
+}
+
+/// The happiness of a freshly caught or generated pokemon.
+pub const DEFAULT_HAPPINESS: u8 = 70;
+
+impl Pokemon {
+    /// The stat as computed from base stats, IVs, EVs, level and nature, before any in-battle
+    /// modifiers.
+    pub fn computed_stat(&self, kind: StatKind) -> u32 {
+        let base = self.form.base_stats.get(kind);
+        let iv = self.iv.get(kind);
+        let ev = self.ev.get(kind);
+        let level = u32::from(self.level);
+
+        let scaled = (2 * base + iv + ev / 4) * level / 100;
+
+        match kind {
+            StatKind::Hp => scaled + level + 10,
+            _ => (f64::from(scaled + 5) * self.nature.modifier(kind)) as u32,
+        }
+    }
+
+    /// The stat as used in battle: `computed_stat` with the pokemon's `stages` and status applied.
+    /// A burn halves Attack and paralysis quarters Speed.
+    pub fn effective_stat(&self, kind: StatKind, stages: &StatStages) -> u32 {
+        let status = match (kind, self.status) {
+            (StatKind::Attack, Status::Burn) if !self.ability.ignores_burn() => 0.5,
+            (StatKind::Speed, Status::Paralysis) => 0.25,
+            _ => 1.0,
+        };
+
+        (f64::from(self.computed_stat(kind)) * stages.multiplier(kind) * status) as u32
+    }
+
+    pub fn is_fainted(&self) -> bool {
+        self.current_hp == 0
+    }
+
+    /// Checks that the pokemon's gender is one its form allows.
+    pub fn validate_gender(&self) -> Result<(), InvalidGenderError> {
+        if self.form.genders.includes(self.gender) {
+            Ok(())
+        } else {
+            Err(InvalidGenderError {
+                pokemon: self.to_string(),
+                gender: self.gender,
+            })
+        }
+    }
+
+    /// Swaps the pokemon's form for the matching one among `species`.
+    ///
+    /// Deserialized pokemon only carry a placeholder form with the species' dex number and the
+    /// form's name, so this has to be called before they're used.
+    pub fn resolve_form(&mut self, species: &[Rc<PokemonSpecies>]) -> Result<(), UnknownFormError> {
+        let form = species
+            .iter()
+            .find(|s| s.national_dex_no == self.form.species.national_dex_no)
+            .and_then(|s| s.form_by_name(self.form.name.as_deref()))
+            .ok_or_else(|| UnknownFormError(self.form.to_string()))?;
+
+        self.form = form;
+        Ok(())
+    }
+
+    /// Restores the pokemon to full health with no status and full PP, ready for a new battle.
+    pub fn reset_for_battle(&mut self) {
+        self.current_hp = self.computed_stat(StatKind::Hp);
+        self.status = Status::Healthy;
+        self.restore_all_pp();
+    }
+
+    /// Takes up to `amount` damage, returning how much was actually taken.
+    pub fn take_damage(&mut self, amount: u32) -> u32 {
+        let amount = amount.min(self.current_hp);
+        self.current_hp -= amount;
+        amount
+    }
+
+    /// Takes damage from anything other than a direct attack, like weather or recoil, which
+    /// `ability` might prevent.
+    pub fn take_indirect_damage(&mut self, amount: u32, ability: Ability) -> u32 {
+        if ability.prevents_indirect_damage() {
+            return 0;
+        }
+
+        self.take_damage(amount)
+    }
+
+    pub fn restore_all_pp(&mut self) {
+        for pokemove in &mut self.moves {
+            pokemove.current_pp = pokemove.max_pp;
+        }
+    }
+
+    pub fn computed_stats(&self) -> Stats {
+        Stats {
+            hp: self.computed_stat(StatKind::Hp),
+            attack: self.computed_stat(StatKind::Attack),
+            defense: self.computed_stat(StatKind::Defense),
+            special_attack: self.computed_stat(StatKind::SpecialAttack),
+            special_defense: self.computed_stat(StatKind::SpecialDefense),
+            speed: self.computed_stat(StatKind::Speed),
+        }
+    }
+
+    /// Evolves the pokemon if `condition_met` allows it, keeping the same amount of damage taken.
+    ///
+    /// Returns whether the pokemon evolved.
+    pub fn evolve(&mut self, condition_met: &EvolutionCondition) -> bool {
+        match self.form.evolve(condition_met) {
+            Some(form) => {
+                let damage = self
+                    .computed_stat(StatKind::Hp)
+                    .saturating_sub(self.current_hp);
+                self.form = form;
+                self.current_hp = self.computed_stat(StatKind::Hp).saturating_sub(damage);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The speed used for all ordering decisions, with every in-battle modifier applied.
+    pub fn effective_speed(
+        &self,
+        stages: &StatStages,
+        item: Option<&Item>,
+        weather: Weather,
+    ) -> u32 {
+        let mut speed = f64::from(self.effective_stat(StatKind::Speed, stages));
+
+        if let Some(Item::ChoiceScarf) = item {
+            speed *= 1.5;
+        }
+
+        speed *= self.ability.speed_multiplier(weather);
+
+        speed as u32
+    }
+
+    /// The type of this pokemon's Hidden Power, derived from the parity of its IVs.
+    pub fn hidden_power_type(&self) -> PokeType {
+        use PokeType::*;
+
+        const TYPES: [PokeType; 16] = [
+            Fighting, Flying, Poison, Ground, Rock, Bug, Ghost, Steel, Fire, Water, Grass,
+            Electric, Psychic, Ice, Dragon, Dark,
+        ];
+
+        let bits = [
+            StatKind::Hp,
+            StatKind::Attack,
+            StatKind::Defense,
+            StatKind::Speed,
+            StatKind::SpecialAttack,
+            StatKind::SpecialDefense,
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, &kind)| (self.iv.get(kind) & 1) << i)
+        .sum::<u32>();
+
+        TYPES[(bits * 15 / 63) as usize]
+    }
+}
+
+/// Builds a pokemon with sensible defaults for everything but its form: level 100, perfect IVs,
+/// no EVs, a neutral nature, the first gender its form allows and full HP.
+#[derive(Debug, Clone)]
+pub struct PokemonBuilder {
+    pokemon: Pokemon,
+}
+
+impl PokemonBuilder {
+    pub fn new(form: Rc<PokemonForm>) -> Self {
+        let gender = form.genders.as_slice()[0];
+
+        Self {
+            pokemon: Pokemon {
+                form,
+                nickname: None,
+                gender,
+                level: 100,
+                moves: ArrayVec::new(),
+                ev: Stats::zero(),
+                iv: Stats::uniform(31),
+                nature: Nature::Hardy,
+                ability: Ability::NoAbility,
+                item: None,
+                current_hp: 0,
+                status: Status::Healthy,
+                is_shiny: false,
+                happiness: DEFAULT_HAPPINESS,
+            },
+        }
+    }
+
+    pub fn nickname(mut self, nickname: &str) -> Self {
+        self.pokemon.nickname = Some(nickname.to_owned());
+        self
+    }
+
+    pub fn gender(mut self, gender: Gender) -> Self {
+        self.pokemon.gender = gender;
+        self
+    }
+
+    pub fn level(mut self, level: u8) -> Self {
+        self.pokemon.level = level;
+        self
+    }
+
+    /// Adds a move, ignoring it if the pokemon already knows four.
+    pub fn with_move(mut self, pokemove: PokeMove) -> Self {
+        let _ = self.pokemon.moves.try_push(pokemove);
+        self
+    }
+
+    pub fn ev(mut self, ev: Stats) -> Self {
+        self.pokemon.ev = ev;
+        self
+    }
+
+    pub fn iv(mut self, iv: Stats) -> Self {
+        self.pokemon.iv = iv;
+        self
+    }
+
+    pub fn nature(mut self, nature: Nature) -> Self {
+        self.pokemon.nature = nature;
+        self
+    }
+
+    pub fn ability(mut self, ability: Ability) -> Self {
+        self.pokemon.ability = ability;
+        self
+    }
+
+    pub fn item(mut self, item: Item) -> Self {
+        self.pokemon.item = Some(item);
+        self
+    }
+
+    pub fn shiny(mut self, is_shiny: bool) -> Self {
+        self.pokemon.is_shiny = is_shiny;
+        self
+    }
+
+    pub fn happiness(mut self, happiness: u8) -> Self {
+        self.pokemon.happiness = happiness;
+        self
+    }
+
+    /// The finished pokemon, at full HP with full PP.
+    pub fn build(self) -> Pokemon {
+        let mut pokemon = self.pokemon;
+        pokemon.reset_for_battle();
+        pokemon
+    }
+}
+
+/// Rolls whether a newly generated pokemon is shiny, at 1/4096 odds or 1/1365 with the Shiny Charm.
+pub fn roll_shiny<R: Rng>(rng: &mut R, shiny_charm: bool) -> bool {
+    if shiny_charm {
+        rng.gen_ratio(1, 1365)
+    } else {
+        rng.gen_ratio(1, 4096)
+    }
+}
+
+/// Pokemon are equal when they share the same form, compared by pointer since forms point back
+/// at their species, and everything else about them matches.
+impl PartialEq for Pokemon {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.form, &other.form)
+            && self.nickname == other.nickname
+            && self.gender == other.gender
+            && self.level == other.level
+            && self.moves == other.moves
+            && self.ev == other.ev
+            && self.iv == other.iv
+            && self.nature == other.nature
+            && self.ability == other.ability
+            && self.item == other.item
+            && self.current_hp == other.current_hp
+            && self.status == other.status
+            && self.is_shiny == other.is_shiny
+            && self.happiness == other.happiness
+    }
 }
 
 impl Display for Pokemon {
@@ -77,18 +692,118 @@ impl Display for Pokemon {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownFormError(pub String);
+
+impl Display for UnknownFormError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "Unknown form: {}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidGenderError {
+    pub pokemon: String,
+    pub gender: Gender,
+}
+
+impl Display for InvalidGenderError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{} can't be {:?}", self.pokemon, self.gender)
+    }
+}
+
+/// (De)serializes a pokemon's form as its species' dex number and name plus the form's name,
+/// since forms are shared and point back at their species.
+#[cfg(feature = "serde")]
+mod form_key {
+    use std::rc::Rc;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{AllowedGenders, PokemonForm, PokemonSpecies, Stats};
+
+    #[derive(Serialize, Deserialize)]
+    struct FormKey {
+        national_dex_no: u32,
+        species: String,
+        form: Option<String>,
+    }
+
+    pub fn serialize<S>(form: &Rc<PokemonForm>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        FormKey {
+            national_dex_no: form.species.national_dex_no,
+            species: form.species.name.clone(),
+            form: form.name.clone(),
+        }
+        .serialize(serializer)
+    }
+
+    /// Gives a placeholder form to be swapped out by `Pokemon::resolve_form`.
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Rc<PokemonForm>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let key = FormKey::deserialize(deserializer)?;
+
+        Ok(Rc::new(PokemonForm {
+            species: Rc::new(PokemonSpecies {
+                national_dex_no: key.national_dex_no,
+                name: key.species,
+                forms: Vec::new(),
+            }),
+            name: key.form,
+            types: Vec::new(),
+            genders: AllowedGenders::NoGender,
+            base_stats: Stats::zero(),
+            weight_hg: 0,
+            evolutions: Vec::new(),
+        }))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum AllowedGenders {
-    MaleOrFemale,
+    /// Either gender, with `female_eighths` out of 8 pokemon being female (e.g. 1 for starters).
+    ///
+    /// Ratios of 0 and 8 are treated as `MaleOnly` and `FemaleOnly`; prefer building this with
+    /// `from_female_eighths`, which folds them.
+    MaleOrFemale {
+        female_eighths: u8,
+    },
     MaleOnly,
     FemaleOnly,
     NoGender,
 }
 
 impl AllowedGenders {
-    pub fn as_slice(&self) -> &'static [Gender] {
+    /// The genders of a species where `female_eighths` out of 8 pokemon are female, folding 0 into
+    /// `MaleOnly` and 8 or more into `FemaleOnly`.
+    pub fn from_female_eighths(female_eighths: u8) -> Self {
+        match female_eighths {
+            0 => AllowedGenders::MaleOnly,
+            1..=7 => AllowedGenders::MaleOrFemale { female_eighths },
+            _ => AllowedGenders::FemaleOnly,
+        }
+    }
+
+    /// The same genders with a one-sided `MaleOrFemale` ratio folded as in `from_female_eighths`.
+    fn folded(self) -> Self {
         match self {
-            AllowedGenders::MaleOrFemale => &[Gender::Male, Gender::Female],
+            AllowedGenders::MaleOrFemale { female_eighths } => {
+                Self::from_female_eighths(female_eighths)
+            }
+            other => other,
+        }
+    }
+
+    pub fn as_slice(&self) -> &'static [Gender] {
+        match self.folded() {
+            AllowedGenders::MaleOrFemale { .. } => &[Gender::Male, Gender::Female],
             AllowedGenders::MaleOnly => &[Gender::Male],
             AllowedGenders::FemaleOnly => &[Gender::Female],
             AllowedGenders::NoGender => &[Gender::None],
@@ -96,29 +811,36 @@ impl AllowedGenders {
     }
 
     pub fn includes(&self, gender: Gender) -> bool {
-        match (self, gender) {
-            (AllowedGenders::MaleOrFemale, Gender::Male) => true,
-            (AllowedGenders::MaleOrFemale, Gender::Female) => true,
-
-            (AllowedGenders::FemaleOnly, Gender::Female) => true,
-
-            (AllowedGenders::MaleOnly, Gender::Male) => true,
-
-            (AllowedGenders::NoGender, Gender::None) => true,
+        self.as_slice().contains(&gender)
+    }
 
-            _ => false,
+    /// Rolls a random legal gender for a newly generated pokemon.
+    pub fn roll<R: Rng>(&self, rng: &mut R) -> Gender {
+        match self.folded() {
+            AllowedGenders::MaleOrFemale { female_eighths } => {
+                if rng.gen_ratio(u32::from(female_eighths), 8) {
+                    Gender::Female
+                } else {
+                    Gender::Male
+                }
+            }
+            AllowedGenders::MaleOnly => Gender::Male,
+            AllowedGenders::FemaleOnly => Gender::Female,
+            AllowedGenders::NoGender => Gender::None,
         }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Gender {
     None,
     Male,
     Female,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum PokeType {
     Normal,
     Fire,
@@ -141,6 +863,76 @@ pub enum PokeType {
 }
 
 impl PokeType {
+    pub fn values() -> &'static [PokeType] {
+        use PokeType::*;
+
+        &[
+            Normal, Fire, Water, Electric, Grass, Ice, Fighting, Poison, Ground, Flying, Psychic,
+            Bug, Rock, Ghost, Dragon, Dark, Steel, Fairy,
+        ]
+    }
+
+    /// The types this type hits for double damage.
+    pub fn super_effective_against(&self) -> Vec<PokeType> {
+        self.defenders_with(TypeEffectiveness::SuperEffective)
+    }
+
+    /// The types that resist this type.
+    pub fn not_very_effective_against(&self) -> Vec<PokeType> {
+        self.defenders_with(TypeEffectiveness::NotVeryEffective)
+    }
+
+    /// The types that are immune to this type.
+    pub fn no_effect_against(&self) -> Vec<PokeType> {
+        self.defenders_with(TypeEffectiveness::NoEffect)
+    }
+
+    fn defenders_with(&self, effectiveness: TypeEffectiveness) -> Vec<PokeType> {
+        PokeType::values()
+            .iter()
+            .copied()
+            .filter(|defender| self.effectiveness_on(defender) == effectiveness)
+            .collect()
+    }
+
+    /// The combined multiplier of this type against every type of `defender`, which is 1.0 for a
+    /// form with no types.
+    pub fn effectiveness_on_form(&self, defender: &PokemonForm) -> f64 {
+        damage::effectiveness_against(*self, defender)
+    }
+
+    /// The first generation this type appeared in.
+    pub fn introduced_in(&self) -> Generation {
+        match self {
+            PokeType::Dark | PokeType::Steel => Generation::Gen2,
+            PokeType::Fairy => Generation::Gen6,
+            _ => Generation::Gen1,
+        }
+    }
+
+    /// The matchup of this type against `defender` under the type chart of `gen`.
+    ///
+    /// Matchups involving a type that didn't exist yet in `gen` are `Regular`.
+    pub fn effectiveness_on_in(&self, defender: &Self, gen: Generation) -> TypeEffectiveness {
+        use Generation::*;
+        use PokeType::*;
+        use TypeEffectiveness::*;
+
+        if self.introduced_in() > gen || defender.introduced_in() > gen {
+            return Regular;
+        }
+
+        match (self, defender) {
+            (Ghost, Psychic) if gen == Gen1 => NoEffect,
+            (Bug, Poison) | (Poison, Bug) if gen == Gen1 => SuperEffective,
+            (Ice, Fire) if gen == Gen1 => Regular,
+            (Ghost, Steel) | (Dark, Steel) if gen < Gen6 => NotVeryEffective,
+            _ => self.effectiveness_on(defender),
+        }
+    }
+
+    /// The matchup of this type against `defender` under the current type chart, used since
+    /// Gen VI.
     pub fn effectiveness_on(&self, defender: &Self) -> TypeEffectiveness {
         use PokeType::*;
         use TypeEffectiveness::*;
@@ -247,7 +1039,24 @@ impl PokeType {
     }
 }
 
-#[derive(Debug, Clone)]
+/// A generation of the main series games, for the rules that changed between them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Generation {
+    Gen1,
+    Gen2,
+    Gen3,
+    Gen4,
+    Gen5,
+    Gen6,
+    Gen7,
+    Gen8,
+    Gen9,
+}
+
+/// Ordered from least to most effective, so matchups can be compared directly.
+/// Keep the variants in this order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum TypeEffectiveness {
     NoEffect,
     NotVeryEffective,
@@ -255,4 +1064,570 @@ pub enum TypeEffectiveness {
     SuperEffective,
 }
 
-pub type Team = ArrayVec<[Pokemon; 6]>;
+impl TypeEffectiveness {
+    /// The damage multiplier of a single type matchup.
+    pub fn multiplier(&self) -> f64 {
+        match self {
+            TypeEffectiveness::NoEffect => 0.0,
+            TypeEffectiveness::NotVeryEffective => 0.5,
+            TypeEffectiveness::Regular => 1.0,
+            TypeEffectiveness::SuperEffective => 2.0,
+        }
+    }
+}
+
+pub type Team = ArrayVec<Pokemon, 6>;
+
+pub fn reset_team_for_battle(team: &mut Team) {
+    for pokemon in team.iter_mut() {
+        pokemon.reset_for_battle();
+    }
+}
+
+/// Looks up the forms of a deserialized team in `species`, as with `Pokemon::resolve_form`.
+pub fn resolve_team_forms(
+    team: &mut Team,
+    species: &[Rc<PokemonSpecies>],
+) -> Result<(), UnknownFormError> {
+    for pokemon in team.iter_mut() {
+        pokemon.resolve_form(species)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn starter_ratio_rolls_mostly_male() {
+        let genders = AllowedGenders::from_female_eighths(1);
+        let mut rng = StdRng::seed_from_u64(105);
+        let females = (0..8000)
+            .filter(|_| genders.roll(&mut rng) == Gender::Female)
+            .count();
+
+        assert!((900..1100).contains(&females), "{} females", females);
+    }
+
+    #[test]
+    fn one_sided_ratios_fold_into_a_single_gender() {
+        assert_eq!(
+            AllowedGenders::from_female_eighths(0),
+            AllowedGenders::MaleOnly
+        );
+        assert_eq!(
+            AllowedGenders::from_female_eighths(8),
+            AllowedGenders::FemaleOnly
+        );
+
+        let all_female = AllowedGenders::MaleOrFemale { female_eighths: 8 };
+        assert_eq!(all_female.as_slice(), &[Gender::Female]);
+        assert!(!all_female.includes(Gender::Male));
+    }
+
+    #[test]
+    fn shiny_odds() {
+        let mut rng = StdRng::seed_from_u64(107);
+        let shinies = (0..409_600).filter(|_| roll_shiny(&mut rng, false)).count();
+        let charmed = (0..409_600).filter(|_| roll_shiny(&mut rng, true)).count();
+
+        assert!((80..120).contains(&shinies), "{} shinies", shinies);
+        assert!((250..350).contains(&charmed), "{} charmed shinies", charmed);
+    }
+
+    fn evolving_into(name: &str, into: Rc<PokemonForm>, level: u8) -> Rc<PokemonForm> {
+        Rc::new(PokemonForm {
+            evolutions: vec![Evolution {
+                into,
+                condition: EvolutionCondition::Level(level),
+            }],
+            ..(*testing::form(name, &[PokeType::Fire])).clone()
+        })
+    }
+
+    #[test]
+    fn mid_stage_forms_can_evolve() {
+        let charizard = testing::form("Charizard", &[PokeType::Fire, PokeType::Flying]);
+        let charmeleon = evolving_into("Charmeleon", charizard.clone(), 36);
+        let charmander = evolving_into("Charmander", charmeleon.clone(), 16);
+
+        assert!(charmander.can_evolve());
+        assert!(charmeleon.can_evolve());
+        assert!(!charizard.can_evolve());
+        assert!(charmeleon.evolve(&EvolutionCondition::Level(35)).is_none());
+        assert!(Rc::ptr_eq(
+            &charmeleon.evolve(&EvolutionCondition::Level(40)).unwrap(),
+            &charizard
+        ));
+    }
+
+    #[test]
+    fn evolving_keeps_damage_taken() {
+        let charizard = Rc::new(PokemonForm {
+            base_stats: Stats::uniform(100),
+            ..(*testing::form("Charizard", &[PokeType::Fire])).clone()
+        });
+        let mut pokemon = PokemonBuilder::new(evolving_into("Charmeleon", charizard, 36)).build();
+        pokemon.take_damage(50);
+
+        assert!(!pokemon.evolve(&EvolutionCondition::Trade));
+        assert!(pokemon.evolve(&EvolutionCondition::Level(36)));
+        assert_eq!(pokemon.current_hp, pokemon.computed_stat(StatKind::Hp) - 50);
+    }
+
+    #[test]
+    fn steel_stopped_resisting_ghost_in_gen_6() {
+        assert_eq!(
+            PokeType::Ghost.effectiveness_on_in(&PokeType::Steel, Generation::Gen5),
+            TypeEffectiveness::NotVeryEffective
+        );
+        assert_eq!(
+            PokeType::Ghost.effectiveness_on_in(&PokeType::Steel, Generation::Gen6),
+            TypeEffectiveness::Regular
+        );
+    }
+
+    #[test]
+    fn types_from_later_generations_are_neutral() {
+        use Generation::*;
+        use PokeType::*;
+        use TypeEffectiveness::*;
+
+        assert_eq!(Dragon.effectiveness_on_in(&Fairy, Gen5), Regular);
+        assert_eq!(Dragon.effectiveness_on_in(&Fairy, Gen6), NoEffect);
+        assert_eq!(Fairy.effectiveness_on_in(&Dragon, Gen5), Regular);
+        assert_eq!(Fighting.effectiveness_on_in(&Steel, Gen1), Regular);
+        assert_eq!(Dark.effectiveness_on_in(&Psychic, Gen1), Regular);
+        assert_eq!(Dark.effectiveness_on_in(&Psychic, Gen2), SuperEffective);
+    }
+
+    #[test]
+    fn genderless_forms_reject_males() {
+        let magnemite = PokemonBuilder::new(testing::form("Magnemite", &[PokeType::Electric]))
+            .gender(Gender::Male)
+            .build();
+
+        assert_eq!(
+            magnemite.validate_gender(),
+            Err(InvalidGenderError {
+                pokemon: "Magnemite".to_owned(),
+                gender: Gender::Male,
+            })
+        );
+    }
+
+    #[test]
+    fn mixed_gender_forms_accept_females() {
+        let form = testing::form_with(
+            "Eevee",
+            &[PokeType::Normal],
+            AllowedGenders::from_female_eighths(1),
+            Stats::uniform(55),
+        );
+        let eevee = PokemonBuilder::new(form).gender(Gender::Female).build();
+
+        assert_eq!(eevee.validate_gender(), Ok(()));
+    }
+
+    #[test]
+    fn type_effectiveness_orders_from_least_to_most_effective() {
+        use TypeEffectiveness::*;
+
+        assert!(NoEffect < NotVeryEffective);
+        assert!(NotVeryEffective < Regular);
+        assert!(Regular < SuperEffective);
+        assert_eq!(
+            [Regular, NoEffect, SuperEffective, NotVeryEffective]
+                .iter()
+                .max(),
+            Some(&SuperEffective)
+        );
+    }
+
+    #[test]
+    fn stab_applies_when_the_form_shares_the_move_type() {
+        let pikachu = testing::form("Pikachu", &[PokeType::Electric]);
+        let charizard = testing::form("Charizard", &[PokeType::Fire, PokeType::Flying]);
+
+        assert_eq!(pikachu.stab_multiplier(&PokeType::Electric), 1.5);
+        assert_eq!(charizard.stab_multiplier(&PokeType::Flying), 1.5);
+        assert_eq!(charizard.stab_multiplier(&PokeType::Water), 1.0);
+    }
+
+    #[test]
+    fn types_can_be_collected_into_a_set() {
+        let form = testing::form("Gyarados", &[PokeType::Water, PokeType::Flying]);
+        let types: HashSet<PokeType> = form.types.iter().chain(&form.types).copied().collect();
+
+        assert_eq!(types.len(), 2);
+        assert!(types.contains(&PokeType::Water));
+        assert!(types.contains(&PokeType::Flying));
+    }
+
+    #[test]
+    fn effective_speed_stacks_every_modifier() {
+        let pokemon = testing::builder("Jolteon", &[PokeType::Electric], &[])
+            .ability(Ability::SwiftSwim)
+            .build();
+        let stages = StatStages {
+            speed: 2,
+            ..StatStages::default()
+        };
+        let speed = |status, item, weather| {
+            let pokemon = Pokemon {
+                status,
+                ..pokemon.clone()
+            };
+            pokemon.effective_speed(&stages, item, weather)
+        };
+
+        assert_eq!(pokemon.computed_stat(StatKind::Speed), 100);
+        assert_eq!(speed(Status::Healthy, None, Weather::Clear), 200);
+        assert_eq!(
+            speed(Status::Paralysis, Some(&Item::ChoiceScarf), Weather::Clear),
+            75
+        );
+        assert_eq!(
+            speed(Status::Paralysis, Some(&Item::ChoiceScarf), Weather::Rain),
+            150
+        );
+    }
+
+    #[test]
+    fn base_stat_totals_sum_all_six_stats() {
+        let stats = Stats {
+            hp: 35,
+            attack: 55,
+            defense: 40,
+            special_attack: 50,
+            special_defense: 50,
+            speed: 90,
+        };
+        let pikachu = testing::form_with(
+            "Pikachu",
+            &[PokeType::Electric],
+            AllowedGenders::NoGender,
+            stats,
+        );
+
+        assert_eq!(pikachu.base_stats.total(), 320);
+        assert_eq!(pikachu.bst(), 320);
+        assert_eq!(Stats::uniform(80).total(), 480);
+    }
+
+    #[test]
+    fn forms_are_found_by_name() {
+        let base = testing::form("Rotom", &[PokeType::Electric, PokeType::Ghost]);
+        let wash = Rc::new(PokemonForm {
+            name: Some("Wash".to_owned()),
+            types: vec![PokeType::Electric, PokeType::Water],
+            ..(*base).clone()
+        });
+        let rotom = PokemonSpecies {
+            national_dex_no: 479,
+            name: "Rotom".to_owned(),
+            forms: vec![wash.clone(), base.clone()],
+        };
+        let only_wash = PokemonSpecies {
+            forms: vec![wash.clone()],
+            ..rotom.clone()
+        };
+
+        assert!(Rc::ptr_eq(
+            &rotom.form_by_name(Some("Wash")).unwrap(),
+            &wash
+        ));
+        assert!(Rc::ptr_eq(&rotom.form_by_name(None).unwrap(), &base));
+        assert!(Rc::ptr_eq(&only_wash.form_by_name(None).unwrap(), &wash));
+        assert!(rotom.form_by_name(Some("Heat")).is_none());
+    }
+
+    #[test]
+    fn dual_types_combine_their_matchups() {
+        let swampert = testing::form("Swampert", &[PokeType::Water, PokeType::Ground]);
+
+        assert_eq!(swampert.weaknesses(), [(PokeType::Grass, 4.0)]);
+        assert_eq!(swampert.immunities(), [PokeType::Electric]);
+        assert!(swampert
+            .resistances()
+            .iter()
+            .any(|&(t, m)| t == PokeType::Fire && m == 0.5));
+    }
+
+    #[test]
+    fn type_effectiveness_multipliers() {
+        assert_eq!(TypeEffectiveness::NoEffect.multiplier(), 0.0);
+        assert_eq!(TypeEffectiveness::NotVeryEffective.multiplier(), 0.5);
+        assert_eq!(TypeEffectiveness::Regular.multiplier(), 1.0);
+        assert_eq!(TypeEffectiveness::SuperEffective.multiplier(), 2.0);
+    }
+
+    #[test]
+    fn effectiveness_multiplies_across_both_defending_types() {
+        let venusaur = testing::form("Venusaur", &[PokeType::Grass, PokeType::Poison]);
+        let skarmory = testing::form("Skarmory", &[PokeType::Flying, PokeType::Steel]);
+        let snorlax = testing::form("Snorlax", &[PokeType::Normal]);
+        let spiritomb = testing::form("Spiritomb", &[PokeType::Ghost, PokeType::Dark]);
+        let typeless = testing::form("Typeless", &[]);
+
+        assert_eq!(PokeType::Fire.effectiveness_on_form(&venusaur), 2.0);
+        assert_eq!(PokeType::Psychic.effectiveness_on_form(&venusaur), 2.0);
+        assert_eq!(PokeType::Ground.effectiveness_on_form(&skarmory), 0.0);
+        assert_eq!(PokeType::Fighting.effectiveness_on_form(&snorlax), 2.0);
+        assert_eq!(PokeType::Fighting.effectiveness_on_form(&spiritomb), 0.0);
+        assert_eq!(PokeType::Fire.effectiveness_on_form(&typeless), 1.0);
+    }
+
+    #[test]
+    fn pokemon_faint_at_zero_hp() {
+        let mut pokemon = testing::builder("Pikachu", &[PokeType::Electric], &[]).build();
+
+        assert_eq!(pokemon.current_hp, pokemon.computed_stat(StatKind::Hp));
+        assert!(!pokemon.is_fainted());
+
+        pokemon.current_hp = 0;
+        assert!(pokemon.is_fainted());
+    }
+
+    #[test]
+    fn stats_match_a_level_100_garchomp() {
+        let base_stats = Stats {
+            hp: 108,
+            attack: 130,
+            defense: 95,
+            special_attack: 80,
+            special_defense: 85,
+            speed: 102,
+        };
+        let form = testing::form_with(
+            "Garchomp",
+            &[PokeType::Dragon, PokeType::Ground],
+            AllowedGenders::NoGender,
+            base_stats,
+        );
+        let garchomp = PokemonBuilder::new(form)
+            .ev(Stats {
+                hp: 4,
+                attack: 252,
+                speed: 252,
+                ..Stats::zero()
+            })
+            .nature(Nature::Jolly)
+            .build();
+
+        assert_eq!(garchomp.computed_stat(StatKind::Hp), 358);
+        assert_eq!(garchomp.computed_stat(StatKind::Attack), 359);
+        assert_eq!(garchomp.computed_stat(StatKind::Defense), 226);
+        assert_eq!(garchomp.computed_stat(StatKind::SpecialAttack), 176);
+        assert_eq!(garchomp.computed_stat(StatKind::SpecialDefense), 206);
+        assert_eq!(garchomp.computed_stat(StatKind::Speed), 333);
+    }
+
+    #[test]
+    fn effective_stats_apply_stat_stages() {
+        let mew = testing::builder("Mew", &[PokeType::Psychic], &[])
+            .level(100)
+            .build();
+        let stages = StatStages {
+            attack: 1,
+            speed: -1,
+            ..StatStages::default()
+        };
+
+        assert_eq!(mew.computed_stat(StatKind::Attack), 196);
+        assert_eq!(
+            mew.effective_stat(StatKind::Attack, &StatStages::default()),
+            196
+        );
+        assert_eq!(mew.effective_stat(StatKind::Attack, &stages), 294);
+        assert_eq!(mew.effective_stat(StatKind::Speed, &stages), 130);
+        assert_eq!(mew.effective_stat(StatKind::Defense, &stages), 196);
+    }
+
+    #[test]
+    fn burns_halve_attack_and_paralysis_quarters_speed() {
+        let healthy = testing::builder("Mew", &[PokeType::Psychic], &[]).build();
+        let burned = Pokemon {
+            status: Status::Burn,
+            ..healthy.clone()
+        };
+        let paralyzed = Pokemon {
+            status: Status::Paralysis,
+            ..healthy.clone()
+        };
+        let guts = Pokemon {
+            ability: Ability::Guts,
+            ..burned.clone()
+        };
+        let stat = |pokemon: &Pokemon, kind| pokemon.effective_stat(kind, &StatStages::default());
+
+        assert_eq!(stat(&healthy, StatKind::Attack), 100);
+        assert_eq!(stat(&burned, StatKind::Attack), 50);
+        assert_eq!(stat(&burned, StatKind::SpecialAttack), 100);
+        assert_eq!(stat(&burned, StatKind::Speed), 100);
+        assert_eq!(stat(&guts, StatKind::Attack), 100);
+        assert_eq!(stat(&paralyzed, StatKind::Speed), 25);
+        assert_eq!(stat(&paralyzed, StatKind::Attack), 100);
+    }
+
+    #[test]
+    fn type_matchups_list_their_defenders() {
+        assert_eq!(
+            PokeType::Fighting.super_effective_against(),
+            [
+                PokeType::Normal,
+                PokeType::Ice,
+                PokeType::Rock,
+                PokeType::Dark,
+                PokeType::Steel
+            ]
+        );
+        assert_eq!(
+            PokeType::Fire.not_very_effective_against(),
+            [
+                PokeType::Fire,
+                PokeType::Water,
+                PokeType::Rock,
+                PokeType::Dragon
+            ]
+        );
+        assert_eq!(PokeType::Normal.no_effect_against(), [PokeType::Ghost]);
+        assert!(PokeType::Fire.no_effect_against().is_empty());
+    }
+
+    #[test]
+    fn stats_iterate_in_order_and_total() {
+        let blissey = Stats::new(255, 10, 10, 75, 135, 55);
+
+        assert_eq!(
+            blissey.iter().collect::<Vec<_>>(),
+            [
+                (StatKind::Hp, 255),
+                (StatKind::Attack, 10),
+                (StatKind::Defense, 10),
+                (StatKind::SpecialAttack, 75),
+                (StatKind::SpecialDefense, 135),
+                (StatKind::Speed, 55),
+            ]
+        );
+        assert_eq!(blissey.total(), 540);
+    }
+
+    #[test]
+    fn evs_are_capped_per_stat_and_in_total() {
+        let mut ev = Stats::zero();
+
+        ev[StatKind::Attack] = 100;
+        assert_eq!(ev.attack, 100);
+
+        assert_eq!(ev.add_ev(StatKind::Attack, 200), 152);
+        assert_eq!(ev[StatKind::Attack], MAX_EV);
+
+        assert_eq!(ev.add_ev(StatKind::Speed, 252), 252);
+        assert_eq!(ev.add_ev(StatKind::Hp, 252), 6);
+        assert_eq!(ev.total(), MAX_EV_TOTAL);
+
+        assert_eq!(ev.add_ev(StatKind::Defense, 4), 0);
+        assert_eq!(ev.defense, 0);
+    }
+
+    #[test]
+    fn stat_stages_clamp_and_scale() {
+        let mut stages = StatStages::default();
+
+        assert_eq!(stages.boost(StatKind::Attack, 5), 5);
+        assert_eq!(stages.boost(StatKind::Attack, 2), 1);
+        assert_eq!(stages.boost(StatKind::Attack, 1), 0);
+        assert_eq!(stages.multiplier(StatKind::Attack), 4.0);
+        assert_eq!(stages.boost(StatKind::Speed, -12), -6);
+        assert_eq!(stages.multiplier(StatKind::Speed), 0.25);
+        assert_eq!(stages.boost(StatKind::Hp, 1), 0);
+
+        stages.boost(StatKind::Defense, 1);
+        stages.boost(StatKind::SpecialDefense, -1);
+        assert_eq!(stages.multiplier(StatKind::Defense), 1.5);
+        assert_eq!(stages.multiplier(StatKind::SpecialDefense), 2.0 / 3.0);
+    }
+
+    #[test]
+    fn accuracy_stages_use_a_gentler_scale() {
+        let mut user = StatStages::default();
+        let mut target = StatStages::default();
+
+        assert_eq!(user.boost_accuracy(7), 6);
+        assert_eq!(user.accuracy_multiplier(&target), 3.0);
+
+        target.boost_evasion(6);
+        assert_eq!(user.accuracy_multiplier(&target), 1.0);
+
+        user.boost_accuracy(-12);
+        assert_eq!(user.accuracy_multiplier(&target), 1.0 / 3.0);
+        assert_eq!(
+            StatStages::default().accuracy_multiplier(&StatStages {
+                evasion: 1,
+                ..StatStages::default()
+            }),
+            0.75
+        );
+    }
+
+    #[test]
+    fn builder_defaults() {
+        let pokemon = PokemonBuilder::new(testing::form("Mew", &[PokeType::Psychic])).build();
+
+        assert_eq!(pokemon.level, 100);
+        assert_eq!(pokemon.iv.total(), 31 * 6);
+        assert_eq!(pokemon.ev.total(), 0);
+        assert_eq!(pokemon.gender, Gender::None);
+        assert_eq!(pokemon.ability, Ability::NoAbility);
+        assert_eq!(pokemon.current_hp, pokemon.computed_stat(StatKind::Hp));
+    }
+
+    #[test]
+    fn builder_ignores_a_fifth_move() {
+        let pokemon = ["Tackle", "Tackle", "Tackle", "Tackle", "Surf"]
+            .iter()
+            .fold(
+                PokemonBuilder::new(testing::form("Mew", &[PokeType::Psychic])),
+                |builder, m| builder.with_move(m.parse().unwrap()),
+            )
+            .build();
+
+        assert_eq!(pokemon.moves.len(), 4);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn teams_round_trip_through_json() {
+        let form = testing::form("Pikachu", &[PokeType::Electric]);
+        let species = vec![Rc::new(PokemonSpecies {
+            national_dex_no: form.species.national_dex_no,
+            name: form.species.name.clone(),
+            forms: vec![form.clone()],
+        })];
+        let mut sparky = PokemonBuilder::new(form.clone())
+            .nickname("Sparky")
+            .item(Item::Leftovers)
+            .with_move("Thunderbolt".parse().unwrap())
+            .build();
+        sparky.moves[0].current_pp -= 1;
+        sparky.current_hp /= 2;
+        sparky.status = Status::Paralysis;
+        let team: Team = vec![sparky, PokemonBuilder::new(form).level(5).build()]
+            .into_iter()
+            .collect();
+
+        let json = serde_json::to_string(&team).unwrap();
+        let mut loaded: Team = serde_json::from_str(&json).unwrap();
+        resolve_team_forms(&mut loaded, &species).unwrap();
+
+        assert_eq!(loaded.as_slice(), team.as_slice());
+    }
+}
@@ -1,4 +1,662 @@
-use strum_macros::Display;
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+    sync::OnceLock,
+};
 
-#[derive(Debug, Clone, Display)]
-pub enum PokeMove {}
+use crate::{
+    pokemon::{PokeType, Pokemon, StatKind, StatStages},
+    terrain::Terrain,
+    weather::Weather,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PokeMove {
+    pub name: String,
+    pub poke_type: PokeType,
+    pub category: MoveCategory,
+    pub power: u32,
+    /// The percentage chance to hit, or `None` for moves that never miss.
+    pub accuracy: Option<u8>,
+    pub current_pp: u8,
+    pub max_pp: u8,
+    /// Moves with higher priority go first, regardless of speed.
+    pub priority: i8,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub target: MoveTarget,
+    pub effect: Option<MoveEffect>,
+    pub secondary: Option<Secondary>,
+}
+
+impl PokeMove {
+    /// Moves start out targeting a single opponent, which `with_target` can change.
+    pub fn new<N>(name: N, poke_type: PokeType, category: MoveCategory, power: u32, pp: u8) -> Self
+    where
+        N: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            poke_type,
+            category,
+            power,
+            accuracy: Some(100),
+            current_pp: pp,
+            max_pp: pp,
+            priority: 0,
+            target: MoveTarget::Opponent,
+            effect: None,
+            secondary: None,
+        }
+    }
+
+    /// The move used when a pokemon has no other move it can use.
+    pub fn struggle() -> Self {
+        Self::new("Struggle", PokeType::Normal, MoveCategory::Physical, 50, 1)
+            .with_effect(MoveEffect::Struggle)
+            .never_misses()
+    }
+
+    pub fn with_accuracy(mut self, accuracy: u8) -> Self {
+        self.accuracy = Some(accuracy);
+        self
+    }
+
+    pub fn never_misses(mut self) -> Self {
+        self.accuracy = None;
+        self
+    }
+
+    pub fn with_effect(mut self, effect: MoveEffect) -> Self {
+        self.effect = Some(effect);
+        self
+    }
+
+    pub fn with_priority(mut self, priority: i8) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn with_target(mut self, target: MoveTarget) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Gives the move a secondary effect which triggers on hit `chance`% of the time.
+    pub fn with_secondary(mut self, effect: SecondaryEffect, chance: u8) -> Self {
+        self.secondary = Some(Secondary { effect, chance });
+        self
+    }
+
+    /// The chance of the move hitting, from its accuracy and the user's accuracy and target's
+    /// evasion stages.
+    pub fn hit_chance(&self, user_stages: &StatStages, target_stages: &StatStages) -> f64 {
+        match self.accuracy {
+            Some(accuracy) => (f64::from(accuracy) / 100.0
+                * user_stages.accuracy_multiplier(target_stages))
+            .min(1.0),
+            None => 1.0,
+        }
+    }
+
+    /// The user's stat the move's damage scales with, or `None` for status moves.
+    pub fn attacking_stat(&self) -> Option<StatKind> {
+        match self.category {
+            MoveCategory::Physical => Some(StatKind::Attack),
+            MoveCategory::Special => Some(StatKind::SpecialAttack),
+            MoveCategory::Status => None,
+        }
+    }
+
+    /// The target's stat that reduces the move's damage, or `None` for status moves.
+    pub fn defending_stat(&self) -> Option<StatKind> {
+        match self.category {
+            MoveCategory::Physical => Some(StatKind::Defense),
+            MoveCategory::Special => Some(StatKind::SpecialDefense),
+            MoveCategory::Status => None,
+        }
+    }
+
+    /// Whether the move affects the opposing pokemon, rather than only the user or the field.
+    pub fn targets_opponent(&self) -> bool {
+        self.target != MoveTarget::SelfTarget
+    }
+
+    /// Spends one PP to use the move.
+    ///
+    /// Returns whether there was any PP left to spend.
+    pub fn use_pp(&mut self) -> bool {
+        if self.current_pp == 0 {
+            return false;
+        }
+
+        self.current_pp -= 1;
+        true
+    }
+
+    /// Restores up to `amount` PP, without going over `max_pp`.
+    pub fn restore_pp(&mut self, amount: u32) {
+        let missing = self.max_pp - self.current_pp;
+        self.current_pp += amount.min(u32::from(missing)) as u8;
+    }
+
+    /// The type this move has when used by `user`, which differs from `poke_type` for moves like
+    /// Hidden Power and Weather Ball.
+    pub fn effective_type(&self, user: &Pokemon, weather: Weather) -> PokeType {
+        match self.effect {
+            Some(MoveEffect::HiddenPower) => user.hidden_power_type(),
+            Some(MoveEffect::WeatherBall) => match weather {
+                Weather::Clear => self.poke_type,
+                Weather::Rain => PokeType::Water,
+                Weather::Sun => PokeType::Fire,
+                Weather::Sandstorm => PokeType::Rock,
+                Weather::Hail => PokeType::Ice,
+            },
+            _ => self.poke_type,
+        }
+    }
+
+    /// The base power of this move when used by `user` on `target`.
+    pub fn power_against(&self, user: &Pokemon, target: &Pokemon) -> u32 {
+        match self.effect {
+            Some(MoveEffect::WeightBasedPower) => match target.form.weight_hg {
+                0..=99 => 20,
+                100..=249 => 40,
+                250..=499 => 60,
+                500..=999 => 80,
+                1000..=1999 => 100,
+                _ => 120,
+            },
+            Some(MoveEffect::WeightRatioPower) => {
+                let ratio = user.form.weight_hg / target.form.weight_hg.max(1);
+
+                match ratio {
+                    0..=1 => 40,
+                    2 => 60,
+                    3 => 80,
+                    4 => 100,
+                    _ => 120,
+                }
+            }
+            Some(MoveEffect::HappinessPower) => (u32::from(user.happiness) * 2 / 5).max(1),
+            Some(MoveEffect::InverseHappinessPower) => {
+                (u32::from(255 - user.happiness) * 2 / 5).max(1)
+            }
+            Some(MoveEffect::KnockOff) if target.item.is_some() => self.power * 3 / 2,
+            _ => self.power,
+        }
+    }
+}
+
+impl Display for PokeMove {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// Parses a move by name from the built-in registry.
+impl FromStr for PokeMove {
+    type Err = UnknownMoveError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        MoveRegistry::builtin()
+            .by_name(s)
+            .ok_or_else(|| UnknownMoveError(s.to_owned()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownMoveError(pub String);
+
+impl Display for UnknownMoveError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "Unknown move: {}", self.0)
+    }
+}
+
+/// Name-based lookup of moves, ignoring case, spaces and punctuation (so "U-turn" and "u turn"
+/// are the same move).
+#[derive(Debug, Clone, Default)]
+pub struct MoveRegistry {
+    moves: HashMap<String, PokeMove>,
+}
+
+impl MoveRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry containing every move the engine knows about. It's only built the first time
+    /// it's needed.
+    pub fn builtin() -> &'static Self {
+        static BUILTIN: OnceLock<MoveRegistry> = OnceLock::new();
+
+        BUILTIN.get_or_init(Self::build_builtin)
+    }
+
+    fn build_builtin() -> Self {
+        use MoveCategory::*;
+        use PokeType::*;
+
+        let mut registry = Self::new();
+
+        for pokemove in vec![
+            PokeMove::new("Tackle", Normal, Physical, 40, 35),
+            PokeMove::new("Quick Attack", Normal, Physical, 40, 30).with_priority(1),
+            PokeMove::new("Fake Out", Normal, Physical, 40, 10)
+                .with_priority(3)
+                .with_effect(MoveEffect::FirstTurnOnly)
+                .with_secondary(SecondaryEffect::Flinch, 100),
+            PokeMove::new("Swift", Normal, Special, 60, 20).never_misses(),
+            PokeMove::new("Metronome", Normal, Status, 0, 10)
+                .with_target(MoveTarget::SelfTarget)
+                .with_effect(MoveEffect::CallRandomMove),
+            PokeMove::new("Return", Normal, Physical, 0, 20)
+                .with_effect(MoveEffect::HappinessPower),
+            PokeMove::new("Frustration", Normal, Physical, 0, 20)
+                .with_effect(MoveEffect::InverseHappinessPower),
+            PokeMove::new("Hidden Power", Normal, Special, 60, 15)
+                .with_effect(MoveEffect::HiddenPower),
+            PokeMove::new("Weather Ball", Normal, Special, 50, 10)
+                .with_effect(MoveEffect::WeatherBall),
+            PokeMove::new("Substitute", Normal, Status, 0, 10)
+                .with_target(MoveTarget::SelfTarget)
+                .with_effect(MoveEffect::Substitute),
+            PokeMove::new("Thrash", Normal, Physical, 120, 10).with_effect(MoveEffect::Rampage),
+            PokeMove::new("Swords Dance", Normal, Status, 0, 20)
+                .with_target(MoveTarget::SelfTarget)
+                .with_effect(MoveEffect::SelfBoost(StatKind::Attack, 2)),
+            PokeMove::new("Flamethrower", Fire, Special, 90, 15),
+            PokeMove::new("Heat Crash", Fire, Physical, 0, 10)
+                .with_effect(MoveEffect::WeightRatioPower),
+            PokeMove::new("Grassy Terrain", Grass, Status, 0, 10)
+                .with_target(MoveTarget::SelfTarget)
+                .with_effect(MoveEffect::SetTerrain(Terrain::Grassy)),
+            PokeMove::new("Spore", Grass, Status, 0, 15).with_effect(MoveEffect::Sleep),
+            PokeMove::new("Leech Seed", Grass, Status, 0, 10)
+                .with_accuracy(90)
+                .with_effect(MoveEffect::LeechSeed),
+            PokeMove::new("Synthesis", Grass, Status, 0, 5)
+                .with_target(MoveTarget::SelfTarget)
+                .with_effect(MoveEffect::WeatherHeal),
+            PokeMove::new("Sunny Day", Fire, Status, 0, 5)
+                .with_target(MoveTarget::SelfTarget)
+                .with_effect(MoveEffect::SetWeather(Weather::Sun)),
+            PokeMove::new("Surf", Water, Special, 90, 15).with_target(MoveTarget::AllAdjacent),
+            PokeMove::new("Waterfall", Water, Physical, 80, 15)
+                .with_secondary(SecondaryEffect::Flinch, 20),
+            PokeMove::new("Rain Dance", Water, Status, 0, 5)
+                .with_target(MoveTarget::SelfTarget)
+                .with_effect(MoveEffect::SetWeather(Weather::Rain)),
+            PokeMove::new("Thunderbolt", Electric, Special, 90, 15),
+            PokeMove::new("Volt Switch", Electric, Special, 70, 20).with_effect(MoveEffect::Pivot),
+            PokeMove::new("Electric Terrain", Electric, Status, 0, 10)
+                .with_target(MoveTarget::SelfTarget)
+                .with_effect(MoveEffect::SetTerrain(Terrain::Electric)),
+            PokeMove::new("Energy Ball", Grass, Special, 90, 10),
+            PokeMove::new("Grass Knot", Grass, Special, 0, 20)
+                .with_effect(MoveEffect::WeightBasedPower),
+            PokeMove::new("Ice Beam", Ice, Special, 90, 10),
+            PokeMove::new("Hail", Ice, Status, 0, 10)
+                .with_target(MoveTarget::SelfTarget)
+                .with_effect(MoveEffect::SetWeather(Weather::Hail)),
+            PokeMove::new("Quick Guard", Fighting, Status, 0, 15)
+                .with_target(MoveTarget::SelfTarget)
+                .with_effect(MoveEffect::QuickGuard)
+                .with_priority(3),
+            PokeMove::new("Low Kick", Fighting, Physical, 0, 20)
+                .with_effect(MoveEffect::WeightBasedPower),
+            PokeMove::new("Earthquake", Ground, Physical, 100, 10)
+                .with_target(MoveTarget::AllAdjacent),
+            PokeMove::new("Psychic", Psychic, Special, 90, 10),
+            PokeMove::new("Psychic Terrain", Psychic, Status, 0, 10)
+                .with_target(MoveTarget::SelfTarget)
+                .with_effect(MoveEffect::SetTerrain(Terrain::Psychic)),
+            PokeMove::new("Light Screen", Psychic, Status, 0, 30)
+                .with_target(MoveTarget::SelfTarget)
+                .with_effect(MoveEffect::Screen(Screen::LightScreen)),
+            PokeMove::new("Reflect", Psychic, Status, 0, 20)
+                .with_target(MoveTarget::SelfTarget)
+                .with_effect(MoveEffect::Screen(Screen::Reflect)),
+            PokeMove::new("Recover", Normal, Status, 0, 5)
+                .with_target(MoveTarget::SelfTarget)
+                .with_effect(MoveEffect::Heal(0.5)),
+            PokeMove::new("Roost", Flying, Status, 0, 5)
+                .with_target(MoveTarget::SelfTarget)
+                .with_effect(MoveEffect::Roost),
+            PokeMove::new("Rest", Psychic, Status, 0, 5)
+                .with_target(MoveTarget::SelfTarget)
+                .with_effect(MoveEffect::Rest),
+            PokeMove::new("Trick", Psychic, Status, 0, 10).with_effect(MoveEffect::SwapItems),
+            PokeMove::new("Agility", Psychic, Status, 0, 30)
+                .with_target(MoveTarget::SelfTarget)
+                .with_effect(MoveEffect::SelfBoost(StatKind::Speed, 2)),
+            PokeMove::new("U-turn", Bug, Physical, 70, 20).with_effect(MoveEffect::Pivot),
+            PokeMove::new("Rock Slide", Rock, Physical, 75, 10)
+                .with_accuracy(90)
+                .with_target(MoveTarget::AllOpponents)
+                .with_secondary(SecondaryEffect::Flinch, 30),
+            PokeMove::new("Sandstorm", Rock, Status, 0, 10)
+                .with_target(MoveTarget::SelfTarget)
+                .with_effect(MoveEffect::SetWeather(Weather::Sandstorm)),
+            PokeMove::new("Shadow Ball", Ghost, Special, 80, 15),
+            PokeMove::new("Dragon Claw", Dragon, Physical, 80, 15),
+            PokeMove::new("Outrage", Dragon, Physical, 120, 10).with_effect(MoveEffect::Rampage),
+            PokeMove::new("Switcheroo", Dark, Status, 0, 10).with_effect(MoveEffect::SwapItems),
+            PokeMove::new("Crunch", Dark, Physical, 80, 15),
+            PokeMove::new("Taunt", Dark, Status, 0, 20).with_effect(MoveEffect::Taunt),
+            PokeMove::new("Knock Off", Dark, Physical, 65, 20).with_effect(MoveEffect::KnockOff),
+            PokeMove::new("Heavy Slam", Steel, Physical, 0, 10)
+                .with_effect(MoveEffect::WeightRatioPower),
+            PokeMove::new("Iron Head", Steel, Physical, 80, 15)
+                .with_secondary(SecondaryEffect::Flinch, 30),
+            PokeMove::new("Moonblast", Fairy, Special, 95, 15),
+            PokeMove::new("Misty Terrain", Fairy, Status, 0, 10)
+                .with_target(MoveTarget::SelfTarget)
+                .with_effect(MoveEffect::SetTerrain(Terrain::Misty)),
+        ] {
+            registry.register(pokemove);
+        }
+
+        registry
+    }
+
+    pub fn register(&mut self, pokemove: PokeMove) {
+        self.moves.insert(normalize_name(&pokemove.name), pokemove);
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<PokeMove> {
+        self.moves.get(&normalize_name(name)).cloned()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &PokeMove> {
+        self.moves.values()
+    }
+
+    /// The moves that Metronome can call, sorted by name so the choice is reproducible.
+    pub fn callable_moves(&self) -> Vec<PokeMove> {
+        let mut moves: Vec<_> = self
+            .iter()
+            .filter(|m| !UNCALLABLE_MOVES.contains(&normalize_name(&m.name).as_str()))
+            .cloned()
+            .collect();
+
+        moves.sort_by(|a, b| a.name.cmp(&b.name));
+        moves
+    }
+}
+
+/// Normalized names of moves that can't be called by Metronome: itself, Struggle, protecting moves
+/// and item-stealing moves.
+const UNCALLABLE_MOVES: &[&str] = &["metronome", "struggle", "quickguard", "trick", "switcheroo"];
+
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MoveCategory {
+    Physical,
+    Special,
+    Status,
+}
+
+/// Which pokemon a move affects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MoveTarget {
+    /// A single opponent, or in doubles any other pokemon the user picks.
+    #[default]
+    Opponent,
+    /// The user, its side of the field or the whole field (Swords Dance, Reflect, Rain Dance).
+    SelfTarget,
+    /// Every opposing pokemon (Rock Slide).
+    AllOpponents,
+    /// Every other pokemon on the field, allies included (Earthquake, Surf).
+    AllAdjacent,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MoveEffect {
+    /// Power scales with the target's weight (Low Kick, Grass Knot).
+    WeightBasedPower,
+    /// Power scales with the user's weight relative to the target's (Heavy Slam, Heat Crash).
+    WeightRatioPower,
+    /// Power scales with the user's happiness (Return).
+    HappinessPower,
+    /// Power scales inversely with the user's happiness (Frustration).
+    InverseHappinessPower,
+    /// Changes the user's stat stages (Swords Dance).
+    SelfBoost(StatKind, i8),
+    /// Starts the given weather for 5 turns (Rain Dance, Sunny Day).
+    SetWeather(Weather),
+    /// Starts the given terrain for 5 turns (Electric Terrain, Grassy Terrain).
+    SetTerrain(Terrain),
+    /// Locks the user into the move for 2-3 turns, then confuses it (Outrage, Thrash).
+    Rampage,
+    /// Fails unless it's the user's first turn in battle since switching in (Fake Out).
+    FirstTurnOnly,
+    /// Sets up a screen on the user's side for 5 turns (Reflect, Light Screen).
+    Screen(Screen),
+    /// Heals the user by the given fraction of its max HP (Recover).
+    Heal(f64),
+    /// Heals the user by half its max HP, more in sun and less in other weather (Synthesis).
+    WeatherHeal,
+    /// Heals the user by half its max HP and removes its Flying type for the turn (Roost).
+    Roost,
+    /// Protects the user's side from priority moves for the rest of the turn (Quick Guard).
+    QuickGuard,
+    /// Puts the target to sleep for 1-3 turns (Spore).
+    Sleep,
+    /// Fully heals the user and puts it to sleep for two turns (Rest).
+    Rest,
+    /// Switches the user out after it hits (U-turn, Volt Switch).
+    Pivot,
+    /// Knocks away the target's item, with 1.5x power if it was holding one (Knock Off).
+    KnockOff,
+    /// Uses a random move instead (Metronome).
+    CallRandomMove,
+    /// Swaps the held items of the user and the target (Trick, Switcheroo).
+    SwapItems,
+    /// Puts a quarter of the user's max HP into a substitute that takes hits in its place
+    /// (Substitute).
+    Substitute,
+    /// Takes its type from the user's IVs.
+    HiddenPower,
+    /// Takes its type from the current weather.
+    WeatherBall,
+    /// Ignores type matchups and makes the user lose a quarter of its max HP (Struggle).
+    Struggle,
+    /// Seeds the target, draining its HP every turn. Grass types are immune (Leech Seed).
+    LeechSeed,
+    /// Stops the target from using status moves for 3 turns (Taunt).
+    Taunt,
+}
+
+/// An effect that may trigger when a damaging move hits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Secondary {
+    pub effect: SecondaryEffect,
+    /// Percent chance of triggering.
+    pub chance: u8,
+}
+
+/// A side-wide barrier that halves damage from moves of one category.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Screen {
+    Reflect,
+    LightScreen,
+}
+
+impl Screen {
+    /// The category of moves this screen weakens.
+    pub fn category(self) -> MoveCategory {
+        match self {
+            Screen::Reflect => MoveCategory::Physical,
+            Screen::LightScreen => MoveCategory::Special,
+        }
+    }
+}
+
+impl Display for Screen {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Screen::Reflect => write!(f, "Reflect"),
+            Screen::LightScreen => write!(f, "Light Screen"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SecondaryEffect {
+    /// The target loses its action this turn, if it hasn't moved yet.
+    Flinch,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{
+        pokemon::{PokemonForm, Stats},
+        testing,
+    };
+
+    fn weighing(weight_hg: u32) -> Pokemon {
+        let mut pokemon = testing::builder("Target", &[PokeType::Normal], &[]).build();
+        pokemon.form = Rc::new(PokemonForm {
+            weight_hg,
+            ..(*pokemon.form).clone()
+        });
+        pokemon
+    }
+
+    #[test]
+    fn low_kick_scales_with_target_weight() {
+        let low_kick: PokeMove = "Low Kick".parse().unwrap();
+        let user = weighing(500);
+
+        assert_eq!(low_kick.power_against(&user, &weighing(10)), 20);
+        assert_eq!(low_kick.power_against(&user, &weighing(4000)), 120);
+    }
+
+    #[test]
+    fn heavy_slam_scales_with_weight_ratio() {
+        let heavy_slam: PokeMove = "Heavy Slam".parse().unwrap();
+
+        assert_eq!(heavy_slam.power_against(&weighing(500), &weighing(500)), 40);
+        assert_eq!(
+            heavy_slam.power_against(&weighing(2500), &weighing(500)),
+            120
+        );
+    }
+
+    #[test]
+    fn return_and_frustration_scale_with_happiness() {
+        let return_move: PokeMove = "Return".parse().unwrap();
+        let frustration: PokeMove = "Frustration".parse().unwrap();
+        let target = weighing(500);
+        let with_happiness = |happiness| {
+            testing::builder("User", &[PokeType::Normal], &[])
+                .happiness(happiness)
+                .build()
+        };
+
+        assert_eq!(
+            return_move.power_against(&with_happiness(255), &target),
+            102
+        );
+        assert_eq!(return_move.power_against(&with_happiness(0), &target), 1);
+        assert_eq!(frustration.power_against(&with_happiness(0), &target), 102);
+        assert_eq!(frustration.power_against(&with_happiness(255), &target), 1);
+    }
+
+    #[test]
+    fn registries_look_moves_up_ignoring_case_and_punctuation() {
+        let mut registry = MoveRegistry::new();
+        registry.register(PokeMove::new(
+            "Will-O-Wisp",
+            PokeType::Fire,
+            MoveCategory::Status,
+            0,
+            15,
+        ));
+
+        assert_eq!(
+            registry.by_name("will o wisp").map(|m| m.name),
+            Some("Will-O-Wisp".to_owned())
+        );
+        assert_eq!(registry.by_name("Tackle"), None);
+    }
+
+    #[test]
+    fn builtin_moves_parse_from_their_display_name() {
+        let u_turn: PokeMove = "u turn".parse().unwrap();
+
+        assert_eq!(u_turn.to_string(), "U-turn");
+        assert_eq!(u_turn.to_string().parse::<PokeMove>(), Ok(u_turn));
+        assert_eq!(
+            "Splash".parse::<PokeMove>(),
+            Err(UnknownMoveError("Splash".to_owned()))
+        );
+    }
+
+    #[test]
+    fn hidden_power_and_weather_ball_change_type() {
+        let hidden_power: PokeMove = "Hidden Power".parse().unwrap();
+        let weather_ball: PokeMove = "Weather Ball".parse().unwrap();
+        let user = testing::builder("Castform", &[PokeType::Normal], &[])
+            .iv(Stats {
+                attack: 30,
+                defense: 30,
+                ..Stats::uniform(31)
+            })
+            .build();
+
+        assert_eq!(
+            hidden_power.effective_type(&user, Weather::Clear),
+            PokeType::Ice
+        );
+        assert_eq!(
+            weather_ball.effective_type(&user, Weather::Clear),
+            PokeType::Normal
+        );
+        assert_eq!(
+            weather_ball.effective_type(&user, Weather::Sun),
+            PokeType::Fire
+        );
+    }
+
+    #[test]
+    fn categories_pick_the_stats_they_use() {
+        let stats = |name: &str| {
+            let pokemove: PokeMove = name.parse().unwrap();
+            (pokemove.attacking_stat(), pokemove.defending_stat())
+        };
+
+        assert_eq!(
+            stats("Tackle"),
+            (Some(StatKind::Attack), Some(StatKind::Defense))
+        );
+        assert_eq!(
+            stats("Thunderbolt"),
+            (
+                Some(StatKind::SpecialAttack),
+                Some(StatKind::SpecialDefense)
+            )
+        );
+        assert_eq!(stats("Swords Dance"), (None, None));
+    }
+}
@@ -0,0 +1,109 @@
+use std::fmt::{self, Display, Formatter};
+
+use crate::{
+    pokemon::{InvalidGenderError, Pokemon, Team},
+    state::RngBehavior,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Format-specific settings for a battle.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BattleRules {
+    /// If set, every pokemon is brought to exactly this level for the battle, as in formats that
+    /// normalize everyone to level 50 or 100.
+    pub level_cap: Option<u8>,
+    /// A player can't put more than one opposing pokemon to sleep at a time.
+    pub sleep_clause: bool,
+    /// A team can't have two pokemon of the same species.
+    pub species_clause: bool,
+    /// How long weather set by a move lasts, before items like Damp Rock extend it.
+    pub weather_turns: u8,
+    /// Whether moves can land critical hits.
+    pub crits: bool,
+    /// Whether damage varies randomly from 85% to 100%, instead of always being the maximum.
+    ///
+    /// Every hit branches 16 ways, which makes the tree a lot bigger, so searches may want to turn
+    /// this off.
+    pub damage_rolls: bool,
+    /// If set, the battle stops after this many turns and goes to whoever has the larger share of
+    /// their team's HP left, or ends in a tie if that's even.
+    pub max_turns: Option<u32>,
+    /// Whether random events like accuracy checks branch over every outcome, or only follow the
+    /// most likely one.
+    pub rng_behavior: RngBehavior,
+}
+
+impl Default for BattleRules {
+    fn default() -> Self {
+        Self {
+            level_cap: None,
+            sleep_clause: false,
+            species_clause: false,
+            weather_turns: 5,
+            crits: true,
+            damage_rolls: true,
+            max_turns: None,
+            rng_behavior: RngBehavior::Branching,
+        }
+    }
+}
+
+impl BattleRules {
+    /// Adjusts a pokemon to these rules before the battle starts.
+    pub fn prepare(&self, pokemon: &mut Pokemon) {
+        if let Some(level) = self.level_cap {
+            pokemon.level = level;
+            pokemon.current_hp = pokemon.computed_stats().hp;
+        }
+    }
+
+    /// Checks that a team is allowed under these rules.
+    pub fn check_team(&self, team: &Team) -> Result<(), RulesViolation> {
+        for pokemon in team.iter() {
+            pokemon
+                .validate_gender()
+                .map_err(RulesViolation::InvalidGender)?;
+        }
+
+        if self.species_clause {
+            for (i, pokemon) in team.iter().enumerate() {
+                let species = &pokemon.form.species;
+
+                if team[..i]
+                    .iter()
+                    .any(|p| p.form.species.national_dex_no == species.national_dex_no)
+                {
+                    return Err(RulesViolation::DuplicateSpecies(species.name.clone()));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RulesViolation {
+    /// The team has more than one pokemon of this species, breaking Species Clause.
+    DuplicateSpecies(String),
+    /// A pokemon has a gender its form can't have.
+    InvalidGender(InvalidGenderError),
+    /// A player's chosen lead isn't on their team.
+    InvalidLead { player: String, lead: usize },
+}
+
+impl Display for RulesViolation {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            RulesViolation::DuplicateSpecies(species) => {
+                write!(f, "Species Clause: more than one {} on the team", species)
+            }
+            RulesViolation::InvalidGender(error) => write!(f, "{}", error),
+            RulesViolation::InvalidLead { player, lead } => {
+                write!(f, "{} can't lead with pokemon {}", player, lead)
+            }
+        }
+    }
+}
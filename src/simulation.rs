@@ -0,0 +1,232 @@
+use rand::Rng;
+#[cfg(feature = "rayon")]
+use rand::{rngs::StdRng, SeedableRng};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::{
+    battle::Battle,
+    pokemon::Team,
+    rules::{BattleRules, RulesViolation},
+    single::{Player, State},
+    state::{Decision, DecisionPolicy},
+};
+
+/// How many turns a simulated battle can last before it goes to the HP tiebreak, so that stall
+/// matchups can't go on forever.
+pub const MAX_TURNS: u32 = 1000;
+
+/// The results of playing the same matchup many times.
+#[derive(Debug, Clone, Default)]
+pub struct MatchupStats {
+    pub player_1_wins: usize,
+    pub player_2_wins: usize,
+    pub ties: usize,
+    /// The mean number of turns a battle lasted.
+    pub average_turns: f64,
+}
+
+impl MatchupStats {
+    pub fn games(&self) -> usize {
+        self.player_1_wins + self.player_2_wins + self.ties
+    }
+
+    /// The fraction of games won by player 1.
+    pub fn player_1_win_rate(&self) -> f64 {
+        match self.games() {
+            0 => 0.0,
+            games => self.player_1_wins as f64 / games as f64,
+        }
+    }
+}
+
+/// Plays `games` battles between `player_1_team` and `player_2_team`, starting each one with
+/// freshly reset teams, and tallies up the results. Battles are cut off after `MAX_TURNS` turns.
+///
+/// Fails if either team breaks the default rules.
+pub fn simulate_matchup<P1, P2, R>(
+    player_1_team: Team,
+    player_2_team: Team,
+    games: usize,
+    player_1_policy: &mut P1,
+    player_2_policy: &mut P2,
+    rng: &mut R,
+) -> Result<MatchupStats, RulesViolation>
+where
+    P1: DecisionPolicy<State>,
+    P2: DecisionPolicy<State>,
+    R: Rng,
+{
+    let mut policy = SplitPolicy {
+        player_1: player_1_policy,
+        player_2: player_2_policy,
+    };
+    let results = (0..games)
+        .map(|_| {
+            play_game(
+                player_1_team.clone(),
+                player_2_team.clone(),
+                &mut policy,
+                rng,
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(tally(&results))
+}
+
+/// Like `simulate_matchup`, but plays the games on rayon's thread pool.
+///
+/// Teams and policies can't be sent between threads, so every game builds its own with `teams`
+/// and `policies`. Both are handed the game's seed, which also seeds its chances, so the results
+/// only depend on `seed`.
+#[cfg(feature = "rayon")]
+pub fn simulate_matchup_parallel<T, M, P1, P2>(
+    teams: T,
+    games: usize,
+    policies: M,
+    seed: u64,
+) -> Result<MatchupStats, RulesViolation>
+where
+    T: Fn(u64) -> (Team, Team) + Sync,
+    M: Fn(u64) -> (P1, P2) + Sync,
+    P1: DecisionPolicy<State>,
+    P2: DecisionPolicy<State>,
+{
+    let results = (0..games as u64)
+        .into_par_iter()
+        .map(|game| {
+            let game_seed = seed.wrapping_add(game);
+            let (player_1_team, player_2_team) = teams(game_seed);
+            let (mut player_1, mut player_2) = policies(game_seed);
+            let mut policy = SplitPolicy {
+                player_1: &mut player_1,
+                player_2: &mut player_2,
+            };
+
+            play_game(
+                player_1_team,
+                player_2_team,
+                &mut policy,
+                &mut StdRng::seed_from_u64(game_seed),
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(tally(&results))
+}
+
+/// Plays a single battle, and gives back its winner and how many turns it lasted.
+fn play_game<P, R>(
+    player_1_team: Team,
+    player_2_team: Team,
+    policy: &mut P,
+    rng: &mut R,
+) -> Result<(Option<Player>, u32), RulesViolation>
+where
+    P: DecisionPolicy<State>,
+    R: Rng,
+{
+    let rules = BattleRules {
+        max_turns: Some(MAX_TURNS),
+        ..BattleRules::default()
+    };
+    let state = State::start_with_rules(player_1_team, player_2_team, rules)?.play(policy, rng);
+
+    Ok((state.winner(), state.turn()))
+}
+
+fn tally(results: &[(Option<Player>, u32)]) -> MatchupStats {
+    let mut stats = MatchupStats::default();
+
+    for &(winner, _) in results {
+        match winner {
+            Some(Player::Player1) => stats.player_1_wins += 1,
+            Some(Player::Player2) => stats.player_2_wins += 1,
+            None => stats.ties += 1,
+        }
+    }
+
+    if !results.is_empty() {
+        let total_turns: u64 = results.iter().map(|&(_, turns)| u64::from(turns)).sum();
+        stats.average_turns = total_turns as f64 / results.len() as f64;
+    }
+
+    stats
+}
+
+/// Hands each decision to the policy of the player making it.
+struct SplitPolicy<'a, P1, P2> {
+    player_1: &'a mut P1,
+    player_2: &'a mut P2,
+}
+
+impl<'a, P1, P2> DecisionPolicy<State> for SplitPolicy<'a, P1, P2>
+where
+    P1: DecisionPolicy<State>,
+    P2: DecisionPolicy<State>,
+{
+    fn choose(&mut self, state: &State, decision: &Decision<State>) -> usize {
+        match decision.player() {
+            Player::Player1 => self.player_1.choose(state, decision),
+            Player::Player2 => self.player_2.choose(state, decision),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::{driver::RandomPolicy, pokemon::PokeType, testing};
+
+    fn teams() -> (Team, Team) {
+        let pokemon = |name| testing::builder(name, &[PokeType::Normal], &["Tackle"]).build();
+
+        (
+            testing::team(vec![pokemon("Rattata")]),
+            testing::team(vec![pokemon("Pidgey")]),
+        )
+    }
+
+    #[test]
+    fn every_game_is_tallied() {
+        let (player_1_team, player_2_team) = teams();
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut player_1 = RandomPolicy::new(StdRng::seed_from_u64(2));
+        let mut player_2 = RandomPolicy::new(StdRng::seed_from_u64(3));
+
+        let stats = simulate_matchup(
+            player_1_team,
+            player_2_team,
+            20,
+            &mut player_1,
+            &mut player_2,
+            &mut rng,
+        )
+        .unwrap();
+
+        assert_eq!(stats.games(), 20);
+        assert!(stats.average_turns >= 1.0);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_runs_only_depend_on_the_seed() {
+        let policies = |seed: u64| {
+            (
+                RandomPolicy::new(StdRng::seed_from_u64(seed)),
+                RandomPolicy::new(StdRng::seed_from_u64(!seed)),
+            )
+        };
+        let run = || simulate_matchup_parallel(|_| teams(), 20, policies, 7).unwrap();
+        let (first, second) = (run(), run());
+
+        assert_eq!(first.games(), 20);
+        assert_eq!(
+            (first.player_1_wins, first.ties, first.average_turns),
+            (second.player_1_wins, second.ties, second.average_turns)
+        );
+    }
+}
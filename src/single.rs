@@ -1,14 +1,53 @@
+use std::{cmp::Reverse, fmt, rc::Rc};
+
 use strum_macros::Display;
 
 use crate::{
-    pokemon::{Pokemon, Team},
-    state::{self, DecisionBuilder, EventHandler, PlayerBase, PlayerStateBase, StateBase},
+    battle::Battle,
+    damage,
+    effect::Effect,
+    item::Item,
+    log::LogEvent,
+    pokemon::{
+        self, PokeType, Pokemon, PokemonSpecies, StatKind, StatStages, Team, TypeEffectiveness,
+        UnknownFormError,
+    },
+    pokemove::{
+        MoveCategory, MoveEffect, MoveRegistry, MoveTarget, PokeMove, Screen, Secondary,
+        SecondaryEffect,
+    },
+    rules::{BattleRules, RulesViolation},
+    state::{
+        self, ChanceBuilder, DecisionBuilder, EventHandler, PlayerBase, PlayerStateBase,
+        RngBehavior, StateBase,
+    },
+    status::Status,
+    terrain::Terrain,
+    weather::Weather,
 };
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct State {
     player_1: PlayerState,
     player_2: PlayerState,
+    pub weather: Weather,
+    /// Turns left before the weather clears.
+    pub weather_turns: u8,
+    pub terrain: Terrain,
+    pub terrain_turns: u8,
+    pub rules: BattleRules,
+    turn: u32,
+    winner: Option<Player>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    log_events: Vec<LogEvent>,
+    /// Hooks to run once the current action or end of turn is over.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pending_hooks: Vec<PendingHook>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    handlers: Handlers,
 }
 
 impl StateBase for State {
@@ -28,14 +67,199 @@ impl StateBase for State {
             Player::Player2 => &mut self.player_2,
         }
     }
+
+    fn drain_log(&mut self) -> Vec<LogEvent> {
+        self.log_events.drain(..).collect()
+    }
+
+    fn rng_behavior(&self) -> RngBehavior {
+        self.rules.rng_behavior
+    }
+}
+
+impl Battle for State {
+    type Position = Player;
+
+    const DOUBLES: bool = false;
+
+    fn side_of(player: Player) -> Player {
+        player
+    }
+
+    fn side(&self, player: Player) -> &SideConditions {
+        &self.player(player).side
+    }
+
+    fn side_mut(&mut self, player: Player) -> &mut SideConditions {
+        &mut self.player_mut(player).side
+    }
+
+    fn rules(&self) -> &BattleRules {
+        &self.rules
+    }
+
+    fn turn(&self) -> u32 {
+        self.turn
+    }
+
+    fn team(&self, player: Player) -> &Team {
+        &self.player(player).team
+    }
+
+    fn pokemon(&self, player: Player) -> Option<&Pokemon> {
+        self.player(player).active_pokemon()
+    }
+
+    fn pokemon_mut(&mut self, player: Player) -> Option<&mut Pokemon> {
+        self.player_mut(player).active_pokemon_mut()
+    }
+
+    fn opponents(&self, player: Player) -> Vec<Player> {
+        let opponent = player.opponent();
+
+        match self.pokemon(opponent) {
+            Some(pokemon) if !pokemon.is_fainted() => vec![opponent],
+            _ => Vec::new(),
+        }
+    }
+
+    fn boost(&mut self, player: Player, kind: StatKind, stages: i8) -> i8 {
+        let player_state = self.player_mut(player);
+        let pokemon = player_state.active_pokemon().unwrap();
+        let stages = pokemon.ability.modify_boost(stages);
+        let name = pokemon.to_string();
+
+        let changed = player_state.stat_stages.boost(kind, stages);
+
+        self.log(LogEvent::StatChanged {
+            pokemon: name,
+            stat: kind,
+            stages: changed,
+        });
+
+        changed
+    }
+
+    fn apply_status(&mut self, target: Player, status: Status) -> bool {
+        if !self.can_inflict_status(target, status) {
+            return false;
+        }
+
+        let target_state = self.player_mut(target);
+
+        if let Status::Sleep { .. } = status {
+            target_state.slept_by_opponent = target_state.active_pokemon_idx;
+        }
+
+        let idx = target_state.active_pokemon_idx.unwrap();
+        let pokemon = target_state.active_pokemon_mut().unwrap();
+        pokemon.status = status;
+
+        let pokemon = pokemon.to_string();
+        self.log(LogEvent::StatusApplied { pokemon, status });
+        self.pending_hooks.push(PendingHook::StatusApplied {
+            target,
+            idx,
+            status,
+        });
+
+        true
+    }
+
+    fn stat_stages(&self, player: Player) -> &StatStages {
+        &self.player(player).stat_stages
+    }
+
+    fn active_types(&self, player: Player) -> Vec<PokeType> {
+        let player_state = self.player(player);
+        let types = &player_state.active_pokemon().unwrap().form.types;
+
+        if !player_state.roosted {
+            return types.clone();
+        }
+
+        // A roosting pure Flying type is treated as Normal
+        let grounded: Vec<_> = types
+            .iter()
+            .copied()
+            .filter(|&t| t != PokeType::Flying)
+            .collect();
+
+        if grounded.is_empty() {
+            vec![PokeType::Normal]
+        } else {
+            grounded
+        }
+    }
+
+    fn weather(&self) -> Weather {
+        self.weather
+    }
+
+    fn terrain(&self) -> Terrain {
+        self.terrain
+    }
+
+    fn speed(&self, player: Player) -> u32 {
+        let player_state = self.player(player);
+        let pokemon = player_state
+            .active_pokemon()
+            .unwrap_or_else(|| panic!("No active pokemon for {}", player));
+
+        pokemon.effective_speed(
+            &player_state.stat_stages,
+            pokemon.item.as_ref(),
+            self.weather,
+        )
+    }
+
+    fn deal_indirect_damage(&mut self, target: Player, amount: u32) {
+        let pokemon = self.player_mut(target).active_pokemon_mut().unwrap();
+        let ability = pokemon.ability;
+        let amount = pokemon.take_indirect_damage(amount, ability);
+
+        if amount > 0 {
+            self.log_damage(target, amount, TypeEffectiveness::Regular);
+        }
+    }
+
+    fn log(&mut self, event: LogEvent) {
+        self.log_events.push(event);
+    }
+
+    fn handlers(&self) -> Vec<Rc<dyn EventHandler<Self>>> {
+        self.handlers.0.clone()
+    }
 }
 
-#[derive(Debug, Clone, Copy, Display)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Display)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Player {
     Player1,
     Player2,
 }
 
+impl Player {
+    pub fn opponent(self) -> Player {
+        match self {
+            Player::Player1 => Player::Player2,
+            Player::Player2 => Player::Player1,
+        }
+    }
+
+    /// The player whose pokemon a move with `target` used by this player's pokemon affects.
+    ///
+    /// With only one pokemon per side, spread moves hit the same pokemon a single target would.
+    pub fn target(self, target: MoveTarget) -> Player {
+        match target {
+            MoveTarget::SelfTarget => self,
+            MoveTarget::Opponent | MoveTarget::AllOpponents | MoveTarget::AllAdjacent => {
+                self.opponent()
+            }
+        }
+    }
+}
+
 impl PlayerBase for Player {
     fn values() -> &'static [Self] {
         &[Player::Player1, Player::Player2]
@@ -43,47 +267,286 @@ impl PlayerBase for Player {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct PlayerState {
     pub active_pokemon_idx: Option<usize>,
     pub turn_action: Option<Action>,
     pub team: Team,
+    pub stat_stages: StatStages,
+    /// Effects on the active pokemon, which go away when it switches out.
+    pub effects: Vec<Effect>,
+    /// The index of the move the active pokemon is locked into, and for how many more turns.
+    pub rampage: Option<(usize, u8)>,
+    /// Whether the active pokemon flinched and loses its action this turn.
+    pub flinched: bool,
+    /// How many turns the active pokemon has started in battle, counting the current one. One
+    /// that came in during a turn hasn't started any yet.
+    pub turns_active: u32,
+    /// The HP left on the active pokemon's substitute, if it has one.
+    pub substitute_hp: Option<u32>,
+    pub side: SideConditions,
+    /// Whether the active pokemon used Roost this turn and lost its Flying type.
+    pub roosted: bool,
+    last_move: Option<usize>,
+    /// The index of the move the active pokemon is locked into by its Choice item.
+    pub choice_lock: Option<usize>,
+    /// The team index of the pokemon the opponent last put to sleep, for Sleep Clause.
+    pub slept_by_opponent: Option<usize>,
 }
 
 impl PlayerState {
-    fn active_pokemon(&self) -> Option<&Pokemon> {
+    fn new(team: Team) -> Self {
+        Self {
+            team,
+            active_pokemon_idx: None,
+            turn_action: None,
+            stat_stages: StatStages::default(),
+            effects: Vec::new(),
+            rampage: None,
+            flinched: false,
+            turns_active: 0,
+            substitute_hp: None,
+            side: SideConditions::default(),
+            roosted: false,
+            last_move: None,
+            choice_lock: None,
+            slept_by_opponent: None,
+        }
+    }
+
+    /// Clears everything tied to the active pokemon being in battle, as happens on switching out.
+    fn clear_volatiles(&mut self) {
+        self.stat_stages = StatStages::default();
+        self.effects.clear();
+        self.rampage = None;
+        self.flinched = false;
+        self.turns_active = 0;
+        self.substitute_hp = None;
+        self.roosted = false;
+        self.last_move = None;
+        self.choice_lock = None;
+    }
+
+    fn is_confused(&self) -> bool {
+        self.effects
+            .iter()
+            .any(|e| matches!(e, Effect::Confusion { .. }))
+    }
+
+    fn is_seeded(&self) -> bool {
+        self.effects.iter().any(|e| matches!(e, Effect::LeechSeed))
+    }
+
+    fn is_taunted(&self) -> bool {
+        self.effects
+            .iter()
+            .any(|e| matches!(e, Effect::Taunt { .. }))
+    }
+
+    /// The index of the last move the active pokemon used since switching in.
+    pub fn last_move(&self) -> Option<usize> {
+        self.last_move
+    }
+
+    /// The pokemon currently in battle, or `None` before one has been chosen.
+    pub fn active_pokemon(&self) -> Option<&Pokemon> {
         self.active_pokemon_idx.map(|idx| &self.team[idx])
     }
+
+    /// The pokemon currently in battle, or `None` before one has been chosen.
+    pub fn active_pokemon_mut(&mut self) -> Option<&mut Pokemon> {
+        let team = &mut self.team;
+        self.active_pokemon_idx.map(move |idx| &mut team[idx])
+    }
+}
+
+impl PlayerStateBase for PlayerState {
+    fn describe_changes(&self, before: &Self) -> Vec<String> {
+        let mut changes = Vec::new();
+
+        for (old, new) in before.team.iter().zip(&self.team) {
+            if old.current_hp != new.current_hp {
+                changes.push(format!(
+                    "{}: HP {} \u{2192} {}",
+                    new, old.current_hp, new.current_hp
+                ));
+            }
+
+            if old.status != new.status {
+                changes.push(format!(
+                    "{}: {:?} \u{2192} {:?}",
+                    new, old.status, new.status
+                ));
+            }
+
+            if old.item != new.item {
+                match &new.item {
+                    Some(item) => changes.push(format!("{}: now holding {}", new, item)),
+                    None => changes.push(format!("{}: lost its item", new)),
+                }
+            }
+        }
+
+        if before.active_pokemon_idx != self.active_pokemon_idx {
+            if let Some(pokemon) = self.active_pokemon() {
+                changes.push(format!("{}: switched in", pokemon));
+            }
+        }
+
+        changes
+    }
+}
+
+/// Conditions on a player's side of the field, which stay up when pokemon switch.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SideConditions {
+    pub reflect_turns: u8,
+    pub light_screen_turns: u8,
+    /// Whether Quick Guard is protecting this side from priority moves this turn.
+    pub quick_guard: bool,
 }
 
-impl PlayerStateBase for PlayerState {}
+impl SideConditions {
+    pub fn screen_turns(&self, screen: Screen) -> u8 {
+        match screen {
+            Screen::Reflect => self.reflect_turns,
+            Screen::LightScreen => self.light_screen_turns,
+        }
+    }
+
+    pub(crate) fn screen_turns_mut(&mut self, screen: Screen) -> &mut u8 {
+        match screen {
+            Screen::Reflect => &mut self.reflect_turns,
+            Screen::LightScreen => &mut self.light_screen_turns,
+        }
+    }
+
+    /// Whether a screen weakening moves of `category` is up.
+    pub fn is_screened(&self, category: MoveCategory) -> bool {
+        [Screen::Reflect, Screen::LightScreen]
+            .iter()
+            .any(|&screen| screen.category() == category && self.screen_turns(screen) > 0)
+    }
+}
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Action {
     UsePokeMove(usize),
     SwitchPokemon(usize),
+    /// Only offered when none of the active pokemon's moves can be used.
+    Struggle,
+}
+
+/// The handlers installed with [`State::add_handler`].
+#[derive(Clone, Default)]
+struct Handlers(Vec<Rc<dyn EventHandler<State>>>);
+
+impl fmt::Debug for Handlers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} handlers", self.0.len())
+    }
+}
+
+/// Something that happened to an active pokemon, which its handlers hear about once the action
+/// it happened during is over.
+#[derive(Debug, Clone)]
+enum PendingHook {
+    Damage {
+        target: Player,
+        /// The team index of the pokemon that took the damage.
+        idx: usize,
+        amount: u32,
+        /// Whether the damage brought the pokemon down to 0 HP.
+        fainted: bool,
+    },
+    StatusApplied {
+        target: Player,
+        idx: usize,
+        status: Status,
+    },
 }
 
 pub type Node = state::Node<State>;
 
 impl State {
-    pub fn start(player_1_team: Team, player_2_team: Team) -> Node {
-        let state = State {
-            player_1: PlayerState {
-                team: player_1_team,
-                active_pokemon_idx: None,
-                turn_action: None,
-            },
-            player_2: PlayerState {
-                team: player_2_team,
-                active_pokemon_idx: None,
-                turn_action: None,
-            },
-        };
+    /// Fails if either team breaks the default rules.
+    pub fn start(player_1_team: Team, player_2_team: Team) -> Result<Node, RulesViolation> {
+        Self::start_with_rules(player_1_team, player_2_team, BattleRules::default())
+    }
 
-        state
+    /// Fails if either team breaks `rules`.
+    pub fn start_with_rules(
+        player_1_team: Team,
+        player_2_team: Team,
+        rules: BattleRules,
+    ) -> Result<Node, RulesViolation> {
+        Ok(Self::new(player_1_team, player_2_team, rules)?
             .choose_starting_pokemon()
             .then(Self::initial_etb)
-            .then(Self::main_turn)
+            .then(Self::main_turn))
+    }
+
+    /// Starts a battle with predetermined leads, skipping the starting pokemon decisions.
+    ///
+    /// Fails if either team breaks `rules`, or if either lead is out of range. Every pokemon starts
+    /// at full HP, so no lead can be fainted.
+    pub fn start_with_leads(
+        player_1_team: Team,
+        player_2_team: Team,
+        player_1_lead: usize,
+        player_2_lead: usize,
+        rules: BattleRules,
+    ) -> Result<Node, RulesViolation> {
+        let mut state = Self::new(player_1_team, player_2_team, rules)?;
+
+        for (&player, &lead) in Player::values().iter().zip(&[player_1_lead, player_2_lead]) {
+            let player_state = state.player_mut(player);
+
+            if lead >= player_state.team.len() {
+                return Err(RulesViolation::InvalidLead {
+                    player: player.to_string(),
+                    lead,
+                });
+            }
+
+            player_state.active_pokemon_idx = Some(lead);
+            state.log_switch_in(player);
+        }
+
+        Ok(state.initial_etb().then(Self::main_turn))
+    }
+
+    /// Fails if either team breaks `rules`.
+    fn new(
+        mut player_1_team: Team,
+        mut player_2_team: Team,
+        rules: BattleRules,
+    ) -> Result<Self, RulesViolation> {
+        for team in &[&player_1_team, &player_2_team] {
+            rules.check_team(team)?;
+        }
+
+        for pokemon in player_1_team.iter_mut().chain(player_2_team.iter_mut()) {
+            rules.prepare(pokemon);
+            pokemon.reset_for_battle();
+        }
+
+        Ok(State {
+            player_1: PlayerState::new(player_1_team),
+            player_2: PlayerState::new(player_2_team),
+            weather: Weather::Clear,
+            weather_turns: 0,
+            terrain: Terrain::None,
+            terrain_turns: 0,
+            rules,
+            turn: 0,
+            winner: None,
+            log_events: Vec::new(),
+            pending_hooks: Vec::new(),
+            handlers: Handlers::default(),
+        })
     }
 
     fn choose_starting_pokemon(self) -> Node {
@@ -92,43 +555,218 @@ impl State {
                 .indexed_choices(state.player(player).team.iter())
                 .build(state, move |mut state, choice| {
                     state.player_mut(player).active_pokemon_idx = Some(choice);
+                    state.log_switch_in(player);
                     Node::pending(state)
                 })
         })
     }
 
     fn initial_etb(self) -> Node {
-        self.fold(Player::values(), |state, &player| state.pokemon_etb(player))
+        self.in_speed_order(Player::values().to_vec(), Self::pokemon_etb)
+    }
+
+    /// Picks a battle back up from a state saved once both leads are out, such as a deserialized
+    /// [`Node::state`](state::Node::state).
+    ///
+    /// Deserialized pokemon only know which form they are, so their forms are looked back up in
+    /// `species`. Actions chosen before saving are dropped and the current turn is played from
+    /// the start, or the last turn wrapped up if a fainted pokemon still needs replacing.
+    pub fn resume(mut self, species: &[Rc<PokemonSpecies>]) -> Result<Node, UnknownFormError> {
+        for &player in Player::values() {
+            let player_state = self.player_mut(player);
+            player_state.turn_action = None;
+            pokemon::resolve_team_forms(&mut player_state.team, species)?;
+        }
+
+        let needs_replacement = Player::values().iter().any(|&player| {
+            self.player(player)
+                .active_pokemon()
+                .is_some_and(|pokemon| pokemon.is_fainted())
+        });
+
+        Ok(if needs_replacement {
+            self.handle_faints().then(Self::main_turn)
+        } else {
+            self.play_turn()
+        })
+    }
+
+    fn main_turn(mut self) -> Node {
+        if self.turn_limit_reached() {
+            self.winner = self.hp_tiebreak();
+            return Node::end(self);
+        }
+
+        self.turn += 1;
+
+        for &player in Player::values() {
+            self.player_mut(player).turns_active += 1;
+        }
+
+        self.play_turn()
     }
 
-    fn main_turn(self) -> Node {
+    fn play_turn(self) -> Node {
         self.choose_actions()
             .then(Self::execute_actions)
+            .then(Self::end_turn)
+            .then(Self::handle_faints)
             .then(Self::main_turn)
     }
 
-    fn choose_actions(self) -> Node {
+    /// Installs `handler` to hear about what happens to every pokemon in the battle, after their
+    /// own abilities and items.
+    pub fn add_handler(&mut self, handler: Rc<dyn EventHandler<State>>) {
+        self.handlers.0.push(handler);
+    }
+
+    /// The winner of a finished battle, or `None` if it's still going or ended in a tie.
+    pub fn winner(&self) -> Option<Player> {
+        self.winner
+    }
+
+    /// Runs the hooks for anything that happened at the end of the turn, then ends the battle if
+    /// a side has run out of pokemon, and otherwise makes each player with a fainted active pokemon
+    /// send in a replacement.
+    fn handle_faints(self) -> Node {
+        self.run_hooks().then(Self::replace_fainted)
+    }
+
+    fn replace_fainted(mut self) -> Node {
+        if Player::values().iter().any(|&player| self.has_lost(player)) {
+            self.winner = self.check_win();
+            return Node::end(self);
+        }
+
         self.fold(Player::values(), |state, &player| {
+            let player_state = state.player(player);
+            let active_pokemon_idx = player_state.active_pokemon_idx.unwrap();
+
+            if !player_state.team[active_pokemon_idx].is_fainted() {
+                return Node::pending(state);
+            }
+
+            let switch_choices = player_state
+                .team
+                .iter()
+                .enumerate()
+                .filter(|(_, p)| !p.is_fainted())
+                .map(|(i, p)| (p.to_string(), i));
+
+            DecisionBuilder::new("Choose a replacement", player)
+                .named_choices(switch_choices)
+                .build(state, move |state, idx| state.switch_in(player, idx))
+        })
+    }
+
+    fn end_turn(mut self) -> Node {
+        if self.weather != Weather::Clear {
+            self.weather_turns = self.weather_turns.saturating_sub(1);
+
+            if self.weather_turns == 0 {
+                self.log(LogEvent::WeatherEnded(self.weather));
+                self.weather = Weather::Clear;
+            }
+        }
+
+        for &player in Player::values() {
+            let player_state = self.player_mut(player);
+            player_state.flinched = false;
+            player_state.roosted = false;
+            player_state.side.quick_guard = false;
+
+            self.tick_screens(player);
+            self.tick_effects(player);
+        }
+
+        let affected = Player::values()
+            .iter()
+            .copied()
+            .filter(|&player| self.has_residual(player))
+            .collect();
+
+        self.in_speed_order(affected, |mut state, player| {
+            state.leech_seed_residual(player);
+            state.weather_residual(player);
+            state.terrain_residual(player);
+            state.run_handlers(player, move |handler, state| {
+                handler.on_turn_end(state, player)
+            })
+        })
+        .then(Self::tick_terrain)
+    }
+
+    fn tick_terrain(mut self) -> Node {
+        if self.terrain != Terrain::None {
+            self.terrain_turns = self.terrain_turns.saturating_sub(1);
+
+            if self.terrain_turns == 0 {
+                self.log(LogEvent::TerrainEnded(self.terrain));
+                self.terrain = Terrain::None;
+            }
+        }
+
+        Node::pending(self)
+    }
+
+    fn choose_actions(self) -> Node {
+        self.fold(Player::values(), |mut state, &player| {
+            if let Some((move_idx, _)) = state.player(player).rampage {
+                state.player_mut(player).turn_action = Some(Action::UsePokeMove(move_idx));
+                return Node::pending(state);
+            }
+
             let player_state = state.player(player);
             let active_pokemon_idx = player_state.active_pokemon_idx.unwrap_or_else(|| {
                 panic!("No active pokemon for {} when choosing actions", player)
             });
 
-            let move_choices = player_state.team[active_pokemon_idx]
-                .moves
-                .iter()
-                .enumerate()
-                .map(|(i, m)| (m.to_string(), Action::UsePokeMove(i)));
+            let pokemon = &player_state.team[active_pokemon_idx];
+            let mut builder = DecisionBuilder::new("Choose your action", player);
+            let mut can_move = false;
+
+            for (i, pokemove) in pokemon.moves.iter().enumerate() {
+                let disabled = match (player_state.choice_lock, &pokemon.item) {
+                    (Some(locked), Some(item)) if locked != i => {
+                        Some(format!("Locked into {} by {}", pokemon.moves[locked], item))
+                    }
+                    _ if pokemove.current_pp == 0 => Some("No PP left".to_owned()),
+                    _ if pokemove.category == MoveCategory::Status && player_state.is_taunted() => {
+                        Some("Taunted".to_owned())
+                    }
+                    _ => None,
+                };
+
+                builder = match disabled {
+                    Some(reason) => builder.named_choice_disabled_with_meta(
+                        pokemove.to_string(),
+                        reason,
+                        pokemove.clone(),
+                    ),
+                    None => {
+                        can_move = true;
+                        builder.named_choice_with_meta(
+                            pokemove.to_string(),
+                            Action::UsePokeMove(i),
+                            pokemove.clone(),
+                        )
+                    }
+                };
+            }
+
+            if !can_move {
+                builder = builder.named_choice("Struggle", Action::Struggle);
+            }
 
             let switch_choices = player_state
                 .team
                 .iter()
                 .enumerate()
-                .filter(|(i, p)| *i != active_pokemon_idx && p.current_hp > 0)
+                .filter(|(i, p)| *i != active_pokemon_idx && !p.is_fainted())
                 .map(|(i, p)| (p.to_string(), Action::SwitchPokemon(i)));
 
-            DecisionBuilder::new("Choose your action", player)
-                .named_choices(move_choices.chain(switch_choices))
+            builder
+                .named_choices(switch_choices)
                 .build(state, move |mut state, choice| {
                     state.player_mut(player).turn_action = Some(choice);
                     Node::pending(state)
@@ -137,16 +775,2620 @@ impl State {
     }
 
     fn execute_actions(self) -> Node {
-        Node::pending(self)
+        self.in_order_by_key(
+            Player::values().to_vec(),
+            Self::action_key,
+            |state, player| state.execute_action(player).then(Self::run_hooks),
+        )
     }
 
-    fn pokemon_etb(self, player: Player) -> Node {
-        // ETB abilities
-        self.player(player)
-            .active_pokemon()
-            .unwrap()
-            .ability
-            .clone()
-            .on_etb(self)
+    fn execute_action(mut self, player: Player) -> Node {
+        match self.player_mut(player).turn_action.take() {
+            Some(Action::SwitchPokemon(idx)) => self.switch_in(player, idx),
+            Some(Action::UsePokeMove(move_idx)) => self.use_move(player, move_idx),
+            Some(Action::Struggle) => self.try_to_move(player, move |state| state.struggle(player)),
+            None => Node::pending(self),
+        }
+    }
+
+    /// Switches `player`'s active pokemon out for the one at `idx` in their team.
+    fn switch_in(self, player: Player, idx: usize) -> Node {
+        let node = if self.player(player).active_pokemon().unwrap().is_fainted() {
+            Node::pending(self)
+        } else {
+            self.run_handlers(player, move |handler, state| {
+                handler.on_switch_out(state, player)
+            })
+        };
+
+        node.then(move |mut state| {
+            let player_state = state.player_mut(player);
+            player_state.clear_volatiles();
+            player_state.active_pokemon_idx = Some(idx);
+
+            state.log_switch_in(player);
+            state.pokemon_etb(player)
+        })
+    }
+
+    fn use_move(self, player: Player, move_idx: usize) -> Node {
+        self.try_to_move(player, move |state| state.perform_move(player, move_idx))
+    }
+
+    /// Goes through everything that can stop `player`'s active pokemon from moving, calling
+    /// `perform` if none of it does.
+    fn try_to_move<F>(mut self, player: Player, perform: F) -> Node
+    where
+        F: FnOnce(State) -> Node + Clone + 'static,
+    {
+        if self.player(player).active_pokemon().unwrap().is_fainted() {
+            return Node::pending(self);
+        }
+
+        if !self.tick_sleep(player) {
+            return Node::pending(self);
+        }
+
+        let player_state = self.player(player);
+
+        if player_state.flinched {
+            let pokemon = player_state.active_pokemon().unwrap().to_string();
+            self.log(LogEvent::Flinched { pokemon });
+            return Node::pending(self);
+        }
+
+        if self.tick_confusion(player) {
+            ChanceBuilder::new("Confusion")
+                .named_possibilities(vec![
+                    ("Hurt itself", 1.0 / 3.0, true),
+                    ("Moves normally", 2.0 / 3.0, false),
+                ])
+                .build(self, move |state, hurt_itself| {
+                    if hurt_itself {
+                        // Hurting itself still uses up a turn of a rampage
+                        state
+                            .hurt_in_confusion(player)
+                            .then(move |state| state.tick_rampage(player))
+                    } else {
+                        perform(state)
+                    }
+                })
+        } else {
+            perform(self)
+        }
+    }
+
+    fn struggle(mut self, player: Player) -> Node {
+        let pokemove = PokeMove::struggle();
+
+        self.log(LogEvent::MoveUsed {
+            pokemon: self.player(player).active_pokemon().unwrap().to_string(),
+            pokemove: pokemove.to_string(),
+        });
+
+        self.resolve_move(player, pokemove)
+    }
+
+    fn perform_move(mut self, player: Player, move_idx: usize) -> Node {
+        let user = self.player(player).active_pokemon().unwrap();
+        let pokemove = user.moves[move_idx].clone();
+
+        self.log(LogEvent::MoveUsed {
+            pokemon: user.to_string(),
+            pokemove: pokemove.to_string(),
+        });
+
+        let player_state = self.player_mut(player);
+        player_state.last_move = Some(move_idx);
+
+        if let Some(item) = &player_state.active_pokemon().unwrap().item {
+            if item.is_choice() && player_state.choice_lock.is_none() {
+                player_state.choice_lock = Some(move_idx);
+            }
+        }
+
+        // Moves after the first turn of a rampage don't cost PP
+        if self.player(player).rampage.is_none() {
+            self.spend_pp(player, move_idx);
+        }
+
+        let starts_rampage = match pokemove.effect {
+            Some(MoveEffect::Rampage) => self.player(player).rampage.is_none(),
+            _ => false,
+        };
+
+        if starts_rampage {
+            ChanceBuilder::new("Rampage duration")
+                .named_possibilities(vec![("2 turns", 0.5, 2), ("3 turns", 0.5, 3)])
+                .build(self, move |mut state, turns| {
+                    state.player_mut(player).rampage = Some((move_idx, turns));
+                    state
+                        .resolve_move(player, pokemove)
+                        .then(move |state| state.tick_rampage(player))
+                })
+        } else {
+            self.resolve_move(player, pokemove)
+                .then(move |state| state.tick_rampage(player))
+        }
+    }
+
+    /// Spends a PP of the move `player`'s active pokemon is using, eating a Leppa Berry if it runs
+    /// out.
+    fn spend_pp(&mut self, player: Player, move_idx: usize) {
+        let pokemon = self.player_mut(player).active_pokemon_mut().unwrap();
+        let pokemove = &mut pokemon.moves[move_idx];
+        pokemove.use_pp();
+
+        if pokemove.current_pp > 0 {
+            return;
+        }
+
+        if let Some(Item::LeppaBerry) = pokemon.item {
+            pokemon.item = None;
+            pokemon.moves[move_idx].restore_pp(10);
+
+            let event = LogEvent::PpRestored {
+                pokemon: pokemon.to_string(),
+                pokemove: pokemon.moves[move_idx].to_string(),
+            };
+            self.log(event);
+        }
+    }
+
+    /// Counts down the sleep of `player`'s active pokemon as it tries to move.
+    ///
+    /// Returns whether it's awake and can move.
+    fn tick_sleep(&mut self, player: Player) -> bool {
+        let pokemon = self.player_mut(player).active_pokemon_mut().unwrap();
+
+        match &mut pokemon.status {
+            Status::Sleep { turns } if *turns > 0 => {
+                *turns -= 1;
+                let pokemon = pokemon.to_string();
+                self.log(LogEvent::FastAsleep { pokemon });
+                false
+            }
+            Status::Sleep { .. } => {
+                pokemon.status = Status::Healthy;
+                let pokemon = pokemon.to_string();
+                self.log(LogEvent::WokeUp { pokemon });
+                true
+            }
+            _ => true,
+        }
+    }
+
+    /// Counts down the rampage `player`'s active pokemon is locked into, confusing it at the end.
+    fn tick_rampage(mut self, player: Player) -> Node {
+        let player_state = self.player_mut(player);
+
+        match player_state.rampage {
+            Some((move_idx, turns)) if turns > 1 => {
+                player_state.rampage = Some((move_idx, turns - 1));
+                Node::pending(self)
+            }
+            Some(_) => {
+                player_state.rampage = None;
+                self.confuse(player)
+            }
+            None => Node::pending(self),
+        }
+    }
+
+    /// Confuses `player`'s active pokemon for 2-5 turns, unless it's fainted or already confused.
+    pub fn confuse(self, player: Player) -> Node {
+        let player_state = self.player(player);
+
+        if player_state.active_pokemon().unwrap().is_fainted() || player_state.is_confused() {
+            return Node::pending(self);
+        }
+
+        ChanceBuilder::new("Confusion duration")
+            .named_possibilities((2..=5).map(|turns| (format!("{} turns", turns), 0.25, turns)))
+            .build(self, move |mut state, turns| {
+                let pokemon = state.player(player).active_pokemon().unwrap().to_string();
+
+                state
+                    .player_mut(player)
+                    .effects
+                    .push(Effect::Confusion { turns });
+                state.log(LogEvent::Confused { pokemon });
+
+                Node::pending(state)
+            })
+    }
+
+    /// Counts down the confusion of `player`'s active pokemon as it tries to move.
+    ///
+    /// Returns whether it's still confused.
+    fn tick_confusion(&mut self, player: Player) -> bool {
+        let player_state = self.player_mut(player);
+        let pokemon = player_state.active_pokemon().unwrap().to_string();
+
+        let turns = player_state
+            .effects
+            .iter_mut()
+            .find_map(Effect::confusion_turns_mut);
+
+        match turns {
+            Some(turns) => {
+                *turns = turns.saturating_sub(1);
+
+                if *turns == 0 {
+                    player_state
+                        .effects
+                        .retain(|e| !matches!(e, Effect::Confusion { .. }));
+                    self.log(LogEvent::ConfusionEnded { pokemon });
+                    false
+                } else {
+                    self.log(LogEvent::Confused { pokemon });
+                    true
+                }
+            }
+            None => false,
+        }
+    }
+
+    fn hurt_in_confusion(mut self, player: Player) -> Node {
+        let player_state = self.player(player);
+        let pokemon = player_state.active_pokemon().unwrap();
+        let amount = damage::confusion_damage(pokemon, &player_state.stat_stages);
+
+        self.log(LogEvent::HurtInConfusion {
+            pokemon: pokemon.to_string(),
+        });
+        self.deal_indirect_damage(player, amount);
+
+        Node::pending(self)
+    }
+
+    fn resolve_move(mut self, player: Player, pokemove: PokeMove) -> Node {
+        let target = player.target(pokemove.target);
+
+        // A status move chosen before the user got taunted fails
+        if pokemove.category == MoveCategory::Status && self.player(player).is_taunted() {
+            self.log(LogEvent::MoveFailed);
+            return Node::pending(self);
+        }
+
+        if let Some(MoveEffect::CallRandomMove) = pokemove.effect {
+            return self.call_random_move(player);
+        }
+
+        if pokemove.priority > 0 && pokemove.targets_opponent() && self.blocks_priority(target) {
+            self.log(LogEvent::MoveFailed);
+            return Node::pending(self);
+        }
+
+        if let Some(MoveEffect::FirstTurnOnly) = pokemove.effect {
+            if self.player(player).turns_active > 1 {
+                self.log(LogEvent::MoveFailed);
+                return Node::pending(self);
+            }
+        }
+
+        let hit_chance_move = pokemove.clone();
+
+        self.check_accuracy(player, target, &hit_chance_move, move |state| {
+            state.land_move(player, pokemove)
+        })
+    }
+
+    /// Carries out a move used by `player`'s active pokemon once it's known to connect.
+    fn land_move(mut self, player: Player, pokemove: PokeMove) -> Node {
+        let target = player.target(pokemove.target);
+
+        if pokemove.category == MoveCategory::Status {
+            match pokemove.effect {
+                Some(MoveEffect::SelfBoost(kind, stages)) => {
+                    self.boost(player, kind, stages);
+                }
+                Some(MoveEffect::SetWeather(weather)) => {
+                    self.set_weather(player, weather);
+                }
+                Some(MoveEffect::SetTerrain(terrain)) => {
+                    self.set_terrain(terrain);
+                }
+                Some(MoveEffect::Screen(screen)) => self.set_screen(player, screen),
+                Some(MoveEffect::Heal(fraction)) => {
+                    self.heal(player, fraction);
+                }
+                Some(MoveEffect::WeatherHeal) => {
+                    let fraction = match self.weather {
+                        Weather::Clear => 0.5,
+                        Weather::Sun => 2.0 / 3.0,
+                        Weather::Rain | Weather::Sandstorm | Weather::Hail => 0.25,
+                    };
+
+                    self.heal(player, fraction);
+                }
+                Some(MoveEffect::Roost) => {
+                    let healed = self.heal(player, 0.5);
+                    self.player_mut(player).roosted = healed;
+                }
+                Some(MoveEffect::QuickGuard) => {
+                    self.player_mut(player).side.quick_guard = true;
+                    self.log(LogEvent::QuickGuard {
+                        player: player.to_string(),
+                    });
+                }
+                Some(MoveEffect::Sleep) => return self.inflict_sleep(target),
+                Some(MoveEffect::Rest) => self.rest(player),
+                Some(MoveEffect::SwapItems) => self.swap_items(player, target),
+                Some(MoveEffect::Substitute) => self.make_substitute(player),
+                Some(MoveEffect::LeechSeed) => self.seed(target),
+                Some(MoveEffect::Taunt) => self.taunt(target),
+                _ => {}
+            }
+
+            return Node::pending(self);
+        }
+
+        let (immune, incoming) = {
+            let user = self.player(player).active_pokemon().unwrap();
+            let defender = self.player(target).active_pokemon().unwrap();
+            let poke_type = pokemove.effective_type(user, self.weather);
+
+            (
+                damage::effectiveness_with_ability(
+                    poke_type,
+                    &self.hit_types(target, &pokemove),
+                    &defender.ability,
+                ) == 0.0,
+                defender.ability.incoming_multiplier(&poke_type),
+            )
+        };
+
+        if !immune && incoming < 0.0 {
+            return self.absorb_move(target, -incoming);
+        }
+
+        let pivots = !immune && matches!(pokemove.effect, Some(MoveEffect::Pivot));
+
+        let node = self.roll_hit(!immune, move |state, crit, roll| {
+            state.hit(player, pokemove, crit, roll)
+        });
+
+        if pivots {
+            node.then(move |state| state.pivot(player))
+        } else {
+            node
+        }
+    }
+
+    /// Lets `player` switch their active pokemon out after it used a move like U-turn, if it's
+    /// still standing and there's someone to switch to.
+    fn pivot(self, player: Player) -> Node {
+        let player_state = self.player(player);
+        let active_pokemon_idx = player_state.active_pokemon_idx.unwrap();
+
+        if player_state.team[active_pokemon_idx].is_fainted() {
+            return Node::pending(self);
+        }
+
+        let switch_choices = player_state
+            .team
+            .iter()
+            .enumerate()
+            .filter(|(i, p)| *i != active_pokemon_idx && !p.is_fainted())
+            .map(|(i, p)| (p.to_string(), i));
+
+        DecisionBuilder::new("Choose a pokemon to switch to", player)
+            .named_choices(switch_choices)
+            .build(self, move |state, idx| state.switch_in(player, idx))
+    }
+
+    /// Deals the damage of a move used by `player`'s active pokemon, and everything that follows
+    /// from it.
+    fn hit(mut self, player: Player, pokemove: PokeMove, crit: bool, roll: f64) -> Node {
+        let target = player.opponent();
+
+        if crit {
+            self.log(LogEvent::Crit);
+        }
+
+        let (amount, effectiveness) =
+            self.move_damage(player, target, &pokemove, false, crit, roll);
+
+        let attacker_ability = self.player(player).active_pokemon().unwrap().ability;
+        let dealt_move = pokemove.clone();
+        let damage_dealt = move |handler: &dyn EventHandler<Self>, state| {
+            handler.on_damage_dealt(state, player, target, &dealt_move, amount)
+        };
+
+        if self.player(target).substitute_hp.is_some() && !attacker_ability.infiltrates() {
+            self.damage_substitute(target, amount);
+            self.recoil(player, &pokemove);
+            return self.run_handlers(player, damage_dealt);
+        }
+
+        self.deal_damage(target, amount, effectiveness);
+
+        if amount == 0 {
+            return Node::pending(self);
+        }
+
+        self.recoil(player, &pokemove);
+
+        if let Some(MoveEffect::KnockOff) = pokemove.effect {
+            self.knock_off(target);
+        }
+
+        let secondary = pokemove.secondary;
+
+        self.run_handlers(player, damage_dealt)
+            .then(move |state| {
+                state.run_handlers(target, move |handler, state| {
+                    handler.on_damage_taken(state, target, player, &pokemove, amount)
+                })
+            })
+            .then(move |state| match secondary {
+                Some(secondary) => state.roll_secondary(target, secondary),
+                None => Node::pending(state),
+            })
+    }
+
+    /// Heals `target`'s active pokemon by `fraction` of its max HP instead of taking damage, as
+    /// with Dry Skin.
+    fn absorb_move(mut self, target: Player, fraction: f64) -> Node {
+        if !self.restore_hp(target, fraction) {
+            let pokemon = self.player(target).active_pokemon().unwrap().to_string();
+
+            self.log(LogEvent::Damage {
+                pokemon,
+                amount: 0,
+                effectiveness: TypeEffectiveness::NoEffect,
+            });
+        }
+
+        Node::pending(self)
+    }
+
+    /// Uses a random move from the built-in registry on behalf of `player`'s active pokemon.
+    fn call_random_move(self, player: Player) -> Node {
+        let moves = MoveRegistry::builtin().callable_moves();
+        let weight = 1.0 / moves.len() as f64;
+
+        ChanceBuilder::new("Called move")
+            .named_possibilities(
+                moves
+                    .into_iter()
+                    .map(|pokemove| (pokemove.to_string(), weight, pokemove)),
+            )
+            .build(self, move |mut state, pokemove| {
+                state.log(LogEvent::MoveUsed {
+                    pokemon: state.player(player).active_pokemon().unwrap().to_string(),
+                    pokemove: pokemove.to_string(),
+                });
+                state.resolve_move(player, pokemove)
+            })
+    }
+
+    /// Rolls for a secondary effect of a move that just hit `target`'s active pokemon.
+    fn roll_secondary(self, target: Player, secondary: Secondary) -> Node {
+        if self.player(target).active_pokemon().unwrap().is_fainted() {
+            return Node::pending(self);
+        }
+
+        if secondary.chance >= 100 {
+            return self.apply_secondary(target, secondary.effect);
+        }
+
+        let chance = f64::from(secondary.chance) / 100.0;
+
+        ChanceBuilder::new("Secondary effect")
+            .named_possibilities(vec![
+                ("Triggers", chance, true),
+                ("Doesn't trigger", 1.0 - chance, false),
+            ])
+            .build(self, move |state, triggers| {
+                if triggers {
+                    state.apply_secondary(target, secondary.effect)
+                } else {
+                    Node::pending(state)
+                }
+            })
+    }
+
+    fn apply_secondary(mut self, target: Player, effect: SecondaryEffect) -> Node {
+        match effect {
+            SecondaryEffect::Flinch => {
+                // Flinching only matters if the target still has to move this turn
+                let target_state = self.player_mut(target);
+
+                if target_state.turn_action.is_some() {
+                    target_state.flinched = true;
+                }
+            }
+        }
+
+        Node::pending(self)
+    }
+
+    /// Counts down the effects on `player`'s active pokemon that wear off at the end of a turn.
+    fn tick_effects(&mut self, player: Player) {
+        let player_state = self.player_mut(player);
+        let was_taunted = player_state.is_taunted();
+        player_state.effects.retain_mut(Effect::tick);
+
+        if was_taunted && !player_state.is_taunted() {
+            let pokemon = player_state.active_pokemon().unwrap().to_string();
+            self.log(LogEvent::TauntEnded { pokemon });
+        }
+    }
+
+    /// Seeds `target`'s active pokemon. Fails on Grass types, substitutes and pokemon that are
+    /// already seeded.
+    fn seed(&mut self, target: Player) {
+        let target_state = self.player(target);
+
+        if target_state.is_seeded()
+            || target_state.substitute_hp.is_some()
+            || self.active_types(target).contains(&PokeType::Grass)
+        {
+            self.log(LogEvent::MoveFailed);
+            return;
+        }
+
+        let pokemon = target_state.active_pokemon().unwrap().to_string();
+        self.player_mut(target).effects.push(Effect::LeechSeed);
+        self.log(LogEvent::Seeded { pokemon });
+    }
+
+    /// Taunts `target`'s active pokemon for 3 turns. Fails if it's already taunted.
+    fn taunt(&mut self, target: Player) {
+        let target_state = self.player(target);
+
+        if target_state.is_taunted() {
+            self.log(LogEvent::MoveFailed);
+            return;
+        }
+
+        let pokemon = target_state.active_pokemon().unwrap().to_string();
+        self.player_mut(target)
+            .effects
+            .push(Effect::Taunt { turns: 3 });
+        self.log(LogEvent::Taunted { pokemon });
+    }
+
+    /// Drains HP from `player`'s active pokemon into the opposing one, if it's seeded.
+    fn leech_seed_residual(&mut self, player: Player) {
+        let opponent = player.opponent();
+        let pokemon = self.player(player).active_pokemon().unwrap();
+
+        if !self.player(player).is_seeded()
+            || pokemon.is_fainted()
+            || self.player(opponent).active_pokemon().unwrap().is_fainted()
+        {
+            return;
+        }
+
+        let hp_before = pokemon.current_hp;
+        self.deal_indirect_damage(player, (pokemon.computed_stat(StatKind::Hp) / 8).max(1));
+
+        let drained = hp_before - self.player(player).active_pokemon().unwrap().current_hp;
+
+        if drained > 0 {
+            self.restore_hp_by(opponent, drained);
+        }
+    }
+
+    /// Whether any end of turn effect applies to `player`'s active pokemon, so that speed ties
+    /// only need breaking when more than one pokemon is affected.
+    fn has_residual(&self, player: Player) -> bool {
+        let player_state = self.player(player);
+        let pokemon = player_state.active_pokemon().unwrap();
+        let can_heal = pokemon.current_hp < pokemon.computed_stat(StatKind::Hp);
+
+        if pokemon.is_fainted() {
+            return false;
+        }
+
+        (player_state.is_seeded()
+            && !self
+                .player(player.opponent())
+                .active_pokemon()
+                .unwrap()
+                .is_fainted())
+            || self.weather.damages(&self.active_types(player))
+            || (can_heal && self.terrain == Terrain::Grassy && self.is_grounded(player))
+            || pokemon.ability.acts_on_turn_end(self, player)
+            || pokemon
+                .item
+                .as_ref()
+                .is_some_and(|item| item.acts_on_turn_end(self, player))
+    }
+
+    /// End of turn effects of the terrain on `player`'s active pokemon.
+    fn terrain_residual(&mut self, player: Player) {
+        if self.terrain != Terrain::Grassy || !self.is_grounded(player) {
+            return;
+        }
+
+        self.restore_hp(player, 1.0 / 16.0);
+    }
+
+    /// Recoil `player`'s active pokemon takes from `pokemove` after it hits with it. Recoil from
+    /// items is up to their `on_damage_dealt`.
+    fn recoil(&mut self, player: Player, pokemove: &PokeMove) {
+        if let Some(MoveEffect::Struggle) = pokemove.effect {
+            self.struggle_recoil(player);
+        }
+    }
+
+    /// End of turn damage from the weather to `player`'s active pokemon.
+    fn weather_residual(&mut self, player: Player) {
+        let pokemon = self.player(player).active_pokemon().unwrap();
+
+        if !pokemon.is_fainted() && self.weather.damages(&self.active_types(player)) {
+            let amount = (pokemon.computed_stat(StatKind::Hp) / 16).max(1);
+            self.deal_indirect_damage(player, amount);
+        }
+    }
+
+    /// Heals `player`'s active pokemon by `fraction` of its max HP. Fails if it's at full HP.
+    ///
+    /// Returns whether it was healed.
+    fn heal(&mut self, player: Player, fraction: f64) -> bool {
+        let healed = self.restore_hp(player, fraction);
+
+        if !healed {
+            self.log(LogEvent::MoveFailed);
+        }
+
+        healed
+    }
+
+    /// Puts `target`'s active pokemon to sleep for 1-3 turns, if it can be.
+    fn inflict_sleep(mut self, target: Player) -> Node {
+        if !self.can_inflict_status(target, Status::Sleep { turns: 0 }) {
+            self.log(LogEvent::MoveFailed);
+            return Node::pending(self);
+        }
+
+        ChanceBuilder::new("Sleep duration")
+            .named_possibilities(
+                (1..=3).map(|turns| (format!("{} turns", turns), 1.0 / 3.0, turns)),
+            )
+            .build(self, move |mut state, turns| {
+                state.apply_status(target, Status::Sleep { turns });
+                Node::pending(state)
+            })
+    }
+
+    /// Whether the opponent of `target` could give `status` to `target`'s active pokemon.
+    fn can_inflict_status(&self, target: Player, status: Status) -> bool {
+        let target_state = self.player(target);
+        let pokemon = target_state.active_pokemon().unwrap();
+
+        if pokemon.is_fainted()
+            || pokemon.status != Status::Healthy
+            || target_state.substitute_hp.is_some()
+            || (self.terrain == Terrain::Misty && self.is_grounded(target))
+        {
+            return false;
+        }
+
+        match status {
+            Status::Sleep { .. } => {
+                // Sleep Clause: only one pokemon on a team can be put to sleep by the opponent
+                let clause_blocks = self.rules.sleep_clause
+                    && target_state.slept_by_opponent.is_some_and(|idx| {
+                        matches!(target_state.team[idx].status, Status::Sleep { .. })
+                    });
+
+                !clause_blocks && !pokemon.ability.prevents_sleep()
+            }
+            _ => true,
+        }
+    }
+
+    /// Fully heals `player`'s active pokemon and puts it to sleep, replacing any other status.
+    ///
+    /// Fails if there's nothing to heal, or if it's already asleep or kept awake by its ability or
+    /// Misty Terrain.
+    fn rest(&mut self, player: Player) {
+        let misty = self.terrain == Terrain::Misty && self.is_grounded(player);
+        let pokemon = self.player_mut(player).active_pokemon_mut().unwrap();
+        let max_hp = pokemon.computed_stat(StatKind::Hp);
+
+        if (pokemon.current_hp == max_hp && pokemon.status == Status::Healthy)
+            || matches!(pokemon.status, Status::Sleep { .. })
+            || pokemon.ability.prevents_sleep()
+            || misty
+        {
+            self.log(LogEvent::MoveFailed);
+            return;
+        }
+
+        let amount = max_hp - pokemon.current_hp;
+        let status = Status::Sleep { turns: 2 };
+        pokemon.current_hp = max_hp;
+        pokemon.status = status;
+
+        let pokemon = pokemon.to_string();
+        self.log(LogEvent::StatusApplied {
+            pokemon: pokemon.clone(),
+            status,
+        });
+        self.pending_hooks.push(PendingHook::StatusApplied {
+            target: player,
+            idx: self.player(player).active_pokemon_idx.unwrap(),
+            status,
+        });
+
+        if amount > 0 {
+            self.log(LogEvent::Healed { pokemon, amount });
+        }
+    }
+
+    /// Makes a substitute for `player`'s active pokemon, paying a quarter of its max HP.
+    fn make_substitute(&mut self, player: Player) {
+        let player_state = self.player_mut(player);
+        let has_substitute = player_state.substitute_hp.is_some();
+        let pokemon = player_state.active_pokemon_mut().unwrap();
+        let cost = pokemon.computed_stat(StatKind::Hp) / 4;
+
+        if has_substitute || pokemon.current_hp <= cost {
+            self.log(LogEvent::MoveFailed);
+            return;
+        }
+
+        pokemon.current_hp -= cost;
+        let pokemon = pokemon.to_string();
+
+        self.player_mut(player).substitute_hp = Some(cost);
+        self.log(LogEvent::SubstituteCreated { pokemon });
+    }
+
+    /// Deals damage to the substitute of `target`'s active pokemon, breaking it at 0 HP.
+    fn damage_substitute(&mut self, target: Player, amount: u32) {
+        let target_state = self.player_mut(target);
+        let pokemon = target_state.active_pokemon().unwrap().to_string();
+        let remaining = target_state
+            .substitute_hp
+            .map(|hp| hp.saturating_sub(amount))
+            .filter(|&hp| hp > 0);
+
+        target_state.substitute_hp = remaining;
+        self.log(LogEvent::SubstituteDamaged {
+            pokemon: pokemon.clone(),
+        });
+
+        if remaining.is_none() {
+            self.log(LogEvent::SubstituteFaded { pokemon });
+        }
+    }
+
+    /// Removes the item of `target`'s active pokemon, unless its ability keeps it.
+    fn knock_off(&mut self, target: Player) {
+        let pokemon = self.player_mut(target).active_pokemon_mut().unwrap();
+
+        // A fainted pokemon keeps its item, which leaves Focus Sash the chance to save it
+        if pokemon.ability.keeps_item() || pokemon.is_fainted() {
+            return;
+        }
+
+        if let Some(item) = pokemon.item.take() {
+            let event = LogEvent::ItemKnockedOff {
+                pokemon: pokemon.to_string(),
+                item: item.to_string(),
+            };
+            self.player_mut(target).choice_lock = None;
+            self.log(event);
+        }
+    }
+
+    /// Swaps the items of `player`'s and `target`'s active pokemon, resetting any Choice locks.
+    fn swap_items(&mut self, player: Player, target: Player) {
+        let user = self.player(player).active_pokemon().unwrap();
+        let defender = self.player(target).active_pokemon().unwrap();
+
+        if (user.item.is_none() && defender.item.is_none())
+            || user.ability.keeps_item()
+            || defender.ability.keeps_item()
+            || self.player(target).substitute_hp.is_some()
+        {
+            self.log(LogEvent::MoveFailed);
+            return;
+        }
+
+        let user_item = self
+            .player_mut(player)
+            .active_pokemon_mut()
+            .unwrap()
+            .item
+            .take();
+        let target_item = self
+            .player_mut(target)
+            .active_pokemon_mut()
+            .unwrap()
+            .item
+            .take();
+
+        self.log(LogEvent::ItemsSwapped {
+            pokemon: self.player(player).active_pokemon().unwrap().to_string(),
+        });
+
+        for (holder, item) in [(player, target_item), (target, user_item)] {
+            let player_state = self.player_mut(holder);
+            player_state.choice_lock = None;
+            let pokemon = player_state.active_pokemon_mut().unwrap();
+
+            if let Some(item) = item {
+                let event = LogEvent::ItemObtained {
+                    pokemon: pokemon.to_string(),
+                    item: item.to_string(),
+                };
+                pokemon.item = Some(item);
+                self.log(event);
+            }
+        }
+    }
+
+    fn deal_damage(&mut self, target: Player, amount: u32, effectiveness: TypeEffectiveness) {
+        let defender = self.player_mut(target).active_pokemon_mut().unwrap();
+        let amount = defender.take_damage(amount);
+        self.log_damage(target, amount, effectiveness);
+    }
+
+    fn log_damage(&mut self, target: Player, amount: u32, effectiveness: TypeEffectiveness) {
+        let defender = self.player(target).active_pokemon().unwrap();
+        let pokemon = defender.to_string();
+        let fainted = amount > 0 && defender.is_fainted();
+
+        self.log(LogEvent::Damage {
+            pokemon,
+            amount,
+            effectiveness,
+        });
+
+        if amount > 0 {
+            self.pending_hooks.push(PendingHook::Damage {
+                target,
+                idx: self.player(target).active_pokemon_idx.unwrap(),
+                amount,
+                fainted,
+            });
+        }
+    }
+
+    /// Runs the `EventHandler` hooks for whatever happened since they were last run, in order.
+    ///
+    /// Hooks for a pokemon that has since left the battle are dropped.
+    fn run_hooks(mut self) -> Node {
+        let hooks: Vec<_> = self.pending_hooks.drain(..).collect();
+        self.fold(hooks, |state, hook| state.run_hook(hook))
+    }
+
+    fn run_hook(mut self, hook: PendingHook) -> Node {
+        let (target, idx) = match hook {
+            PendingHook::Damage { target, idx, .. } => (target, idx),
+            PendingHook::StatusApplied { target, idx, .. } => (target, idx),
+        };
+
+        if self.player(target).active_pokemon_idx != Some(idx) {
+            // Nothing can save it anymore, but its faint still gets logged
+            if let PendingHook::Damage { fainted: true, .. } = hook {
+                self.log_faint(target, idx);
+            }
+
+            return Node::pending(self);
+        }
+
+        match hook {
+            PendingHook::Damage {
+                amount, fainted, ..
+            } => {
+                let node = self.run_handlers(target, move |handler, state| {
+                    handler.on_damage(state, target, amount)
+                });
+
+                if !fainted {
+                    return node;
+                }
+
+                node.then(move |state| {
+                    state.run_handlers(target, move |handler, state| {
+                        handler.on_faint(state, target, amount)
+                    })
+                })
+                .then(move |mut state| {
+                    state.log_faint(target, idx);
+                    Node::pending(state)
+                })
+            }
+            PendingHook::StatusApplied { status, .. } => self
+                .run_handlers(target, move |handler, state| {
+                    handler.on_status_applied(state, target, status)
+                }),
+        }
+    }
+
+    /// Logs that the pokemon at `idx` in `player`'s team fainted, unless it was saved.
+    fn log_faint(&mut self, player: Player, idx: usize) {
+        let pokemon = &self.player(player).team[idx];
+
+        if pokemon.is_fainted() {
+            let pokemon = pokemon.to_string();
+            self.log(LogEvent::Fainted { pokemon });
+        }
+    }
+
+    /// Sorts actions so that switches go first, then moves in descending order of priority and
+    /// then effective speed. Players whose keys are equal are in a speed tie.
+    fn action_key(&self, player: Player) -> (bool, Reverse<Option<i8>>, Reverse<u32>) {
+        let player_state = self.player(player);
+        let priority = match player_state.turn_action {
+            Some(Action::UsePokeMove(idx)) => {
+                Some(player_state.active_pokemon().unwrap().moves[idx].priority)
+            }
+            Some(Action::Struggle) => Some(PokeMove::struggle().priority),
+            Some(Action::SwitchPokemon(_)) | None => None,
+        };
+
+        (
+            priority.is_some(),
+            Reverse(priority),
+            Reverse(self.speed(player)),
+        )
+    }
+
+    fn log_switch_in(&mut self, player: Player) {
+        let pokemon = self.player(player).active_pokemon().unwrap().to_string();
+
+        self.log(LogEvent::SwitchedIn {
+            player: player.to_string(),
+            pokemon,
+        });
+    }
+
+    /// Runs the switch-in and then the ETB hooks of `player`'s active pokemon.
+    fn pokemon_etb(self, player: Player) -> Node {
+        self.run_handlers(player, move |handler, state| {
+            handler.on_switch_in(state, player)
+        })
+        .then(move |state| {
+            state.run_handlers(player, move |handler, state| handler.on_etb(state, player))
+        })
+    }
+
+    /// Starts `weather`, as set by `player`'s active pokemon. Fails if it's already active.
+    ///
+    /// Returns whether the weather was started.
+    pub fn set_weather(&mut self, player: Player, weather: Weather) -> bool {
+        if self.weather == weather {
+            self.log(LogEvent::MoveFailed);
+            return false;
+        }
+
+        let extended = match &self.player(player).active_pokemon().unwrap().item {
+            Some(item) => item.extends_weather(weather),
+            None => false,
+        };
+
+        self.weather = weather;
+        self.weather_turns = self.rules.weather_turns + if extended { 3 } else { 0 };
+        self.log(LogEvent::WeatherStarted(weather));
+
+        true
+    }
+
+    /// Starts `terrain` for 5 turns. Fails if it's already active.
+    ///
+    /// Returns whether the terrain was started.
+    pub fn set_terrain(&mut self, terrain: Terrain) -> bool {
+        if self.terrain == terrain {
+            self.log(LogEvent::MoveFailed);
+            return false;
+        }
+
+        self.terrain = terrain;
+        self.terrain_turns = 5;
+        self.log(LogEvent::TerrainStarted(terrain));
+
+        true
+    }
+
+    /// Whether priority moves used against `target`'s active pokemon fail, because of Quick Guard
+    /// or Psychic Terrain.
+    fn blocks_priority(&self, target: Player) -> bool {
+        self.player(target).side.quick_guard
+            || (self.terrain == Terrain::Psychic && self.is_grounded(target))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+    use crate::{ability::Ability, testing};
+
+    fn battle(player_1: Vec<Pokemon>, player_2: Vec<Pokemon>) -> Node {
+        State::start_with_leads(
+            testing::team(player_1),
+            testing::team(player_2),
+            0,
+            0,
+            BattleRules::default(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn rules_violations_stop_the_battle_from_starting() {
+        let rules = BattleRules {
+            species_clause: true,
+            ..BattleRules::default()
+        };
+        let form = testing::form("Ditto", &[PokeType::Normal]);
+        let ditto = || pokemon::PokemonBuilder::new(form.clone()).build();
+
+        let result = State::start_with_rules(
+            testing::team(vec![ditto(), ditto()]),
+            testing::team(vec![ditto()]),
+            rules,
+        );
+
+        assert!(matches!(
+            result,
+            Err(RulesViolation::DuplicateSpecies(species)) if species == "Ditto"
+        ));
+    }
+
+    #[test]
+    fn invalid_genders_stop_the_battle_from_starting() {
+        let magnemite = testing::builder("Magnemite", &[PokeType::Electric], &["Tackle"])
+            .gender(pokemon::Gender::Male)
+            .build();
+        let foe = testing::builder("Blissey", &[PokeType::Normal], &["Tackle"]).build();
+
+        assert!(matches!(
+            State::start(testing::team(vec![foe]), testing::team(vec![magnemite])),
+            Err(RulesViolation::InvalidGender(_))
+        ));
+    }
+
+    #[test]
+    fn most_likely_rng_only_branches_on_decisions() {
+        let rules = BattleRules {
+            rng_behavior: RngBehavior::MostLikely,
+            ..BattleRules::default()
+        };
+        let attacker = || testing::builder("Pikachu", &[PokeType::Electric], &["Thunderbolt"]);
+        let mut node = State::start_with_rules(
+            testing::team(vec![attacker().build()]),
+            testing::team(vec![attacker().build()]),
+            rules,
+        )
+        .unwrap();
+
+        for _ in 0..1000 {
+            node = match node.branches() {
+                state::Branches::Chance(c) => {
+                    assert_eq!(c.possibilities().len(), 1);
+                    node.resolve_chance(0).unwrap()
+                }
+                state::Branches::Decision(_) => node.resolve_decision(0).unwrap(),
+                state::Branches::Pending | state::Branches::End => break,
+            };
+        }
+
+        assert!(node.state().winner().is_some());
+    }
+
+    #[test]
+    fn self_boosting_moves_boost_the_user() {
+        let dancer = testing::builder("Scizor", &[PokeType::Bug], &["Swords Dance"]).build();
+        let foe = testing::builder("Blissey", &[PokeType::Normal], &["Tackle"]).build();
+
+        let node = testing::play(battle(vec![dancer], vec![foe]), &["Swords Dance", "Tackle"]);
+        let state = node.state();
+
+        assert_eq!(state.turn(), 2);
+        assert_eq!(state.player(Player::Player1).stat_stages.attack, 2);
+        assert_eq!(state.player(Player::Player2).stat_stages.attack, 0);
+    }
+
+    #[test]
+    fn move_choices_carry_their_move() {
+        let snorlax = testing::builder("Snorlax", &[PokeType::Normal], &["Tackle", "Rest"]).build();
+        let foe = testing::builder("Blissey", &[PokeType::Normal], &["Tackle"]).build();
+        let mut state = State::new(
+            testing::team(vec![snorlax]),
+            testing::team(vec![foe]),
+            BattleRules::default(),
+        )
+        .unwrap();
+        state.player_1.active_pokemon_idx = Some(0);
+        state.player_2.active_pokemon_idx = Some(0);
+        state.player_1.team[0].moves[0].current_pp = 0;
+
+        let node = state.choose_actions();
+        let decision = match node.branches() {
+            state::Branches::Decision(d) => d,
+            other => panic!("Expected a decision, got {:?}", other),
+        };
+        let metas: Vec<_> = decision
+            .choices()
+            .iter()
+            .map(|c| {
+                c.meta::<PokeMove>()
+                    .map(|m| (m.name.as_str(), c.is_enabled()))
+            })
+            .collect();
+
+        assert_eq!(metas, [Some(("Tackle", false)), Some(("Rest", true))]);
+    }
+
+    fn action_choices(node: &Node) -> Vec<(String, bool)> {
+        match node.branches() {
+            state::Branches::Decision(d) => d
+                .choices()
+                .iter()
+                .map(|c| (c.name().to_string(), c.is_enabled()))
+                .collect(),
+            other => panic!("Expected a decision, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn moves_are_disabled_once_they_run_out_of_pp() {
+        let mut pikachu = testing::builder("Pikachu", &[PokeType::Electric], &["Tackle"]).build();
+        pikachu.moves[0].max_pp = 1;
+        let foe = testing::builder("Blissey", &[PokeType::Normal], &["Tackle"]).build();
+
+        let node = testing::play(battle(vec![pikachu], vec![foe]), &[]);
+        assert_eq!(action_choices(&node), [("Tackle".to_owned(), true)]);
+
+        let node = testing::play(node, &["Tackle", "Tackle"]);
+        assert_eq!(node.state().turn(), 2);
+        assert_eq!(
+            action_choices(&node),
+            [("Tackle".to_owned(), false), ("Struggle".to_owned(), true)]
+        );
+    }
+
+    #[test]
+    fn pokemon_without_pp_struggle_and_take_recoil() {
+        let mut pikachu =
+            testing::builder("Pikachu", &[PokeType::Electric], &["Tackle", "Rest"]).build();
+        for pokemove in &mut pikachu.moves {
+            pokemove.max_pp = 0;
+        }
+        let foe = testing::builder("Blissey", &[PokeType::Normal], &["Swords Dance"]).build();
+
+        let node = testing::play(battle(vec![pikachu], vec![foe]), &[]);
+        assert_eq!(
+            action_choices(&node),
+            [
+                ("Tackle".to_owned(), false),
+                ("Rest".to_owned(), false),
+                ("Struggle".to_owned(), true)
+            ]
+        );
+
+        let node = testing::play(node, &["Struggle", "Swords Dance"]);
+        let state = node.state();
+        let user = state.player(Player::Player1).active_pokemon().unwrap();
+        let foe = state.player(Player::Player2).active_pokemon().unwrap();
+
+        assert_eq!(state.turn(), 2);
+        assert!(foe.current_hp < foe.computed_stat(StatKind::Hp));
+        assert_eq!(
+            user.current_hp,
+            user.computed_stat(StatKind::Hp) - user.computed_stat(StatKind::Hp) / 4
+        );
+    }
+
+    #[test]
+    fn sandstorm_chips_pokemon_that_arent_immune() {
+        let tyranitar = testing::builder("Tyranitar", &[PokeType::Rock], &["Sandstorm"]).build();
+        let foe = testing::builder("Blissey", &[PokeType::Normal], &["Swords Dance"]).build();
+
+        let node = testing::play(
+            battle(vec![tyranitar], vec![foe]),
+            &["Sandstorm", "Swords Dance"],
+        );
+        let state = node.state();
+        let tyranitar = state.player(Player::Player1).active_pokemon().unwrap();
+        let foe = state.player(Player::Player2).active_pokemon().unwrap();
+
+        assert_eq!(state.weather, Weather::Sandstorm);
+        assert_eq!(tyranitar.current_hp, tyranitar.computed_stat(StatKind::Hp));
+        assert_eq!(
+            foe.current_hp,
+            foe.computed_stat(StatKind::Hp) - foe.computed_stat(StatKind::Hp) / 16
+        );
+    }
+
+    #[test]
+    fn rain_boosts_water_moves() {
+        let surf_damage = |script: &[&str]| {
+            let attacker =
+                testing::builder("Lapras", &[PokeType::Ice], &["Surf", "Rain Dance"]).build();
+            let foe = testing::builder("Blissey", &[PokeType::Normal], &["Swords Dance"]).build();
+            let node = testing::play(battle(vec![attacker], vec![foe]), script);
+            let foe = node
+                .state()
+                .player(Player::Player2)
+                .active_pokemon()
+                .unwrap();
+
+            foe.computed_stat(StatKind::Hp) - foe.current_hp
+        };
+
+        let dry = surf_damage(&["Surf", "Swords Dance"]);
+        let rain = surf_damage(&["Rain Dance", "Swords Dance", "Surf", "Swords Dance"]);
+
+        // Damage is rounded after every multiplier, so it's only roughly 1.5x.
+        assert!(dry > 0);
+        assert!((f64::from(rain) / f64::from(dry) - 1.5).abs() < 0.05);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn battles_resume_from_a_state_saved_mid_battle() {
+        let form = testing::form("Pikachu", &[PokeType::Electric]);
+        let species = vec![Rc::new(PokemonSpecies {
+            national_dex_no: form.species.national_dex_no,
+            name: form.species.name.clone(),
+            forms: vec![form.clone()],
+        })];
+        let pikachu = || {
+            ["Thunderbolt", "Rain Dance", "Swords Dance"]
+                .iter()
+                .fold(
+                    pokemon::PokemonBuilder::new(form.clone()).level(50),
+                    |b, m| b.with_move(m.parse().unwrap()),
+                )
+                .build()
+        };
+
+        let node = testing::play(
+            battle(vec![pikachu(), pikachu()], vec![pikachu()]),
+            &["Rain Dance", "Swords Dance", "Thunderbolt", "Thunderbolt"],
+        );
+        let json = serde_json::to_string(node.state()).unwrap();
+        let resumed = serde_json::from_str::<State>(&json)
+            .unwrap()
+            .resume(&species)
+            .unwrap();
+
+        for &player in Player::values() {
+            assert_eq!(
+                resumed.state().player(player).team.as_slice(),
+                node.state().player(player).team.as_slice()
+            );
+        }
+        assert_eq!(resumed.state().turn(), 3);
+        assert_eq!(resumed.state().weather, Weather::Rain);
+        assert_eq!(
+            serde_json::to_value(resumed.state()).unwrap(),
+            serde_json::to_value(node.state()).unwrap()
+        );
+        assert_eq!(action_choices(&resumed), action_choices(&node));
+    }
+
+    #[test]
+    fn item_swaps_are_described() {
+        let holder = testing::builder("Alakazam", &[PokeType::Psychic], &["Trick"])
+            .item(Item::ChoiceScarf)
+            .build();
+        let before = PlayerState::new(testing::team(vec![holder]));
+        let mut after = before.clone();
+        after.team[0].item = Some(Item::Leftovers);
+
+        assert_eq!(
+            after.describe_changes(&before),
+            vec!["Alakazam: now holding Leftovers".to_owned()]
+        );
+    }
+
+    #[test]
+    fn a_strong_move_faints_a_frail_target_in_one_turn() {
+        let attacker = testing::builder("Garchomp", &[PokeType::Ground], &["Earthquake"])
+            .level(100)
+            .build();
+        let frail = testing::builder("Abra", &[PokeType::Normal], &["Tackle"])
+            .level(5)
+            .build();
+
+        let node = testing::play(
+            battle(vec![attacker], vec![frail]),
+            &["Earthquake", "Tackle"],
+        );
+
+        assert_eq!(node.state().turn(), 1);
+        assert_eq!(node.state().winner(), Some(Player::Player1));
+    }
+
+    #[test]
+    fn damage_branches_over_sixteen_rolls() {
+        let attacker = testing::builder("Pikachu", &[PokeType::Electric], &["Thunderbolt"]).build();
+        let foe = testing::builder("Blissey", &[PokeType::Normal], &["Tackle"]).build();
+
+        let mut node = testing::play(battle(vec![attacker], vec![foe]), &["Thunderbolt"])
+            .resolve_decision(0)
+            .unwrap();
+
+        // Skip the accuracy and crit checks in front of the first damage roll
+        let node = loop {
+            node = match node.branches() {
+                state::Branches::Chance(c) if c.name().to_string() == "Damage roll" => break node,
+                state::Branches::Chance(_) => node.resolve_chance(0).unwrap(),
+                other => panic!("Expected a damage roll, got {:?}", other),
+            };
+        };
+        let rolls = match node.branches() {
+            state::Branches::Chance(c) => c.possibilities(),
+            _ => unreachable!(),
+        };
+
+        assert_eq!(rolls.len(), 16);
+        assert!(rolls.iter().all(|p| (p.weight() - 1.0 / 16.0).abs() < 1e-9));
+        assert_eq!(rolls[0].name().to_string(), "85%");
+        assert_eq!(rolls[15].name().to_string(), "100%");
+    }
+
+    #[test]
+    fn metronome_calls_a_seeded_random_move() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let caller = testing::builder("Clefable", &[PokeType::Fairy], &["Metronome"]).build();
+        let foe = testing::builder("Blissey", &[PokeType::Normal], &["Tackle"]).build();
+        let mut rng = StdRng::seed_from_u64(7);
+
+        let mut node = testing::play(battle(vec![caller], vec![foe]), &["Metronome"])
+            .resolve_decision(0)
+            .unwrap();
+        let mut called = None;
+        let mut log = Vec::new();
+
+        while called.is_none() {
+            node = match node.branches() {
+                state::Branches::Chance(c) if c.name().to_string() == "Called move" => {
+                    let idx = c.sample(&mut rng);
+                    called = Some(c.possibilities()[idx].name().to_string());
+                    node.resolve_chance(idx).unwrap()
+                }
+                state::Branches::Chance(_) => node.resolve_chance(0).unwrap(),
+                other => panic!("Expected Metronome to call a move, got {:?}", other),
+            };
+            log.extend(node.drain_log());
+        }
+        let called = called.unwrap();
+
+        assert_eq!(called, "Crunch");
+        assert!(log.iter().any(|event| matches!(
+            event,
+            LogEvent::MoveUsed { pokemove, .. } if *pokemove == called
+        )));
+
+        let node = testing::play(node, &[]);
+        let foe = node
+            .state()
+            .player(Player::Player2)
+            .active_pokemon()
+            .unwrap();
+        assert!(foe.current_hp < foe.computed_stat(StatKind::Hp));
+    }
+
+    #[test]
+    fn metronome_cant_call_uncallable_moves() {
+        let callable = MoveRegistry::builtin().callable_moves();
+
+        for name in &["Metronome", "Struggle", "Quick Guard", "Trick"] {
+            assert!(
+                callable.iter().all(|m| m.name != *name),
+                "{} is callable",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn turns_count_full_rounds() {
+        let pokemon = |name| testing::builder(name, &[PokeType::Normal], &["Swords Dance"]).build();
+        let node = battle(vec![pokemon("Rattata")], vec![pokemon("Pidgey")]);
+
+        let node = testing::play(node, &[]);
+        assert_eq!(node.state().turn(), 1);
+
+        let node = testing::play(node, &["Swords Dance", "Swords Dance"]);
+        assert_eq!(node.state().turn(), 2);
+
+        let node = testing::play(node, &["Swords Dance", "Swords Dance"]);
+        assert_eq!(node.state().turn(), 3);
+    }
+
+    #[test]
+    fn max_turns_ends_the_battle_on_the_hp_tiebreak() {
+        let rules = BattleRules {
+            max_turns: Some(1),
+            ..BattleRules::default()
+        };
+        let attacker = testing::builder("Rattata", &[PokeType::Normal], &["Tackle"]).build();
+        let dancer = testing::builder("Pidgey", &[PokeType::Normal], &["Swords Dance"]).build();
+        let node = State::start_with_rules(
+            testing::team(vec![attacker]),
+            testing::team(vec![dancer]),
+            rules,
+        )
+        .unwrap();
+
+        let node = testing::play(node, &["Rattata", "Pidgey", "Tackle", "Swords Dance"]);
+
+        assert!(matches!(node.branches(), state::Branches::End));
+        assert_eq!(node.state().turn(), 1);
+        assert_eq!(node.state().winner(), Some(Player::Player1));
+    }
+
+    #[test]
+    fn intimidate_raises_the_attack_of_a_contrary_foe() {
+        let intimidator = testing::builder("Gyarados", &[PokeType::Water], &["Tackle"])
+            .ability(Ability::Intimidate)
+            .build();
+        let contrary = testing::builder("Spinda", &[PokeType::Normal], &["Tackle"])
+            .ability(Ability::Contrary)
+            .build();
+
+        let node = testing::play(battle(vec![intimidator], vec![contrary]), &[]);
+
+        assert_eq!(node.state().player(Player::Player2).stat_stages.attack, 1);
+    }
+
+    #[test]
+    fn self_boosts_are_inverted_by_contrary() {
+        let contrary = testing::builder("Spinda", &[PokeType::Normal], &["Swords Dance"])
+            .ability(Ability::Contrary)
+            .build();
+        let foe = testing::builder("Blissey", &[PokeType::Normal], &["Swords Dance"]).build();
+
+        let node = testing::play(
+            battle(vec![contrary], vec![foe]),
+            &["Swords Dance", "Swords Dance"],
+        );
+
+        assert_eq!(node.state().player(Player::Player1).stat_stages.attack, -2);
+        assert_eq!(node.state().player(Player::Player2).stat_stages.attack, 2);
+    }
+
+    #[test]
+    fn intimidates_resolve_in_speed_order() {
+        let slow = testing::builder("Gyarados", &[PokeType::Water], &["Tackle"])
+            .ability(Ability::Intimidate)
+            .build();
+        let fast = testing::builder("Arcanine", &[PokeType::Fire], &["Tackle"])
+            .ability(Ability::Intimidate)
+            .level(60)
+            .build();
+
+        let mut node = testing::play(battle(vec![slow], vec![fast]), &[]);
+        let intimidated: Vec<_> = node
+            .drain_log()
+            .into_iter()
+            .filter_map(|event| match event {
+                LogEvent::StatChanged { pokemon, .. } => Some(pokemon),
+                _ => None,
+            })
+            .collect();
+
+        // The faster Arcanine's Intimidate goes first
+        assert_eq!(intimidated, vec!["Gyarados", "Arcanine"]);
+    }
+
+    #[test]
+    fn intimidate_lowers_the_foes_attack_when_switched_in() {
+        let lead = testing::builder("Rattata", &[PokeType::Normal], &["Tackle"]).build();
+        let intimidator = testing::builder("Gyarados", &[PokeType::Water], &["Tackle"])
+            .ability(Ability::Intimidate)
+            .build();
+        let foe = testing::builder("Pidgey", &[PokeType::Normal], &["Tackle"]).build();
+
+        let node = testing::play(
+            battle(vec![lead, intimidator], vec![foe]),
+            &["Gyarados", "Tackle"],
+        );
+
+        assert_eq!(node.state().player(Player::Player2).stat_stages.attack, -1);
+    }
+
+    #[test]
+    fn leftovers_heal_up_to_max_hp() {
+        let holder = testing::builder("Snorlax", &[PokeType::Normal], &["Swords Dance"])
+            .level(100)
+            .item(Item::Leftovers)
+            .build();
+        let weak = testing::builder("Rattata", &[PokeType::Normal], &["Tackle"])
+            .level(5)
+            .build();
+
+        let mut node = testing::play(
+            battle(vec![holder], vec![weak]),
+            &["Swords Dance", "Tackle"],
+        );
+        let log = node.drain_log();
+        let damage = log.iter().find_map(|event| match event {
+            LogEvent::Damage { amount, .. } => Some(*amount),
+            _ => None,
+        });
+        let healed = log.iter().find_map(|event| match event {
+            LogEvent::Healed { amount, .. } => Some(*amount),
+            _ => None,
+        });
+        let holder = node
+            .state()
+            .player(Player::Player1)
+            .active_pokemon()
+            .unwrap();
+
+        assert!(damage.unwrap() < holder.computed_stat(StatKind::Hp) / 16);
+        assert_eq!(healed, damage);
+        assert_eq!(holder.current_hp, holder.computed_stat(StatKind::Hp));
+    }
+
+    #[test]
+    fn life_orb_costs_a_tenth_of_max_hp_per_hit() {
+        let holder = testing::builder("Machamp", &[PokeType::Fighting], &["Tackle"])
+            .item(Item::LifeOrb)
+            .build();
+        let foe = testing::builder("Snorlax", &[PokeType::Normal], &["Swords Dance"]).build();
+
+        let node = testing::play(battle(vec![holder], vec![foe]), &["Tackle", "Swords Dance"]);
+        let holder = node
+            .state()
+            .player(Player::Player1)
+            .active_pokemon()
+            .unwrap();
+        let max_hp = holder.computed_stat(StatKind::Hp);
+
+        assert_eq!(holder.current_hp, max_hp - max_hp / 10);
+    }
+
+    #[test]
+    fn focus_sash_cancels_a_faint_from_full_hp() {
+        let attacker = testing::builder("Garchomp", &[PokeType::Ground], &["Earthquake"])
+            .level(100)
+            .build();
+        let frail = testing::builder("Abra", &[PokeType::Normal], &["Tackle"])
+            .level(5)
+            .item(Item::FocusSash)
+            .build();
+
+        let mut node = testing::play(
+            battle(vec![attacker], vec![frail]),
+            &["Earthquake", "Tackle"],
+        );
+        let log = node.drain_log();
+        let frail = node
+            .state()
+            .player(Player::Player2)
+            .active_pokemon()
+            .unwrap();
+
+        assert_eq!(frail.current_hp, 1);
+        assert_eq!(frail.item, None);
+        assert!(log
+            .iter()
+            .any(|event| matches!(event, LogEvent::HungOn { .. })));
+        assert!(!log
+            .iter()
+            .any(|event| matches!(event, LogEvent::Fainted { .. })));
+    }
+
+    #[test]
+    fn reflect_halves_physical_damage_for_five_turns() {
+        let setter = testing::builder("Snorlax", &[PokeType::Normal], &["Reflect", "Swords Dance"])
+            .level(100)
+            .build();
+        let attacker = testing::builder("Machamp", &[PokeType::Fighting], &["Tackle", "Swift"])
+            .level(60)
+            .build();
+        let node = battle(vec![setter.clone()], vec![attacker.clone()]);
+
+        let mut node = testing::play(
+            node,
+            &[
+                "Reflect",
+                "Tackle",
+                "Swords Dance",
+                "Swift",
+                "Swords Dance",
+                "Swift",
+                "Swords Dance",
+                "Swift",
+                "Swords Dance",
+                "Swift",
+                "Swords Dance",
+                "Tackle",
+            ],
+        );
+        let log = node.drain_log();
+        let damage: Vec<_> = log
+            .iter()
+            .filter_map(|event| match event {
+                LogEvent::Damage { amount, .. } => Some(*amount),
+                _ => None,
+            })
+            .collect();
+        let tackle = damage::compute_damage(&attacker, &setter, &attacker.moves[0], 0.85);
+        let swift = damage::compute_damage(&attacker, &setter, &attacker.moves[1], 0.85);
+
+        assert_eq!(damage[0], tackle / 2);
+        assert_eq!(damage[1], swift);
+        assert_eq!(damage[5], tackle);
+        assert!(log
+            .iter()
+            .any(|event| matches!(event, LogEvent::ScreenEnded { .. })));
+        assert_eq!(node.state().player(Player::Player1).side.reflect_turns, 0);
+    }
+
+    #[test]
+    fn rampages_confuse_the_user_when_they_end() {
+        let rampager = testing::builder("Tauros", &[PokeType::Normal], &["Thrash"])
+            .level(60)
+            .build();
+        let foe = testing::builder("Snorlax", &[PokeType::Normal], &["Swords Dance"])
+            .level(100)
+            .build();
+
+        let node = testing::play(
+            battle(vec![rampager], vec![foe]),
+            &["Thrash", "Swords Dance", "2 turns", "Swords Dance"],
+        );
+        let player_state = node.state().player(Player::Player1);
+        let thrash = &player_state.active_pokemon().unwrap().moves[0];
+
+        assert_eq!(node.state().turn(), 3);
+        assert_eq!(player_state.rampage, None);
+        assert!(player_state.is_confused());
+        assert_eq!(thrash.current_pp, thrash.max_pp - 1);
+    }
+
+    #[test]
+    fn hurting_itself_in_confusion_counts_as_a_rampage_turn() {
+        let rampager = testing::builder("Tauros", &[PokeType::Normal], &["Thrash"])
+            .level(60)
+            .build();
+        let foe = testing::builder("Snorlax", &[PokeType::Normal], &["Swords Dance"])
+            .level(100)
+            .build();
+
+        let node = testing::play(
+            battle(vec![rampager], vec![foe]),
+            &["Thrash", "Swords Dance", "2 turns"],
+        );
+        let mut state = node.into_state();
+        state
+            .player_mut(Player::Player1)
+            .effects
+            .push(Effect::Confusion { turns: 3 });
+
+        let node = testing::play(state.play_turn(), &["Swords Dance", "Hurt itself"]);
+
+        assert_eq!(node.state().player(Player::Player1).rampage, None);
+    }
+
+    #[test]
+    fn fake_out_works_on_the_turn_after_switching_in_mid_turn() {
+        let pivot = testing::builder("Scyther", &[PokeType::Bug], &["U-turn"]).build();
+        let fake_out = testing::builder("Hitmonlee", &[PokeType::Fighting], &["Fake Out"]).build();
+        let foe = testing::builder("Snorlax", &[PokeType::Normal], &["Swords Dance"])
+            .level(100)
+            .build();
+
+        let mut node = testing::play(
+            battle(vec![pivot, fake_out], vec![foe]),
+            &["U-turn", "Swords Dance", "Hitmonlee"],
+        );
+        node.drain_log();
+        let mut node = testing::play(node, &["Fake Out", "Swords Dance"]);
+        let log = node.drain_log();
+
+        assert!(log
+            .iter()
+            .any(|event| matches!(event, LogEvent::Flinched { pokemon } if pokemon == "Snorlax")));
+        assert!(!log
+            .iter()
+            .any(|event| matches!(event, LogEvent::MoveFailed)));
+    }
+
+    #[test]
+    fn status_moves_fail_once_the_user_is_taunted() {
+        let slow = testing::builder("Snorlax", &[PokeType::Normal], &["Swords Dance"]).build();
+        let taunter = testing::builder("Sableye", &[PokeType::Dark], &["Taunt"])
+            .level(100)
+            .build();
+
+        let node = testing::play(
+            battle(vec![slow], vec![taunter]),
+            &["Swords Dance", "Taunt"],
+        );
+        let player_state = node.state().player(Player::Player1);
+
+        assert!(player_state.is_taunted());
+        assert_eq!(player_state.stat_stages.attack, 0);
+    }
+
+    #[test]
+    fn leech_seed_drains_the_seeded_pokemon_into_its_foe() {
+        let seeder = testing::builder("Venusaur", &[PokeType::Grass], &["Leech Seed"]).build();
+        let seeded = testing::builder("Snorlax", &[PokeType::Normal], &["Tackle"])
+            .level(100)
+            .build();
+
+        let mut node = testing::play(
+            battle(vec![seeder], vec![seeded]),
+            &["Leech Seed", "Tackle"],
+        );
+        let healed = node.drain_log().iter().find_map(|event| match event {
+            LogEvent::Healed { pokemon, amount } if pokemon == "Venusaur" => Some(*amount),
+            _ => None,
+        });
+        let seeded = node
+            .state()
+            .player(Player::Player2)
+            .active_pokemon()
+            .unwrap();
+        let max_hp = seeded.computed_stat(StatKind::Hp);
+
+        assert_eq!(seeded.current_hp, max_hp - max_hp / 8);
+        assert_eq!(healed, Some(max_hp / 8));
+    }
+
+    #[test]
+    fn stamina_raises_defense_each_time_it_is_hit() {
+        let attacker = testing::builder("Rattata", &[PokeType::Normal], &["Tackle"]).build();
+        let mudsdale = testing::builder("Mudsdale", &[PokeType::Ground], &["Swords Dance"])
+            .level(100)
+            .ability(Ability::Stamina)
+            .build();
+
+        let node = testing::play(
+            battle(vec![attacker], vec![mudsdale]),
+            &["Tackle", "Swords Dance", "Tackle", "Swords Dance"],
+        );
+        let stages = &node.state().player(Player::Player2).stat_stages;
+
+        assert_eq!(stages.defense, 2);
+        assert_eq!(stages.attack, 4);
+    }
+
+    #[test]
+    fn the_level_cap_brings_every_pokemon_to_its_level() {
+        let rules = BattleRules {
+            level_cap: Some(50),
+            ..BattleRules::default()
+        };
+        let at_level = |level| {
+            testing::builder("Mew", &[PokeType::Psychic], &["Tackle"])
+                .level(level)
+                .build()
+        };
+        let node = State::start_with_rules(
+            testing::team(vec![at_level(100)]),
+            testing::team(vec![at_level(5)]),
+            rules,
+        )
+        .unwrap();
+
+        for &player in Player::values() {
+            let pokemon = &node.state().player(player).team[0];
+
+            assert_eq!(pokemon.level, 50);
+            assert_eq!(pokemon.current_hp, at_level(50).current_hp);
+        }
+    }
+
+    #[test]
+    fn there_is_no_active_pokemon_until_a_lead_is_chosen() {
+        let mut player_state = PlayerState::new(testing::team(vec![
+            testing::builder("Pikachu", &[PokeType::Electric], &["Tackle"]).build(),
+            testing::builder("Raichu", &[PokeType::Electric], &["Tackle"]).build(),
+        ]));
+
+        assert!(player_state.active_pokemon().is_none());
+        assert!(player_state.active_pokemon_mut().is_none());
+
+        player_state.active_pokemon_idx = Some(1);
+        player_state.active_pokemon_mut().unwrap().nickname = Some("Sparky".to_owned());
+
+        assert_eq!(player_state.team[1].nickname.as_deref(), Some("Sparky"));
+        assert_eq!(
+            player_state.active_pokemon().unwrap().to_string(),
+            "Sparky (Raichu)"
+        );
+    }
+
+    #[test]
+    fn teams_start_their_next_battle_at_full_resources() {
+        let attacker = testing::builder("Breloom", &[PokeType::Grass], &["Spore"]).build();
+        // Faster, so it gets a hit in before being put to sleep.
+        let foe = testing::builder("Snorlax", &[PokeType::Normal], &["Tackle"])
+            .level(100)
+            .build();
+
+        let node = testing::play(battle(vec![attacker], vec![foe]), &["Spore", "Tackle"]);
+        let (used_1, used_2) = {
+            let state = node.state();
+            (
+                state.player(Player::Player1).team.clone(),
+                state.player(Player::Player2).team.clone(),
+            )
+        };
+        assert!(used_1[0].current_hp < used_1[0].computed_stat(StatKind::Hp));
+        assert!(matches!(used_2[0].status, Status::Sleep { .. }));
+        assert!(used_1[0].moves[0].current_pp < used_1[0].moves[0].max_pp);
+
+        let node = State::start_with_leads(used_1, used_2, 0, 0, BattleRules::default()).unwrap();
+
+        for &player in Player::values() {
+            let pokemon = &node.state().player(player).team[0];
+
+            assert_eq!(pokemon.current_hp, pokemon.computed_stat(StatKind::Hp));
+            assert_eq!(pokemon.status, Status::Healthy);
+            assert_eq!(pokemon.moves[0].current_pp, pokemon.moves[0].max_pp);
+        }
+    }
+
+    #[test]
+    fn rain_dance_lasts_five_turns_and_fails_while_it_is_raining() {
+        let dancer = || testing::builder("Politoed", &[PokeType::Water], &["Rain Dance"]);
+        let foe = || testing::builder("Blissey", &[PokeType::Normal], &["Swords Dance"]).build();
+        let turn = ["Rain Dance", "Swords Dance"];
+
+        let mut node = testing::play(battle(vec![dancer().build()], vec![foe()]), &turn);
+        node.drain_log();
+        let mut node = testing::play(node, &turn);
+
+        assert!(node
+            .drain_log()
+            .iter()
+            .any(|event| matches!(event, LogEvent::MoveFailed)));
+
+        let node = testing::play(node, &turn.repeat(2));
+        assert_eq!(node.state().turn(), 5);
+        assert_eq!(node.state().weather, Weather::Rain);
+
+        let node = testing::play(node, &turn);
+        assert_eq!(node.state().weather, Weather::Clear);
+
+        let node = testing::play(
+            battle(vec![dancer().item(Item::DampRock).build()], vec![foe()]),
+            &turn,
+        );
+        assert_eq!(node.state().weather_turns, 7);
+    }
+
+    #[test]
+    fn predetermined_leads_skip_straight_to_the_first_turn() {
+        let team = || {
+            testing::team(vec![
+                testing::builder("Pikachu", &[PokeType::Electric], &["Tackle"]).build(),
+                testing::builder("Raichu", &[PokeType::Electric], &["Thunderbolt"]).build(),
+            ])
+        };
+
+        let node = testing::play(
+            State::start_with_leads(team(), team(), 1, 0, BattleRules::default()).unwrap(),
+            &[],
+        );
+        let state = node.state();
+
+        assert_eq!(state.turn(), 1);
+        assert_eq!(state.player(Player::Player1).active_pokemon_idx, Some(1));
+        assert_eq!(state.player(Player::Player2).active_pokemon_idx, Some(0));
+        match node.branches() {
+            state::Branches::Decision(d) => assert_eq!(d.name().to_string(), "Choose your action"),
+            other => panic!("Expected a decision, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn invalid_leads_stop_the_battle_from_starting() {
+        let team = || {
+            testing::team(vec![testing::builder(
+                "Pikachu",
+                &[PokeType::Electric],
+                &["Tackle"],
+            )
+            .build()])
+        };
+
+        assert!(matches!(
+            State::start_with_leads(team(), team(), 0, 1, BattleRules::default()),
+            Err(RulesViolation::InvalidLead { lead: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn infiltrator_hits_through_substitutes() {
+        let hit_substitute = |ability| {
+            let attacker = testing::builder("Crobat", &[PokeType::Poison], &["Tackle"])
+                .ability(ability)
+                .build();
+            // Faster, so the substitute is up before the hit.
+            let wall = testing::builder("Blissey", &[PokeType::Normal], &["Substitute"])
+                .level(100)
+                .build();
+            let node = testing::play(
+                battle(vec![attacker], vec![wall]),
+                &["Tackle", "Substitute"],
+            );
+            let wall_state = node.state().player(Player::Player2);
+            let wall = wall_state.active_pokemon().unwrap();
+            let cost = wall.computed_stat(StatKind::Hp) / 4;
+
+            (
+                wall.computed_stat(StatKind::Hp) - cost - wall.current_hp,
+                cost - wall_state.substitute_hp.unwrap(),
+            )
+        };
+
+        let (hp_lost, substitute_lost) = hit_substitute(Ability::NoAbility);
+        assert_eq!(hp_lost, 0);
+        assert!(substitute_lost > 0);
+
+        let (hp_lost, substitute_lost) = hit_substitute(Ability::Infiltrator);
+        assert!(hp_lost > 0);
+        assert_eq!(substitute_lost, 0);
+    }
+
+    #[test]
+    fn leppa_berries_restore_pp_to_a_move_that_runs_out() {
+        let holder = testing::builder("Snorlax", &[PokeType::Normal], &["Tackle", "Swords Dance"])
+            .item(Item::LeppaBerry)
+            .build();
+        let foe = testing::builder("Blissey", &[PokeType::Normal], &["Swords Dance"])
+            .level(100)
+            .build();
+
+        let node = testing::play(battle(vec![holder], vec![foe]), &[]);
+        let mut state = node.into_state();
+        state.player_mut(Player::Player1).team[0].moves[0].current_pp = 1;
+
+        let node = testing::play(state.play_turn(), &["Tackle", "Swords Dance"]);
+        let holder = node
+            .state()
+            .player(Player::Player1)
+            .active_pokemon()
+            .unwrap();
+
+        assert_eq!(holder.moves[0].current_pp, 10);
+        assert_eq!(holder.item, None);
+    }
+
+    #[test]
+    fn rest_heals_fully_and_sleeps_for_two_turns() {
+        let rester = || {
+            testing::builder("Snorlax", &[PokeType::Normal], &["Rest"])
+                .level(100)
+                .build()
+        };
+        let foe = || testing::builder("Blissey", &[PokeType::Normal], &["Swords Dance"]).build();
+
+        let mut node = testing::play(
+            battle(vec![rester()], vec![foe()]),
+            &["Rest", "Swords Dance"],
+        );
+        assert!(node
+            .drain_log()
+            .iter()
+            .any(|event| matches!(event, LogEvent::MoveFailed)));
+        assert_eq!(
+            node.state().player(Player::Player1).team[0].status,
+            Status::Healthy
+        );
+
+        let node = testing::play(battle(vec![rester()], vec![foe()]), &[]);
+        let mut state = node.into_state();
+        let pokemon = &mut state.player_mut(Player::Player1).team[0];
+        pokemon.current_hp /= 3;
+        pokemon.status = Status::Burn;
+
+        let node = testing::play(state.play_turn(), &["Rest", "Swords Dance"]);
+        let pokemon = &node.state().player(Player::Player1).team[0];
+
+        assert_eq!(pokemon.current_hp, pokemon.computed_stat(StatKind::Hp));
+        assert_eq!(pokemon.status, Status::Sleep { turns: 2 });
+    }
+
+    #[test]
+    fn rest_fails_when_the_user_cant_fall_asleep() {
+        let node = testing::play(
+            battle(
+                vec![testing::builder("Snorlax", &[PokeType::Normal], &["Rest"]).build()],
+                vec![testing::builder("Blissey", &[PokeType::Normal], &["Swords Dance"]).build()],
+            ),
+            &[],
+        );
+        let rest_fails = |mut state: State| {
+            let before = state.player(Player::Player1).team[0].clone();
+            state.drain_log();
+            state.rest(Player::Player1);
+
+            let failed = state
+                .drain_log()
+                .iter()
+                .any(|event| matches!(event, LogEvent::MoveFailed));
+            failed && state.player(Player::Player1).team[0] == before
+        };
+
+        let mut misty = node.into_state();
+        misty.player_mut(Player::Player1).team[0].current_hp /= 3;
+        let mut asleep = misty.clone();
+        misty.terrain = Terrain::Misty;
+        asleep.player_mut(Player::Player1).team[0].status = Status::Sleep { turns: 1 };
+
+        assert!(rest_fails(misty));
+        assert!(rest_fails(asleep));
+    }
+
+    #[test]
+    fn recover_heals_half_of_max_hp_and_fails_at_full_hp() {
+        let node = testing::play(
+            battle(
+                vec![testing::builder("Snorlax", &[PokeType::Normal], &["Recover"]).build()],
+                vec![testing::builder("Blissey", &[PokeType::Normal], &["Swords Dance"]).build()],
+            ),
+            &[],
+        );
+        let mut state = node.into_state();
+        let max_hp = state.player(Player::Player1).team[0].computed_stat(StatKind::Hp);
+        state.player_mut(Player::Player1).team[0].current_hp = max_hp * 3 / 10;
+
+        let mut node = testing::play(state.play_turn(), &["Recover", "Swords Dance"]);
+        node.drain_log();
+        assert_eq!(
+            node.state().player(Player::Player1).team[0].current_hp,
+            max_hp * 3 / 10 + max_hp / 2
+        );
+
+        let mut state = node.into_state();
+        state.player_mut(Player::Player1).team[0].current_hp = max_hp;
+        let mut node = testing::play(state.play_turn(), &["Recover", "Swords Dance"]);
+
+        assert!(node
+            .drain_log()
+            .iter()
+            .any(|event| matches!(event, LogEvent::MoveFailed)));
+    }
+
+    #[test]
+    fn roosting_drops_the_flying_weakness_for_the_turn() {
+        let pidgeot = testing::builder(
+            "Pidgeot",
+            &[PokeType::Normal, PokeType::Flying],
+            &["Roost", "Swords Dance"],
+        )
+        .level(100)
+        .build();
+        let foe = testing::builder("Pikachu", &[PokeType::Electric], &["Thunderbolt"]).build();
+
+        let node = testing::play(battle(vec![pidgeot], vec![foe]), &[]);
+        let mut state = node.into_state();
+        state.player_mut(Player::Player1).team[0].current_hp /= 2;
+
+        let effectiveness = |node: &mut Node| {
+            node.drain_log().into_iter().find_map(|event| match event {
+                LogEvent::Damage { effectiveness, .. } => Some(effectiveness),
+                _ => None,
+            })
+        };
+
+        let mut node = testing::play(state.play_turn(), &["Roost", "Thunderbolt"]);
+        assert_eq!(effectiveness(&mut node), Some(TypeEffectiveness::Regular));
+
+        let mut node = testing::play(node, &["Swords Dance", "Thunderbolt"]);
+        assert_eq!(
+            effectiveness(&mut node),
+            Some(TypeEffectiveness::SuperEffective)
+        );
+    }
+
+    #[test]
+    fn the_last_move_is_tracked_until_switching_out() {
+        let user =
+            testing::builder("Scizor", &[PokeType::Bug], &["Tackle", "Swords Dance"]).build();
+        let bench = testing::builder("Magnezone", &[PokeType::Electric], &["Tackle"]).build();
+        let foe = testing::builder("Blissey", &[PokeType::Normal], &["Swords Dance"])
+            .level(100)
+            .build();
+
+        let node = testing::play(battle(vec![user, bench], vec![foe]), &[]);
+        assert_eq!(node.state().player(Player::Player1).last_move(), None);
+
+        let node = testing::play(node, &["Swords Dance", "Swords Dance"]);
+        assert_eq!(node.state().player(Player::Player1).last_move(), Some(1));
+
+        let node = testing::play(node, &["Tackle", "Swords Dance"]);
+        assert_eq!(node.state().player(Player::Player1).last_move(), Some(0));
+
+        let node = testing::play(node, &["Magnezone", "Swords Dance"]);
+        assert_eq!(node.state().player(Player::Player1).last_move(), None);
+    }
+
+    #[test]
+    fn knock_off_removes_items_unless_they_are_held_stickily() {
+        let knocked = |ability| {
+            let attacker = testing::builder("Weavile", &[PokeType::Dark], &["Knock Off"]).build();
+            let holder = testing::builder("Snorlax", &[PokeType::Normal], &["Swords Dance"])
+                .level(100)
+                .item(Item::Leftovers)
+                .ability(ability)
+                .build();
+            let node = testing::play(
+                battle(vec![attacker], vec![holder]),
+                &["Knock Off", "Swords Dance"],
+            );
+
+            node.state().player(Player::Player2).team[0].item.clone()
+        };
+
+        assert_eq!(knocked(Ability::NoAbility), None);
+        assert_eq!(knocked(Ability::StickyHold), Some(Item::Leftovers));
+
+        let knock_off: PokeMove = "Knock Off".parse().unwrap();
+        let user = testing::builder("Weavile", &[PokeType::Dark], &[]).build();
+        let with_item = testing::builder("Snorlax", &[PokeType::Normal], &[])
+            .item(Item::Leftovers)
+            .build();
+
+        assert_eq!(
+            knock_off.power_against(&user, &with_item),
+            knock_off.power * 3 / 2
+        );
+        assert_eq!(knock_off.power_against(&user, &user), knock_off.power);
+    }
+
+    #[test]
+    fn tricking_a_choice_scarf_onto_a_wall_locks_its_next_move() {
+        let tricker = testing::builder("Alakazam", &[PokeType::Psychic], &["Trick"])
+            .item(Item::ChoiceScarf)
+            .build();
+        let wall = testing::builder("Blissey", &[PokeType::Normal], &["Swords Dance", "Tackle"])
+            .item(Item::Leftovers)
+            .build();
+
+        let node = testing::play(
+            battle(vec![tricker], vec![wall]),
+            &["Trick", "Swords Dance"],
+        );
+        let state = node.state();
+
+        assert_eq!(
+            state.player(Player::Player1).team[0].item,
+            Some(Item::Leftovers)
+        );
+        assert_eq!(
+            state.player(Player::Player2).team[0].item,
+            Some(Item::ChoiceScarf)
+        );
+        assert_eq!(state.player(Player::Player2).choice_lock, Some(0));
+
+        let node = testing::play(node, &["Trick"]);
+        assert_eq!(
+            action_choices(&node),
+            [
+                ("Swords Dance".to_owned(), true),
+                ("Tackle".to_owned(), false)
+            ]
+        );
+    }
+
+    #[test]
+    fn sleep_clause_allows_one_sleeper_at_a_time() {
+        let rules = BattleRules {
+            sleep_clause: true,
+            ..BattleRules::default()
+        };
+        let breloom = testing::builder("Breloom", &[PokeType::Grass], &["Spore"]).build();
+        let foe = |name| testing::builder(name, &[PokeType::Normal], &["Swords Dance"]).build();
+        let mut state = State::new(
+            testing::team(vec![breloom]),
+            testing::team(vec![foe("Snorlax"), foe("Munchlax")]),
+            rules,
+        )
+        .unwrap();
+        state.player_1.active_pokemon_idx = Some(0);
+        state.player_2.active_pokemon_idx = Some(0);
+        let is_asleep = |node: &Node, idx: usize| {
+            matches!(
+                node.state().player(Player::Player2).team[idx].status,
+                Status::Sleep { .. }
+            )
+        };
+
+        let node = testing::play(state.play_turn(), &["Spore", "Swords Dance"]);
+        assert!(is_asleep(&node, 0));
+
+        let node = testing::play(node, &["Spore", "Munchlax"]);
+        assert!(!is_asleep(&node, 1));
+
+        let mut state = node.into_state();
+        state.player_2.team[0].status = Status::Healthy;
+
+        let node = testing::play(state.play_turn(), &["Spore", "Swords Dance"]);
+        assert!(is_asleep(&node, 1));
+    }
+
+    #[test]
+    fn electric_terrain_only_boosts_grounded_attackers() {
+        let thunderbolt_damage = |types: &[PokeType], script: &[&str]| {
+            let attacker =
+                testing::builder("Attacker", types, &["Thunderbolt", "Electric Terrain"]).build();
+            let foe = testing::builder("Blissey", &[PokeType::Normal], &["Swords Dance"])
+                .level(100)
+                .build();
+            let node = testing::play(battle(vec![attacker], vec![foe]), script);
+            let foe = node
+                .state()
+                .player(Player::Player2)
+                .active_pokemon()
+                .unwrap();
+
+            f64::from(foe.computed_stat(StatKind::Hp) - foe.current_hp)
+        };
+        let clear = ["Thunderbolt", "Swords Dance"];
+        let terrain = [
+            "Electric Terrain",
+            "Swords Dance",
+            "Thunderbolt",
+            "Swords Dance",
+        ];
+
+        let grounded = [PokeType::Electric];
+        let boost = thunderbolt_damage(&grounded, &terrain) / thunderbolt_damage(&grounded, &clear);
+        assert!((boost - 1.3).abs() < 0.05);
+
+        let flying = [PokeType::Electric, PokeType::Flying];
+        assert_eq!(
+            thunderbolt_damage(&flying, &terrain),
+            thunderbolt_damage(&flying, &clear)
+        );
+    }
+
+    #[test]
+    fn thick_fat_halves_fire_damage_and_dry_skin_absorbs_water() {
+        let hp_change = |ability, pokemove: &'static str, wounded: bool| {
+            let attacker = testing::builder("Attacker", &[PokeType::Normal], &[pokemove]).build();
+            let defender = testing::builder("Defender", &[PokeType::Normal], &["Swords Dance"])
+                .level(100)
+                .ability(ability)
+                .build();
+            let node = testing::play(battle(vec![attacker], vec![defender]), &[]);
+            let mut state = node.into_state();
+            let before = {
+                let defender = &mut state.player_mut(Player::Player2).team[0];
+                if wounded {
+                    defender.current_hp /= 2;
+                }
+                defender.current_hp
+            };
+
+            let node = testing::play(state.play_turn(), &[pokemove, "Swords Dance"]);
+            i64::from(node.state().player(Player::Player2).team[0].current_hp) - i64::from(before)
+        };
+
+        let normal = hp_change(Ability::NoAbility, "Flamethrower", false);
+        let thick_fat = hp_change(Ability::ThickFat, "Flamethrower", false);
+        assert!(normal < 0);
+        assert!(((thick_fat as f64) / (normal as f64) - 0.5).abs() < 0.05);
+
+        let max_hp = testing::builder("Defender", &[PokeType::Normal], &[])
+            .level(100)
+            .build()
+            .computed_stat(StatKind::Hp);
+        assert_eq!(
+            hp_change(Ability::DrySkin, "Surf", true),
+            i64::from(max_hp / 4)
+        );
+    }
+
+    #[test]
+    fn u_turn_offers_a_switch_after_hitting() {
+        let team = || {
+            vec![
+                testing::builder("Scizor", &[PokeType::Bug], &["U-turn"]).build(),
+                testing::builder("Magnezone", &[PokeType::Electric], &["Tackle"]).build(),
+            ]
+        };
+        let foe = || testing::builder("Blissey", &[PokeType::Normal], &["Swords Dance"]).build();
+
+        let node = testing::play(battle(team(), vec![foe()]), &["U-turn", "Swords Dance"]);
+        let hit = node
+            .state()
+            .player(Player::Player2)
+            .active_pokemon()
+            .unwrap();
+        assert!(hit.current_hp < hit.computed_stat(StatKind::Hp));
+        match node.branches() {
+            state::Branches::Decision(d) => {
+                assert_eq!(d.name().to_string(), "Choose a pokemon to switch to");
+                assert_eq!(d.player(), Player::Player1);
+            }
+            other => panic!("Expected a decision, got {:?}", other),
+        }
+        assert_eq!(action_choices(&node), [("Magnezone".to_owned(), true)]);
+
+        let node = testing::play(battle(team(), vec![foe()]), &[]);
+        let mut state = node.into_state();
+        state.player_1.team[1].current_hp = 0;
+
+        let node = testing::play(state.play_turn(), &["U-turn", "Swords Dance"]);
+        assert_eq!(node.state().turn(), 2);
+        assert_eq!(
+            node.state().player(Player::Player1).active_pokemon_idx,
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn magic_guard_ignores_life_orb_recoil_and_sandstorm() {
+        let play = |item: Option<Item>| {
+            let mut clefable = testing::builder("Clefable", &[PokeType::Normal], &["Tackle"])
+                .ability(Ability::MagicGuard);
+            if let Some(item) = item {
+                clefable = clefable.item(item);
+            }
+            let tyranitar = testing::builder("Tyranitar", &[PokeType::Rock], &["Sandstorm"])
+                .level(100)
+                .build();
+            let node = testing::play(
+                battle(vec![clefable.build()], vec![tyranitar]),
+                &["Tackle", "Sandstorm"],
+            );
+            let state = node.state();
+            let clefable = state.player(Player::Player1).active_pokemon().unwrap();
+            let tyranitar = state.player(Player::Player2).active_pokemon().unwrap();
+
+            assert_eq!(state.weather, Weather::Sandstorm);
+            assert_eq!(clefable.current_hp, clefable.computed_stat(StatKind::Hp));
+            f64::from(tyranitar.computed_stat(StatKind::Hp) - tyranitar.current_hp)
+        };
+
+        let boost = play(Some(Item::LifeOrb)) / play(None);
+        assert!((boost - 1.3).abs() < 0.1);
+    }
+
+    #[test]
+    fn psychic_terrain_blocks_priority_against_grounded_targets() {
+        let quick_attack_damage = |types: &[PokeType]| {
+            let attacker = testing::builder(
+                "Rattata",
+                &[PokeType::Normal],
+                &["Quick Attack", "Swords Dance"],
+            )
+            .build();
+            let target = testing::builder("Target", types, &["Psychic Terrain", "Swords Dance"])
+                .level(100)
+                .build();
+            let node = testing::play(
+                battle(vec![attacker], vec![target]),
+                &[
+                    "Swords Dance",
+                    "Psychic Terrain",
+                    "Quick Attack",
+                    "Swords Dance",
+                ],
+            );
+            let state = node.state();
+            let target = state.player(Player::Player2).active_pokemon().unwrap();
+
+            assert_eq!(state.terrain, Terrain::Psychic);
+            target.computed_stat(StatKind::Hp) - target.current_hp
+        };
+
+        assert_eq!(quick_attack_damage(&[PokeType::Psychic]), 0);
+        assert!(quick_attack_damage(&[PokeType::Psychic, PokeType::Flying]) > 0);
+    }
+
+    #[test]
+    fn priority_moves_go_before_faster_foes() {
+        let slow = testing::builder("Rattata", &[PokeType::Normal], &["Quick Attack"]).build();
+        let fast = testing::builder("Jolteon", &[PokeType::Electric], &["Tackle"])
+            .level(100)
+            .build();
+
+        let mut node = testing::play(battle(vec![slow], vec![fast]), &["Quick Attack", "Tackle"]);
+        let moves: Vec<_> = node
+            .drain_log()
+            .into_iter()
+            .filter_map(|event| match event {
+                LogEvent::MoveUsed { pokemove, .. } => Some(pokemove),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(moves, ["Quick Attack", "Tackle"]);
+    }
+
+    #[test]
+    fn equally_fast_pokemon_tie_on_a_coin_flip() {
+        let pokemon = || testing::builder("Pikachu", &[PokeType::Electric], &["Tackle"]).build();
+
+        let node = testing::play(battle(vec![pokemon()], vec![pokemon()]), &[]);
+        let node = node
+            .resolve_decision(0)
+            .unwrap()
+            .resolve_decision(0)
+            .unwrap();
+        let chance = match node.branches() {
+            state::Branches::Chance(c) => c,
+            other => panic!("Expected a speed tie, got {:?}", other),
+        };
+        let weights: Vec<_> = chance.possibilities().iter().map(|p| p.weight()).collect();
+
+        assert_eq!(chance.name().to_string(), "Speed tie");
+        assert_eq!(weights, [0.5, 0.5]);
+    }
+
+    #[test]
+    fn fainted_pokemon_are_replaced_before_the_next_turn() {
+        let attacker = testing::builder("Machamp", &[PokeType::Fighting], &["Tackle"])
+            .level(100)
+            .build();
+        let weak = testing::builder("Rattata", &[PokeType::Normal], &["Tackle"])
+            .level(5)
+            .build();
+        let bench = testing::builder("Snorlax", &[PokeType::Normal], &["Tackle"]).build();
+
+        let node = testing::play(
+            battle(vec![attacker], vec![weak, bench]),
+            &["Tackle", "Tackle"],
+        );
+        match node.branches() {
+            state::Branches::Decision(d) => assert_eq!(d.player(), Player::Player2),
+            other => panic!("Expected a forced switch, got {:?}", other),
+        }
+        assert_eq!(action_choices(&node), [("Snorlax".to_owned(), true)]);
+
+        let node = testing::play(node, &["Snorlax"]);
+        let state = node.state();
+
+        assert_eq!(state.turn(), 2);
+        assert_eq!(state.player(Player::Player2).active_pokemon_idx, Some(1));
+        assert_eq!(action_choices(&node), [("Tackle".to_owned(), true)]);
+    }
+
+    #[test]
+    fn battles_end_when_a_side_whites_out() {
+        let attacker = testing::builder("Machamp", &[PokeType::Fighting], &["Tackle"])
+            .level(100)
+            .build();
+        let weak = testing::builder("Rattata", &[PokeType::Normal], &["Tackle"])
+            .level(5)
+            .build();
+
+        let node = testing::play(battle(vec![attacker], vec![weak]), &["Tackle", "Tackle"]);
+
+        assert!(matches!(node.branches(), state::Branches::End));
+        assert!(node.state().player(Player::Player2).team[0].is_fainted());
+    }
+
+    #[test]
+    fn fainting_the_last_pokemon_wins_the_battle() {
+        let attacker = testing::builder("Machamp", &[PokeType::Fighting], &["Tackle"])
+            .level(100)
+            .build();
+        let weak = || {
+            testing::builder("Rattata", &[PokeType::Normal], &["Tackle"])
+                .level(5)
+                .build()
+        };
+
+        let node = testing::play(
+            battle(vec![attacker], vec![weak(), weak()]),
+            &["Tackle", "Tackle", "Rattata"],
+        );
+        assert_eq!(node.state().winner(), None);
+
+        let node = testing::play(node, &["Tackle", "Tackle"]);
+
+        assert!(matches!(node.branches(), state::Branches::End));
+        assert_eq!(node.state().winner(), Some(Player::Player1));
+    }
+
+    /// Records every switch hook that fires, and for which player.
+    #[derive(Default)]
+    struct SwitchRecorder(RefCell<Vec<(&'static str, Player)>>);
+
+    impl EventHandler<State> for SwitchRecorder {
+        fn on_switch_in(&self, state: State, player: Player) -> Node {
+            self.0.borrow_mut().push(("in", player));
+            Node::pending(state)
+        }
+
+        fn on_switch_out(&self, state: State, player: Player) -> Node {
+            self.0.borrow_mut().push(("out", player));
+            Node::pending(state)
+        }
+
+        fn on_etb(&self, state: State, player: Player) -> Node {
+            self.0.borrow_mut().push(("etb", player));
+            Node::pending(state)
+        }
+    }
+
+    #[test]
+    fn switching_runs_the_installed_handlers_out_then_in() {
+        let rattata = || testing::builder("Rattata", &[PokeType::Normal], &["Tackle"]).build();
+        let pidgey = testing::builder("Pidgey", &[PokeType::Normal], &["Tackle"]).build();
+        let mut state =
+            testing::play(battle(vec![rattata(), pidgey], vec![rattata()]), &[]).into_state();
+        let recorder = Rc::new(SwitchRecorder::default());
+        state.add_handler(recorder.clone());
+
+        testing::play(state.play_turn(), &["Pidgey", "Tackle"]);
+
+        assert_eq!(
+            *recorder.0.borrow(),
+            [
+                ("out", Player::Player1),
+                ("in", Player::Player1),
+                ("etb", Player::Player1)
+            ]
+        );
+    }
+
+    /// Records every damaging hit the damage hooks hear about, from both sides of it.
+    #[derive(Default)]
+    struct DamageRecorder(RefCell<Vec<(&'static str, Player, Player, u32)>>);
+
+    impl EventHandler<State> for DamageRecorder {
+        fn on_damage_dealt(
+            &self,
+            state: State,
+            attacker: Player,
+            target: Player,
+            _pokemove: &PokeMove,
+            amount: u32,
+        ) -> Node {
+            self.0
+                .borrow_mut()
+                .push(("dealt", attacker, target, amount));
+            Node::pending(state)
+        }
+
+        fn on_damage_taken(
+            &self,
+            state: State,
+            defender: Player,
+            attacker: Player,
+            _pokemove: &PokeMove,
+            amount: u32,
+        ) -> Node {
+            self.0
+                .borrow_mut()
+                .push(("taken", defender, attacker, amount));
+            Node::pending(state)
+        }
+    }
+
+    #[test]
+    fn damage_hooks_reach_the_installed_handlers() {
+        let rattata = || testing::builder("Rattata", &[PokeType::Normal], &["Tackle"]).build();
+        let mut state = testing::play(battle(vec![rattata()], vec![rattata()]), &[]).into_state();
+        let recorder = Rc::new(DamageRecorder::default());
+        state.add_handler(recorder.clone());
+
+        let node = testing::play(state.play_turn(), &["Tackle", "Tackle"]);
+
+        let lost = |player| {
+            let pokemon = node.state().player(player).active_pokemon().unwrap();
+            pokemon.computed_stat(StatKind::Hp) - pokemon.current_hp
+        };
+        let (to_player_2, to_player_1) = (lost(Player::Player2), lost(Player::Player1));
+        assert_eq!(
+            *recorder.0.borrow(),
+            [
+                ("dealt", Player::Player1, Player::Player2, to_player_2),
+                ("taken", Player::Player2, Player::Player1, to_player_2),
+                ("dealt", Player::Player2, Player::Player1, to_player_1),
+                ("taken", Player::Player1, Player::Player2, to_player_1)
+            ]
+        );
     }
 }
@@ -1,8 +1,48 @@
 use std::{
-    fmt::{self, Debug, Formatter},
-    ops::Try,
+    any::Any,
+    fmt::{self, Debug, Display, Formatter},
 };
 
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    log::{BattleLog, LogEvent},
+    pokemove::PokeMove,
+    status::Status,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The text shown for a decision, choice or possibility.
+///
+/// Front-ends can translate `Key` labels, while `Raw` labels are shown as they are.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Label {
+    Raw(String),
+    Key(&'static str),
+}
+
+impl Display for Label {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Label::Raw(s) => write!(f, "{}", s),
+            Label::Key(k) => write!(f, "{}", k),
+        }
+    }
+}
+
+impl From<String> for Label {
+    fn from(s: String) -> Self {
+        Label::Raw(s)
+    }
+}
+
+impl From<&str> for Label {
+    fn from(s: &str) -> Self {
+        Label::Raw(s.to_owned())
+    }
+}
+
 pub trait StateBase: Clone + Debug {
     type Player: PlayerBase;
     type PlayerState: PlayerStateBase;
@@ -10,6 +50,16 @@ pub trait StateBase: Clone + Debug {
     fn player(&self, player: Self::Player) -> &Self::PlayerState;
     fn player_mut(&mut self, player: Self::Player) -> &mut Self::PlayerState;
 
+    /// Takes the log events emitted since the last call.
+    fn drain_log(&mut self) -> Vec<LogEvent> {
+        Vec::new()
+    }
+
+    /// How chances built on this state branch.
+    fn rng_behavior(&self) -> RngBehavior {
+        RngBehavior::Branching
+    }
+
     fn fold<I, F>(self, iter: I, f: F) -> Node<Self>
     where
         Self: 'static,
@@ -22,21 +72,186 @@ pub trait StateBase: Clone + Debug {
                 node.then(move |state| f(state, item))
             })
     }
+
+    /// Like `fold`, but only moves on to the next item once the previous step is `Pending`.
+    ///
+    /// As soon as a step ends, its `End` node is returned and the rest of the items are never
+    /// looked at, e.g. when one player's action finishes the battle.
+    fn try_fold<I, F>(self, iter: I, f: F) -> Node<Self>
+    where
+        Self: 'static,
+        I: IntoIterator,
+        I::IntoIter: Clone + 'static,
+        F: Fn(Self, I::Item) -> Node<Self> + Clone + 'static,
+    {
+        let mut iter = iter.into_iter();
+
+        match iter.next() {
+            Some(item) => f(self, item).then(move |state| state.try_fold(iter, f)),
+            None => Node::pending(self),
+        }
+    }
+}
+
+/// How a state's chances are built.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum RngBehavior {
+    /// Every possibility gets its own branch.
+    Branching,
+    /// Only the most likely possibility is kept, or the first of the most likely ones, so that only
+    /// decisions branch. This makes the tree far smaller at the cost of ignoring luck.
+    MostLikely,
 }
 
 pub trait PlayerBase: Debug + Clone + Copy {
     fn values() -> &'static [Self];
 }
 
-pub trait PlayerStateBase: Debug + Clone {}
+pub trait PlayerStateBase: Debug + Clone {
+    /// Describes what changed since `before`, one line per change.
+    fn describe_changes(&self, _before: &Self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// Describes what changed for each player between two states, e.g. after taking a branch.
+pub fn diff_states<S>(before: &S, after: &S) -> Vec<String>
+where
+    S: StateBase,
+    S::Player: 'static,
+{
+    S::Player::values()
+        .iter()
+        .flat_map(|&player| {
+            after
+                .player(player)
+                .describe_changes(before.player(player))
+                .into_iter()
+                .map(move |change| format!("{:?}'s {}", player, change))
+        })
+        .collect()
+}
+
+/// Picks a choice for a player whenever a driver reaches a `Decision`.
+///
+/// The chosen index should be one of `Decision::enabled_choices`. Drivers fall back to the first
+/// enabled choice if it's disabled or out of range.
+pub trait DecisionPolicy<S>
+where
+    S: StateBase,
+{
+    fn choose(&mut self, state: &S, decision: &Decision<S>) -> usize;
+}
+
+/// Reacts to what happens in a battle on behalf of a pokemon, through its ability, item or
+/// effects. Each hook is given the position of the handler's pokemon, which is the player it
+/// belongs to unless the state has its own kind of position, like the slots of a double battle.
+pub trait EventHandler<S, P = <S as StateBase>::Player>
+where
+    S: StateBase,
+    P: Copy,
+{
+    fn on_turn_start(&self, state: S, _position: P) -> Node<S> {
+        Node::pending(state)
+    }
+
+    fn on_turn_end(&self, state: S, _position: P) -> Node<S> {
+        Node::pending(state)
+    }
+
+    /// Whether `on_turn_end` would do anything for the handler's pokemon, at `position`, so that
+    /// end of turn effects are only ordered between the pokemon they affect.
+    fn acts_on_turn_end(&self, _state: &S, _position: P) -> bool {
+        false
+    }
+
+    /// Called when the handler's pokemon, at `position`, enters the battle, just after
+    /// `on_switch_in`.
+    fn on_etb(&self, state: S, _position: P) -> Node<S> {
+        Node::pending(state)
+    }
+
+    /// Called when the handler's pokemon, at `position`, is sent into the battle, whether as a
+    /// lead or as a switch, just before `on_etb`.
+    fn on_switch_in(&self, state: S, _position: P) -> Node<S> {
+        Node::pending(state)
+    }
+
+    /// Called when the handler's pokemon, at `position`, is about to be switched out while still
+    /// standing.
+    fn on_switch_out(&self, state: S, _position: P) -> Node<S> {
+        Node::pending(state)
+    }
+
+    /// Called once the action or end of turn that made the handler's pokemon, at `target`, lose
+    /// `amount` HP is over, whatever the source of the damage.
+    fn on_damage(&self, state: S, _target: P, _amount: u32) -> Node<S> {
+        Node::pending(state)
+    }
+
+    /// Called after `on_damage` when that damage, `amount` HP, brought the handler's pokemon, at
+    /// `fainted`, down to 0 HP. It only faints if it still has no HP left afterwards, so this can
+    /// save it.
+    fn on_faint(&self, state: S, _fainted: P, _amount: u32) -> Node<S> {
+        Node::pending(state)
+    }
+
+    /// Called once the action or end of turn that gave the handler's pokemon, at `target`,
+    /// `status` is over.
+    fn on_status_applied(&self, state: S, _target: P, _status: Status) -> Node<S> {
+        Node::pending(state)
+    }
+
+    /// Called after the handler's pokemon, at `attacker`, dealt `amount` damage to the pokemon at
+    /// `target` with `pokemove`, or to its substitute.
+    fn on_damage_dealt(
+        &self,
+        state: S,
+        _attacker: P,
+        _target: P,
+        _pokemove: &PokeMove,
+        _amount: u32,
+    ) -> Node<S> {
+        Node::pending(state)
+    }
+
+    /// Called after the handler's pokemon, at `defender`, took `amount` damage from `pokemove`,
+    /// used by the pokemon at `attacker`.
+    fn on_damage_taken(
+        &self,
+        state: S,
+        _defender: P,
+        _attacker: P,
+        _pokemove: &PokeMove,
+        _amount: u32,
+    ) -> Node<S> {
+        Node::pending(state)
+    }
+}
+
+type ChoiceFilter<T> = Box<dyn Fn(&T) -> bool>;
+
+type Continuation<S> = Box<dyn FnOnce(S) -> Node<S>>;
+
+/// Extra data a front-end can render next to a choice, such as the move being offered.
+type ChoiceMeta = Box<dyn Any>;
+
+enum ChoiceEntry<T> {
+    Enabled(T),
+    /// Shown to the player but not selectable, with the reason why.
+    Disabled(String),
+}
 
 pub struct DecisionBuilder<S, T>
 where
     S: StateBase,
 {
     player: S::Player,
-    name: String,
-    choices: Vec<(String, T)>,
+    name: Label,
+    choices: Vec<(Label, ChoiceEntry<T>, Option<ChoiceMeta>)>,
+    filters: Vec<ChoiceFilter<T>>,
+    default: Option<usize>,
 }
 
 impl<S, T> DecisionBuilder<S, T>
@@ -46,58 +261,195 @@ where
 {
     pub fn new<N>(name: N, player: S::Player) -> Self
     where
-        N: Into<String>,
+        N: Into<Label>,
     {
         Self {
             name: name.into(),
             player,
             choices: Vec::new(),
+            filters: Vec::new(),
+            default: None,
         }
     }
 
+    /// Marks the choice at `idx`, counting every choice added so far, as the one drivers pick
+    /// without asking. It's ignored if that choice ends up disabled or filtered out.
+    pub fn default_choice_index(mut self, idx: usize) -> Self {
+        self.default = Some(idx);
+        self
+    }
+
+    /// Drops every choice whose payload doesn't satisfy `f` when the decision is built.
+    pub fn filter<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&T) -> bool + 'static,
+    {
+        self.filters.push(Box::new(f));
+        self
+    }
+
     pub fn named_choice<N>(mut self, name: N, choice: T) -> Self
     where
-        N: Into<String>,
+        N: Into<Label>,
     {
-        self.choices.push((name.into(), choice));
+        self.choices
+            .push((name.into(), ChoiceEntry::Enabled(choice), None));
+        self
+    }
+
+    /// Adds a choice carrying `meta`, which can be read back with `Choice::meta`.
+    pub fn named_choice_with_meta<N, M>(mut self, name: N, choice: T, meta: M) -> Self
+    where
+        N: Into<Label>,
+        M: Any,
+    {
+        self.choices.push((
+            name.into(),
+            ChoiceEntry::Enabled(choice),
+            Some(Box::new(meta)),
+        ));
+        self
+    }
+
+    /// Adds a choice labelled by a translation key rather than a display string.
+    pub fn keyed_choice(self, key: &'static str, choice: T) -> Self {
+        self.named_choice(Label::Key(key), choice)
+    }
+
+    /// Adds a choice which is listed in the decision but can't be selected, such as a move that
+    /// is out of PP.
+    pub fn named_choice_disabled<N>(mut self, name: N, reason: String) -> Self
+    where
+        N: Into<Label>,
+    {
+        self.choices
+            .push((name.into(), ChoiceEntry::Disabled(reason), None));
+        self
+    }
+
+    /// Like `named_choice_disabled`, for a choice carrying `meta` like `named_choice_with_meta`.
+    pub fn named_choice_disabled_with_meta<N, M>(mut self, name: N, reason: String, meta: M) -> Self
+    where
+        N: Into<Label>,
+        M: Any,
+    {
+        self.choices.push((
+            name.into(),
+            ChoiceEntry::Disabled(reason),
+            Some(Box::new(meta)),
+        ));
         self
     }
 
     pub fn named_choices<N, I>(mut self, choices: I) -> Self
     where
-        N: Into<String>,
+        N: Into<Label>,
         I: IntoIterator<Item = (N, T)>,
     {
-        self.choices
-            .extend(choices.into_iter().map(|(n, t)| (n.into(), t)));
+        self.choices.extend(
+            choices
+                .into_iter()
+                .map(|(n, t)| (n.into(), ChoiceEntry::Enabled(t), None)),
+        );
         self
     }
 
+    /// Builds the decision node.
+    ///
+    /// A decision with no enabled choices could never be resolved, so in that case `f` is never
+    /// called and the state is passed on unchanged as `Node::pending`.
     pub fn build<F>(self, state: S, f: F) -> Node<S>
     where
         F: FnOnce(S, T) -> Node<S> + Clone + 'static,
     {
-        Node {
+        self.build_or(state, f, Node::pending)
+    }
+
+    /// Builds the decision node, or hands the state to `fallback` if there are no enabled choices.
+    pub fn build_or<F, G>(self, state: S, f: F, fallback: G) -> Node<S>
+    where
+        F: FnOnce(S, T) -> Node<S> + Clone + 'static,
+        G: FnOnce(S) -> Node<S>,
+    {
+        match self.try_build(state, f) {
+            Ok(node) => node,
+            Err(DecisionError::NoChoices(state)) => fallback(state),
+        }
+    }
+
+    /// Builds the decision node, failing if there are no enabled choices to pick from.
+    pub fn try_build<F>(mut self, state: S, f: F) -> Result<Node<S>, DecisionError<S>>
+    where
+        F: FnOnce(S, T) -> Node<S> + Clone + 'static,
+    {
+        let filters = self.filters;
+        let declared = self.default;
+        let mut default = None;
+        self.choices = self
+            .choices
+            .into_iter()
+            .enumerate()
+            .filter(|(_, (_, c, _))| match c {
+                ChoiceEntry::Enabled(c) => filters.iter().all(|filter| filter(c)),
+                ChoiceEntry::Disabled(_) => true,
+            })
+            .enumerate()
+            .map(|(new_idx, (idx, choice))| {
+                if declared == Some(idx) && matches!(choice.1, ChoiceEntry::Enabled(_)) {
+                    default = Some(new_idx);
+                }
+
+                choice
+            })
+            .collect();
+
+        let any_enabled = self.choices.iter().any(|(_, c, _)| match c {
+            ChoiceEntry::Enabled(_) => true,
+            ChoiceEntry::Disabled(_) => false,
+        });
+
+        if !any_enabled {
+            return Err(DecisionError::NoChoices(state));
+        }
+
+        Ok(Node {
             state,
             branches: Branches::Decision(Decision {
                 name: self.name,
                 player: self.player,
+                default,
                 choices: self
                     .choices
                     .into_iter()
-                    .map(|(name, c)| {
-                        let f = f.clone();
-                        Choice {
-                            name,
-                            continuation: Box::new(move |s| f(s, c)),
+                    .map(|(name, c, meta)| match c {
+                        ChoiceEntry::Enabled(c) => {
+                            let f = f.clone();
+                            Choice {
+                                name,
+                                continuation: Some(Box::new(move |s| f(s, c))),
+                                disabled: None,
+                                meta,
+                            }
                         }
+                        ChoiceEntry::Disabled(reason) => Choice {
+                            name,
+                            continuation: None,
+                            disabled: Some(reason),
+                            meta,
+                        },
                     })
                     .collect(),
             }),
-        }
+        })
     }
 }
 
+/// Why a `DecisionBuilder` couldn't build its decision. Carries back the state it was given.
+#[derive(Debug)]
+pub enum DecisionError<S> {
+    NoChoices(S),
+}
+
 impl<S, T> DecisionBuilder<S, T>
 where
     S: StateBase,
@@ -144,25 +496,40 @@ where
 }
 
 pub struct ChanceBuilder<T> {
-    name: String,
-    possibilities: Vec<(String, f64, T)>,
+    name: Label,
+    possibilities: Vec<(Label, f64, T)>,
 }
 
 impl<T> ChanceBuilder<T>
 where
     T: 'static,
 {
+    pub fn new<N>(name: N) -> Self
+    where
+        N: Into<Label>,
+    {
+        Self {
+            name: name.into(),
+            possibilities: Vec::new(),
+        }
+    }
+
     pub fn named_possibility<N>(mut self, name: N, weight: f64, possibility: T) -> Self
     where
-        N: Into<String>,
+        N: Into<Label>,
     {
         self.possibilities.push((name.into(), weight, possibility));
         self
     }
 
+    /// Adds a possibility labelled by a translation key rather than a display string.
+    pub fn keyed_possibility(self, key: &'static str, weight: f64, possibility: T) -> Self {
+        self.named_possibility(Label::Key(key), weight, possibility)
+    }
+
     pub fn named_possibilities<N, I>(mut self, choices: I) -> Self
     where
-        N: Into<String>,
+        N: Into<Label>,
         I: IntoIterator<Item = (N, f64, T)>,
     {
         self.possibilities
@@ -170,18 +537,78 @@ where
         self
     }
 
+    /// Rescales the weights of the possibilities so far to sum to 1, turning them into
+    /// probabilities. Does nothing if they sum to 0.
+    pub fn normalized(mut self) -> Self {
+        let total: f64 = self.possibilities.iter().map(|(_, w, _)| w).sum();
+
+        if total > 0.0 {
+            for (_, weight, _) in &mut self.possibilities {
+                *weight /= total;
+            }
+        }
+
+        self
+    }
+
+    /// Builds the chance node.
+    ///
+    /// Panics if any weight is negative or not finite, or if they add up to 0, since such a chance
+    /// could never be sampled.
     pub fn build<S, F>(self, state: S, f: F) -> Node<S>
     where
         S: StateBase,
         F: FnOnce(S, T) -> Node<S> + Clone + 'static,
     {
-        Node {
-            state: state,
+        let name = self.name.clone();
+
+        match self.try_build(state, f) {
+            Ok(node) => node,
+            Err(error) => panic!("Invalid chance {}: {}", name, error),
+        }
+    }
+
+    /// Builds the chance node, failing if the weights can't be sampled from.
+    ///
+    /// If the state has `RngBehavior::MostLikely`, the node only has the most likely possibility.
+    pub fn try_build<S, F>(self, state: S, f: F) -> Result<Node<S>, ChanceError<S>>
+    where
+        S: StateBase,
+        F: FnOnce(S, T) -> Node<S> + Clone + 'static,
+    {
+        if let Some((name, weight, _)) = self
+            .possibilities
+            .iter()
+            .find(|(_, w, _)| !w.is_finite() || *w < 0.0)
+        {
+            return Err(ChanceError::InvalidWeight {
+                state,
+                possibility: name.clone(),
+                weight: *weight,
+            });
+        }
+
+        if self.possibilities.iter().map(|(_, w, _)| w).sum::<f64>() == 0.0 {
+            return Err(ChanceError::NoWeight(state));
+        }
+
+        let mut possibilities = self.possibilities;
+
+        if state.rng_behavior() == RngBehavior::MostLikely {
+            let most_likely = (0..possibilities.len())
+                .rev()
+                .max_by(|&a, &b| possibilities[a].1.partial_cmp(&possibilities[b].1).unwrap())
+                .unwrap();
+            let (name, _, possibility) = possibilities.swap_remove(most_likely);
+            possibilities = vec![(name, 1.0, possibility)];
+        }
+
+        Ok(Node {
+            state,
             branches: Branches::Chance(Chance {
                 name: self.name,
 
-                possibilities: self
-                    .possibilities
+                possibilities: possibilities
                     .into_iter()
                     .map(|(name, weight, p)| {
                         let f = f.clone();
@@ -193,6 +620,32 @@ where
                     })
                     .collect(),
             }),
+        })
+    }
+}
+
+/// Why a `ChanceBuilder` couldn't build its chance. Carries back the state it was given.
+#[derive(Debug)]
+pub enum ChanceError<S> {
+    /// A weight was negative, infinite or NaN.
+    InvalidWeight {
+        state: S,
+        possibility: Label,
+        weight: f64,
+    },
+    /// The weights added up to 0, including when there were no possibilities at all.
+    NoWeight(S),
+}
+
+impl<S> Display for ChanceError<S> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ChanceError::InvalidWeight {
+                possibility,
+                weight,
+                ..
+            } => write!(f, "invalid weight {} for {}", weight, possibility),
+            ChanceError::NoWeight(_) => write!(f, "weights add up to 0"),
         }
     }
 }
@@ -217,23 +670,6 @@ where
     }
 }
 
-pub trait EventHandler<S>
-where
-    S: StateBase,
-{
-    fn on_turn_start(&self, state: S) -> Node<S> {
-        Node::pending(state)
-    }
-
-    fn on_turn_end(&self, state: S) -> Node<S> {
-        Node::pending(state)
-    }
-
-    fn on_etb(&self, state: S) -> Node<S> {
-        Node::pending(state)
-    }
-}
-
 #[derive(Debug)]
 pub struct Node<S>
 where
@@ -260,6 +696,25 @@ where
             branches: Branches::Pending,
         }
     }
+
+    /// The state at this node, before any of its branches are taken.
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    pub fn into_state(self) -> S {
+        self.state
+    }
+
+    /// Takes the events the state logged since they were last drained.
+    pub fn drain_log(&mut self) -> Vec<LogEvent> {
+        self.state.drain_log()
+    }
+
+    /// What can happen next, for inspecting the tree without taking a branch.
+    pub fn branches(&self) -> &Branches<S> {
+        &self.branches
+    }
 }
 
 impl<S> Node<S>
@@ -269,6 +724,32 @@ where
     pub fn then<F>(self, f: F) -> Self
     where
         F: FnOnce(S) -> Node<S> + Clone + 'static,
+    {
+        match self.branches {
+            Branches::Pending => f(self.state),
+            Branches::End => Node::end(self.state),
+            _ => self.map_continuations(move |node| node.then(f)),
+        }
+    }
+
+    /// Transforms the state at every leaf this node leads to, keeping the same branches.
+    ///
+    /// Unlike with `then`, the states of `End` leaves are transformed too.
+    pub fn map_state<F>(self, f: F) -> Self
+    where
+        F: FnOnce(S) -> S + Clone + 'static,
+    {
+        match self.branches {
+            Branches::Pending => Node::pending(f(self.state)),
+            Branches::End => Node::end(f(self.state)),
+            _ => self.map_continuations(move |node| node.map_state(f)),
+        }
+    }
+
+    /// Passes the node each branch leads to through `g`. Leaves are returned unchanged.
+    fn map_continuations<G>(self, g: G) -> Self
+    where
+        G: FnOnce(Node<S>) -> Node<S> + Clone + 'static,
     {
         match self.branches {
             Branches::Chance(c) => Self {
@@ -280,11 +761,11 @@ where
                         .into_iter()
                         .map(move |p| {
                             let cont = p.continuation;
-                            let f = f.clone();
+                            let g = g.clone();
                             Possibility {
                                 name: p.name,
                                 weight: p.weight,
-                                continuation: Box::new(move |s| cont(s).then(f)),
+                                continuation: Box::new(move |s| g(cont(s))),
                             }
                         })
                         .collect(),
@@ -295,55 +776,313 @@ where
                 branches: Branches::Decision(Decision {
                     player: d.player,
                     name: d.name,
+                    default: d.default,
                     choices: d
                         .choices
                         .into_iter()
                         .map(move |c| {
-                            let cont = c.continuation;
-                            let f = f.clone();
+                            let g = g.clone();
                             Choice {
                                 name: c.name,
-                                continuation: Box::new(move |s| cont(s).then(f)),
+                                continuation: c.continuation.map(|cont| -> Continuation<S> {
+                                    Box::new(move |s| g(cont(s)))
+                                }),
+                                disabled: c.disabled,
+                                meta: c.meta,
                             }
                         })
                         .collect(),
                 }),
             },
-            Branches::Pending => f(self.state),
-            Branches::End => Node::end(self.state),
+            Branches::Pending | Branches::End => self,
         }
     }
 }
 
-impl<S> Try for Node<S>
+impl<S> Node<S>
 where
     S: StateBase + 'static,
 {
-    type Ok = Self;
-    type Error = Self;
+    /// Drives the tree to an `End` or `Pending` leaf, asking `policy` at every decision and
+    /// sampling every chance with `rng`.
+    pub fn play<P, R>(self, policy: &mut P, rng: &mut R) -> S
+    where
+        P: DecisionPolicy<S>,
+        R: Rng,
+    {
+        let mut node = self;
+
+        loop {
+            match node.select(policy, rng) {
+                Some(selection) => node = node.take(selection),
+                None => return node.state,
+            }
+        }
+    }
+
+    /// Like `play`, but also collects the events the state logged along the way.
+    pub fn play_with_log<P, R>(self, policy: &mut P, rng: &mut R) -> (S, BattleLog)
+    where
+        P: DecisionPolicy<S>,
+        R: Rng,
+    {
+        let mut log = BattleLog::new();
+        let mut node = self;
+
+        loop {
+            log.extend(node.state.drain_log());
+
+            match node.select(policy, rng) {
+                Some(selection) => node = node.take(selection),
+                None => return (node.state, log),
+            }
+        }
+    }
+
+    /// Like `play`, but seeds its own RNG and records every branch taken so that the
+    /// playthrough can be reproduced with `replay`.
+    pub fn play_recorded<P>(self, policy: &mut P, seed: u64) -> (S, Replay)
+    where
+        P: DecisionPolicy<S>,
+    {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut replay = Replay {
+            seed,
+            selections: Vec::new(),
+        };
+        let mut node = self;
+
+        loop {
+            match node.select(policy, &mut rng) {
+                Some(selection) => {
+                    replay.selections.push(selection);
+                    node = node.take(selection);
+                }
+                None => return (node.state, replay),
+            }
+        }
+    }
+
+    /// Re-drives the tree with the branches recorded in `replay`.
+    ///
+    /// Panics if the tree doesn't have the same shape as the one the replay was recorded on.
+    pub fn replay(self, replay: &Replay) -> S {
+        match self.try_replay(replay) {
+            Ok(state) => state,
+            Err(ReplayError::Mismatch { step, node }) => {
+                panic!("Replay step {} doesn't fit {:?}", step, node)
+            }
+            Err(ReplayError::Unfinished(node)) => {
+                panic!("Replay ended before reaching a leaf: {:?}", node)
+            }
+        }
+    }
+
+    /// Like `replay`, but fails instead of panicking if the tree has a different shape.
+    pub fn try_replay(self, replay: &Replay) -> Result<S, ReplayError<S>> {
+        let mut node = self;
+
+        for (step, &selection) in replay.selections.iter().enumerate() {
+            let next = match selection {
+                BranchSelection::Decision(idx) => node.resolve_decision(idx),
+                BranchSelection::Chance(idx) => node.resolve_chance(idx),
+            };
+
+            node = next.map_err(|node| ReplayError::Mismatch { step, node })?;
+        }
+
+        match node.branches {
+            Branches::Pending | Branches::End => Ok(node.state),
+            Branches::Chance(_) | Branches::Decision(_) => Err(ReplayError::Unfinished(node)),
+        }
+    }
+
+    /// Follows every branch of this node at once, giving each one its own copy of the state.
+    ///
+    /// Disabled choices are left out, but the remaining ones keep their original indices.
+    pub fn expand(self) -> Expansion<S> {
+        let state = self.state;
+
+        match self.branches {
+            Branches::Chance(c) => Expansion::Chance(
+                c.possibilities
+                    .into_iter()
+                    .map(|p| (p.weight, (p.continuation)(state.clone())))
+                    .collect(),
+            ),
+            Branches::Decision(d) => Expansion::Decision(
+                d.player,
+                d.choices
+                    .into_iter()
+                    .enumerate()
+                    .filter_map(|(idx, c)| {
+                        c.continuation
+                            .map(|continuation| (idx, continuation(state.clone())))
+                    })
+                    .collect(),
+            ),
+            Branches::Pending | Branches::End => Expansion::Leaf(state),
+        }
+    }
+
+    /// Follows the choice at `choice_index` of this decision, as picked by a player.
+    ///
+    /// Gives the node back unchanged if it isn't a decision, or if the choice doesn't exist or is
+    /// disabled.
+    pub fn resolve_decision(self, choice_index: usize) -> Result<Self, Self> {
+        match &self.branches {
+            Branches::Decision(d)
+                if d.choices
+                    .get(choice_index)
+                    .is_some_and(|choice| choice.is_enabled()) =>
+            {
+                Ok(self.take(BranchSelection::Decision(choice_index)))
+            }
+            _ => Err(self),
+        }
+    }
 
-    fn into_result(self) -> Result<Self::Ok, Self::Error> {
+    /// Follows the possibility at `index` of this chance.
+    ///
+    /// Gives the node back unchanged if it isn't a chance or the possibility doesn't exist.
+    pub fn resolve_chance(self, index: usize) -> Result<Self, Self> {
         match &self.branches {
-            Branches::End => Err(self),
-            Branches::Chance(_) | Branches::Decision(_) | Branches::Pending => Ok(self),
+            Branches::Chance(c) if index < c.possibilities.len() => {
+                Ok(self.take(BranchSelection::Chance(index)))
+            }
+            _ => Err(self),
         }
     }
 
-    fn from_error(v: Self::Ok) -> Self {
-        match &v.branches {
-            Branches::Chance(_) | Branches::Decision(_) | Branches::Pending => v,
-            Branches::End => panic!("Invalid Ok: {:?}", v),
+    /// Follows a possibility of this chance picked at random, proportionally to its weight.
+    ///
+    /// Gives the node back unchanged if it isn't a chance or its weights add up to nothing.
+    pub fn sample_chance<R: Rng>(self, rng: &mut R) -> Result<Self, Self> {
+        match &self.branches {
+            Branches::Chance(c) if c.total_weight() > 0.0 => {
+                let index = c.sample(rng);
+                Ok(self.take(BranchSelection::Chance(index)))
+            }
+            _ => Err(self),
         }
     }
 
-    fn from_ok(v: Self::Error) -> Self {
-        match &v.branches {
-            Branches::Chance(_) | Branches::Decision(_) | Branches::Pending => {
-                panic!("Invalid Error: {:?}", v)
+    /// Follows decisions for as long as they have a `Decision::trivial_choice`, so that forced
+    /// choices don't need to be asked. Stops at the first chance, leaf or real decision.
+    pub fn auto_resolve_trivial(self) -> Self {
+        let mut node = self;
+
+        loop {
+            let idx = match &node.branches {
+                Branches::Decision(d) => d.trivial_choice(),
+                _ => None,
+            };
+
+            match idx {
+                Some(idx) => node = node.take(BranchSelection::Decision(idx)),
+                None => return node,
             }
-            Branches::End => v,
         }
     }
+
+    /// Picks the branch this node would take, or `None` if this is a leaf.
+    fn select<P, R>(&self, policy: &mut P, rng: &mut R) -> Option<BranchSelection>
+    where
+        P: DecisionPolicy<S>,
+        R: Rng,
+    {
+        match &self.branches {
+            Branches::Chance(c) => Some(BranchSelection::Chance(c.sample(rng))),
+            Branches::Decision(d) => Some(BranchSelection::Decision(
+                d.enabled_or_first(policy.choose(&self.state, d)),
+            )),
+            Branches::Pending | Branches::End => None,
+        }
+    }
+
+    /// Follows the selected branch.
+    ///
+    /// Panics if the selection doesn't match the kind of node or is out of range.
+    fn take(self, selection: BranchSelection) -> Self {
+        match (self.branches, selection) {
+            (Branches::Chance(c), BranchSelection::Chance(idx)) => {
+                let count = c.possibilities.len();
+                let possibility = c
+                    .possibilities
+                    .into_iter()
+                    .nth(idx)
+                    .unwrap_or_else(|| panic!("Possibility {} out of {}", idx, count));
+                (possibility.continuation)(self.state)
+            }
+            (Branches::Decision(d), BranchSelection::Decision(idx)) => {
+                let count = d.choices.len();
+                let choice = d
+                    .choices
+                    .into_iter()
+                    .nth(idx)
+                    .unwrap_or_else(|| panic!("Choice {} out of {}", idx, count));
+                let Choice {
+                    name,
+                    continuation,
+                    disabled,
+                    ..
+                } = choice;
+                let continuation = continuation.unwrap_or_else(|| {
+                    panic!(
+                        "Choice {} ({}) is disabled: {}",
+                        idx,
+                        name,
+                        disabled.unwrap_or_default()
+                    )
+                });
+                continuation(self.state)
+            }
+            (branches, selection) => panic!(
+                "Cannot take {:?} on {:?} in state {:?}",
+                selection, branches, self.state
+            ),
+        }
+    }
+}
+
+/// A node with all of its branches followed, as given by `Node::expand`.
+#[derive(Debug)]
+pub enum Expansion<S>
+where
+    S: StateBase,
+{
+    /// The weight of every possibility, with the node it leads to.
+    Chance(Vec<(f64, Node<S>)>),
+    /// The player deciding, and the index of every enabled choice with the node it leads to.
+    Decision(S::Player, Vec<(usize, Node<S>)>),
+    /// The state of a `Pending` or `End` node, which has nothing left to follow.
+    Leaf(S),
+}
+
+/// The branch taken at a single node: the index of a decision's choice or a chance's possibility.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BranchSelection {
+    Decision(usize),
+    Chance(usize),
+}
+
+/// Why a replay couldn't be followed through, with the node it got stuck on.
+#[derive(Debug)]
+pub enum ReplayError<S>
+where
+    S: StateBase,
+{
+    /// The selection at `step` was the wrong kind for `node`, out of range, or a disabled choice.
+    Mismatch { step: usize, node: Node<S> },
+    /// Every selection was followed, but `node` still has branches.
+    Unfinished(Node<S>),
+}
+
+/// A recording of a playthrough, which can be fed back into `Node::replay`.
+#[derive(Debug, Clone)]
+pub struct Replay {
+    pub seed: u64,
+    pub selections: Vec<BranchSelection>,
 }
 
 #[derive(Debug)]
@@ -362,19 +1101,66 @@ pub struct Chance<S>
 where
     S: StateBase,
 {
-    name: String,
+    name: Label,
     possibilities: Vec<Possibility<S>>,
 }
 
+impl<S> Chance<S>
+where
+    S: StateBase,
+{
+    pub fn name(&self) -> &Label {
+        &self.name
+    }
+
+    pub fn possibilities(&self) -> &[Possibility<S>] {
+        &self.possibilities
+    }
+
+    pub fn total_weight(&self) -> f64 {
+        self.possibilities.iter().map(|p| p.weight).sum()
+    }
+
+    /// Picks the index of a possibility, proportionally to its weight.
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> usize {
+        let mut roll = rng.gen::<f64>() * self.total_weight();
+
+        for (i, p) in self.possibilities.iter().enumerate() {
+            if roll < p.weight {
+                return i;
+            }
+
+            roll -= p.weight;
+        }
+
+        self.possibilities.len() - 1
+    }
+}
+
 pub struct Possibility<S>
 where
     S: StateBase,
 {
-    name: String,
+    name: Label,
     continuation: Box<dyn FnOnce(S) -> Node<S>>,
     weight: f64,
 }
 
+impl<S> Possibility<S>
+where
+    S: StateBase,
+{
+    pub fn name(&self) -> &Label {
+        &self.name
+    }
+
+    /// The relative likelihood of this possibility. Weights in a chance need not sum to 1, unless
+    /// it was built with `ChanceBuilder::normalized`.
+    pub fn weight(&self) -> f64 {
+        self.weight
+    }
+}
+
 impl<S: Debug> Debug for Possibility<S>
 where
     S: StateBase,
@@ -392,17 +1178,96 @@ pub struct Decision<S>
 where
     S: StateBase,
 {
-    name: String,
+    name: Label,
     player: S::Player,
     choices: Vec<Choice<S>>,
+    default: Option<usize>,
+}
+
+impl<S> Decision<S>
+where
+    S: StateBase,
+{
+    pub fn name(&self) -> &Label {
+        &self.name
+    }
+
+    pub fn player(&self) -> S::Player {
+        self.player
+    }
+
+    pub fn choices(&self) -> &[Choice<S>] {
+        &self.choices
+    }
+
+    /// The index of the choice to pick without asking, if one was declared.
+    pub fn default_choice(&self) -> Option<usize> {
+        self.default
+    }
+
+    /// The choice a driver can pick without asking: the declared default, or the only enabled
+    /// choice.
+    pub fn trivial_choice(&self) -> Option<usize> {
+        let mut enabled = self.enabled_choices().map(|(idx, _)| idx);
+
+        self.default.or(match (enabled.next(), enabled.next()) {
+            (Some(idx), None) => Some(idx),
+            _ => None,
+        })
+    }
+
+    /// The indices and choices that may actually be selected.
+    pub fn enabled_choices(&self) -> impl Iterator<Item = (usize, &Choice<S>)> {
+        self.choices
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.is_enabled())
+    }
+
+    /// `idx` if it's an enabled choice, and otherwise the first enabled choice.
+    pub fn enabled_or_first(&self, idx: usize) -> usize {
+        match self.choices.get(idx) {
+            Some(choice) if choice.is_enabled() => idx,
+            _ => self
+                .enabled_choices()
+                .next()
+                .map_or(idx, |(first, _)| first),
+        }
+    }
 }
 
 pub struct Choice<S>
 where
     S: StateBase,
 {
-    name: String,
-    continuation: Box<dyn FnOnce(S) -> Node<S>>,
+    name: Label,
+    /// `None` for disabled choices.
+    continuation: Option<Continuation<S>>,
+    disabled: Option<String>,
+    meta: Option<ChoiceMeta>,
+}
+
+impl<S> Choice<S>
+where
+    S: StateBase,
+{
+    pub fn name(&self) -> &Label {
+        &self.name
+    }
+
+    /// The reason this choice can't be selected, if it's disabled.
+    pub fn disabled(&self) -> Option<&str> {
+        self.disabled.as_deref()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.disabled.is_none()
+    }
+
+    /// The metadata the choice was built with, if it has some of type `M`.
+    pub fn meta<M: Any>(&self) -> Option<&M> {
+        self.meta.as_ref()?.downcast_ref()
+    }
 }
 
 impl<S: Debug> Debug for Choice<S>
@@ -410,6 +1275,467 @@ where
     S: StateBase,
 {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        f.debug_struct("Choice").field("name", &self.name).finish()
+        f.debug_struct("Choice")
+            .field("name", &self.name)
+            .field("disabled", &self.disabled)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use super::*;
+    use crate::testing::{Counter, Side};
+
+    fn is_end<S: StateBase>(node: &Node<S>) -> bool {
+        matches!(node.branches(), Branches::End)
+    }
+
+    #[test]
+    fn try_fold_stops_at_the_first_end() {
+        let calls = Rc::new(Cell::new(0));
+        let counted = calls.clone();
+
+        let node = Counter(0).try_fold(vec![1, 2, 3], move |state, item| {
+            counted.set(counted.get() + 1);
+            let state = Counter(state.0 + item);
+
+            if item == 2 {
+                Node::end(state)
+            } else {
+                Node::pending(state)
+            }
+        });
+
+        assert!(is_end(&node));
+        assert_eq!(node.state(), &Counter(3));
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[test]
+    fn try_fold_runs_every_pending_step() {
+        let node = Counter(0).try_fold(vec![1, 2, 3], |state, item| {
+            Node::pending(Counter(state.0 + item))
+        });
+
+        assert!(!is_end(&node));
+        assert_eq!(node.state(), &Counter(6));
+    }
+
+    fn add(state: Counter, amount: u32) -> Node<Counter> {
+        Node::pending(Counter(state.0 + amount))
+    }
+
+    #[test]
+    fn build_or_builds_when_there_are_choices() {
+        let node = DecisionBuilder::new("Add", Side::Us)
+            .choices(vec![1, 2])
+            .build_or(Counter(0), add, |state| Node::end(Counter(state.0 + 100)));
+
+        match node.branches() {
+            Branches::Decision(d) => assert_eq!(d.choices().len(), 2),
+            other => panic!("Expected a decision, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn build_or_falls_back_without_choices() {
+        let node = DecisionBuilder::new("Add", Side::Us)
+            .choices(Vec::<u32>::new())
+            .build_or(Counter(0), add, |state| Node::end(Counter(state.0 + 100)));
+
+        assert!(is_end(&node));
+        assert_eq!(node.state(), &Counter(100));
+    }
+
+    #[test]
+    fn disabled_choices_are_listed_but_not_selectable() {
+        let node = DecisionBuilder::new("Add", Side::Us)
+            .named_choice_disabled("Two", "Out of twos".to_owned())
+            .named_choice("One", 1)
+            .build(Counter(0), add);
+
+        match node.branches() {
+            Branches::Decision(d) => {
+                assert_eq!(d.choices()[0].disabled(), Some("Out of twos"));
+                assert_eq!(d.enabled_choices().map(|(i, _)| i).collect::<Vec<_>>(), [1]);
+            }
+            other => panic!("Expected a decision, got {:?}", other),
+        }
+
+        let node = node.resolve_decision(0).unwrap_err();
+        assert_eq!(node.resolve_decision(1).unwrap().state(), &Counter(1));
+    }
+
+    #[test]
+    fn only_disabled_choices_is_no_choice() {
+        let result = DecisionBuilder::new("Add", Side::Us)
+            .named_choice_disabled("Two", "Out of twos".to_owned())
+            .try_build(Counter(0), add);
+
+        assert!(matches!(result, Err(DecisionError::NoChoices(Counter(0)))));
+    }
+
+    #[test]
+    fn empty_decisions_are_rejected() {
+        let result = DecisionBuilder::new("Add", Side::Us)
+            .choices(Vec::<u32>::new())
+            .try_build(Counter(0), add);
+
+        assert!(matches!(result, Err(DecisionError::NoChoices(Counter(0)))));
+
+        let node = DecisionBuilder::new("Add", Side::Us)
+            .choices(Vec::<u32>::new())
+            .build(Counter(0), add);
+
+        assert!(matches!(node.branches(), Branches::Pending));
+        assert_eq!(node.state(), &Counter(0));
+    }
+
+    /// Always picks the first choice, even when it's disabled.
+    struct FirstPolicy;
+
+    impl DecisionPolicy<Counter> for FirstPolicy {
+        fn choose(&mut self, _state: &Counter, _decision: &Decision<Counter>) -> usize {
+            0
+        }
+    }
+
+    /// Adds a chosen number and then a random one, three times over.
+    fn rolls(state: Counter) -> Node<Counter> {
+        state.fold(0..3, |state, _| {
+            DecisionBuilder::new("Add", Side::Us)
+                .choices(vec![1, 2])
+                .build(state, |state, n| {
+                    ChanceBuilder::new("Roll")
+                        .possibility(0.5, 10 * n)
+                        .possibility(0.5, 100 * n)
+                        .build(state, add)
+                })
+        })
+    }
+
+    #[test]
+    fn replays_reproduce_the_playthrough() {
+        let mut policy = crate::driver::RandomPolicy::new(StdRng::seed_from_u64(1));
+        let (state, replay) = rolls(Counter(0)).play_recorded(&mut policy, 2);
+
+        assert_eq!(replay.selections.len(), 6);
+        assert_eq!(rolls(Counter(0)).replay(&replay), state);
+
+        let mut policy = crate::driver::RandomPolicy::new(StdRng::seed_from_u64(1));
+        let (_, again) = rolls(Counter(0)).play_recorded(&mut policy, 2);
+        assert_eq!(again.selections, replay.selections);
+    }
+
+    #[test]
+    #[should_panic(expected = "Replay step 0 doesn't fit")]
+    fn replays_check_the_shape_of_the_tree() {
+        let replay = Replay {
+            seed: 0,
+            selections: vec![BranchSelection::Chance(0)],
+        };
+
+        rolls(Counter(0)).replay(&replay);
+    }
+
+    #[test]
+    fn picking_a_disabled_choice_falls_back_to_the_first_enabled_one() {
+        let node = DecisionBuilder::new("Add", Side::Us)
+            .named_choice_disabled("Two", "Out of twos".to_owned())
+            .named_choice("One", 1)
+            .named_choice("Three", 3)
+            .build(Counter(0), add);
+
+        let state = node.play(&mut FirstPolicy, &mut rand::thread_rng());
+
+        assert_eq!(state, Counter(1));
+    }
+
+    #[test]
+    fn single_choice_decisions_auto_resolve() {
+        let node = DecisionBuilder::new("Add", Side::Us)
+            .named_choice("One", 1)
+            .build(Counter(0), add)
+            .auto_resolve_trivial();
+
+        assert!(matches!(node.branches(), Branches::Pending));
+        assert_eq!(node.state(), &Counter(1));
+    }
+
+    #[test]
+    fn two_choice_decisions_need_asking() {
+        let node = DecisionBuilder::new("Add", Side::Us)
+            .choices(vec![1, 2])
+            .build(Counter(0), add)
+            .auto_resolve_trivial();
+
+        match node.branches() {
+            Branches::Decision(d) => assert_eq!(d.trivial_choice(), None),
+            other => panic!("Expected a decision, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn filters_drop_choices_whose_payload_fails() {
+        let node = DecisionBuilder::new("Add", Side::Us)
+            .choices(1..=5)
+            .filter(|&n| n % 2 == 0)
+            .filter(|&n| n != 4)
+            .build(Counter(0), add);
+
+        match node.branches() {
+            Branches::Decision(d) => {
+                let names: Vec<_> = d.choices().iter().map(|c| c.name().to_string()).collect();
+                assert_eq!(names, ["2"]);
+            }
+            other => panic!("Expected a decision, got {:?}", other),
+        }
+
+        let node = DecisionBuilder::new("Add", Side::Us)
+            .choices(1..=5)
+            .filter(|&n| n > 5)
+            .build_or(Counter(0), add, |state| Node::end(Counter(state.0 + 100)));
+
+        assert!(is_end(&node));
+        assert_eq!(node.state(), &Counter(100));
+    }
+
+    #[test]
+    fn keyed_labels_stay_keys() {
+        let node = DecisionBuilder::new("Add", Side::Us)
+            .keyed_choice("choice.one", 1)
+            .named_choice("Two", 2)
+            .build(Counter(0), |state, n| {
+                ChanceBuilder::new("Roll")
+                    .keyed_possibility("roll.high", 0.5, 10 * n)
+                    .named_possibility("Low", 0.5, n)
+                    .build(state, add)
+            });
+
+        match node.branches() {
+            Branches::Decision(d) => {
+                assert_eq!(d.choices()[0].name(), &Label::Key("choice.one"));
+                assert_eq!(d.choices()[1].name(), &Label::Raw("Two".to_owned()));
+            }
+            other => panic!("Expected a decision, got {:?}", other),
+        }
+
+        match node.resolve_decision(0).unwrap().branches() {
+            Branches::Chance(c) => {
+                assert_eq!(c.possibilities()[0].name(), &Label::Key("roll.high"));
+                assert_eq!(c.possibilities()[1].name(), &Label::Raw("Low".to_owned()));
+            }
+            other => panic!("Expected a chance, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn chance_builders_start_empty_and_collect_possibilities() {
+        let node = ChanceBuilder::new("Roll")
+            .named_possibility("Low", 3.0, 1)
+            .possibility(1.0, 10)
+            .normalized()
+            .build(Counter(0), add);
+
+        match node.branches() {
+            Branches::Chance(c) => {
+                assert_eq!(c.name(), &Label::Raw("Roll".to_owned()));
+                let possibilities: Vec<_> = c
+                    .possibilities()
+                    .iter()
+                    .map(|p| (p.name().to_string(), p.weight()))
+                    .collect();
+                assert_eq!(
+                    possibilities,
+                    [("Low".to_owned(), 0.75), ("10".to_owned(), 0.25)]
+                );
+            }
+            other => panic!("Expected a chance, got {:?}", other),
+        }
+
+        assert!(ChanceBuilder::<u32>::new("Roll")
+            .try_build(Counter(0), add)
+            .is_err());
+    }
+
+    #[test]
+    fn default_choices_survive_filtering() {
+        let node = DecisionBuilder::new("Add", Side::Us)
+            .choices(vec![1, 2, 3])
+            .default_choice_index(2)
+            .filter(|&n| n != 1)
+            .build(Counter(0), add);
+
+        match node.branches() {
+            Branches::Decision(d) => assert_eq!(d.default_choice(), Some(1)),
+            other => panic!("Expected a decision, got {:?}", other),
+        }
+
+        assert_eq!(node.auto_resolve_trivial().state(), &Counter(3));
+    }
+
+    #[test]
+    fn choices_carry_their_meta() {
+        let node = DecisionBuilder::new("Add", Side::Us)
+            .named_choice_with_meta("One", 1, "uno")
+            .named_choice_disabled_with_meta("Two", "Out of twos".to_owned(), "dos")
+            .named_choice("Three", 3)
+            .build(Counter(0), add);
+
+        match node.branches() {
+            Branches::Decision(d) => {
+                let metas: Vec<_> = d.choices().iter().map(|c| c.meta::<&str>()).collect();
+                assert_eq!(metas, [Some(&"uno"), Some(&"dos"), None]);
+                assert_eq!(d.choices()[0].meta::<u32>(), None);
+            }
+            other => panic!("Expected a decision, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn meta_survives_chaining() {
+        let node = DecisionBuilder::new("Add", Side::Us)
+            .named_choice_with_meta("One", 1, 1.5)
+            .build(Counter(0), add)
+            .then(Node::end);
+
+        match node.branches() {
+            Branches::Decision(d) => assert_eq!(d.choices()[0].meta::<f64>(), Some(&1.5)),
+            other => panic!("Expected a decision, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn decisions_can_be_inspected_without_resolving_them() {
+        let node = DecisionBuilder::new("Player1 must choose", Side::Us)
+            .named_choice("Thunderbolt", 1)
+            .named_choice("Switch to Blissey", 2)
+            .build(Counter(0), add);
+
+        match node.branches() {
+            Branches::Decision(d) => {
+                let names: Vec<_> = d.choices().iter().map(|c| c.name().to_string()).collect();
+
+                assert_eq!(d.name().to_string(), "Player1 must choose");
+                assert_eq!(d.player(), Side::Us);
+                assert_eq!(names, ["Thunderbolt", "Switch to Blissey"]);
+            }
+            other => panic!("Expected a decision, got {:?}", other),
+        }
+        assert_eq!(node.state(), &Counter(0));
+    }
+
+    #[test]
+    fn resolving_decisions_by_index() {
+        let decision = || {
+            DecisionBuilder::new("Add", Side::Us)
+                .choices(vec![1, 2])
+                .build(Counter(0), add)
+        };
+
+        assert_eq!(decision().resolve_decision(1).unwrap().state(), &Counter(2));
+
+        let node = decision().resolve_decision(2).unwrap_err();
+        assert!(matches!(node.branches(), Branches::Decision(_)));
+
+        let node = ChanceBuilder::new("Roll")
+            .possibility(1.0, 1)
+            .build(Counter(0), add)
+            .resolve_decision(0)
+            .unwrap_err();
+        assert!(matches!(node.branches(), Branches::Chance(_)));
+    }
+
+    #[test]
+    fn single_possibility_chances_always_resolve_to_it() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        for _ in 0..10 {
+            let node = ChanceBuilder::new("Roll")
+                .possibility(1.0, 5)
+                .build(Counter(0), add);
+
+            assert_eq!(node.sample_chance(&mut rng).unwrap().state(), &Counter(5));
+        }
+    }
+
+    #[test]
+    fn chances_sample_in_proportion_to_their_weights() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let draws: u32 = 10_000;
+
+        let likely = (0..draws)
+            .filter(|_| {
+                let node = ChanceBuilder::new("Roll")
+                    .possibility(3.0, 1)
+                    .possibility(1.0, 2)
+                    .build(Counter(0), add);
+
+                node.sample_chance(&mut rng).unwrap().state() == &Counter(1)
+            })
+            .count();
+
+        assert!((likely as f64 / f64::from(draws) - 0.75).abs() < 0.02);
+    }
+
+    #[test]
+    fn invalid_weights_are_rejected() {
+        for weight in [f64::NAN, -1.0, f64::INFINITY] {
+            let result = ChanceBuilder::new("Roll")
+                .possibility(1.0, 1)
+                .possibility(weight, 2)
+                .try_build(Counter(0), add);
+
+            assert!(matches!(
+                result,
+                Err(ChanceError::InvalidWeight { weight: w, .. }) if w.to_bits() == weight.to_bits()
+            ));
+        }
+    }
+
+    #[test]
+    fn normalizing_turns_weights_into_probabilities() {
+        let node = ChanceBuilder::new("Roll")
+            .possibility(1.0, 1)
+            .possibility(3.0, 2)
+            .normalized()
+            .build(Counter(0), add);
+
+        match node.branches() {
+            Branches::Chance(c) => {
+                let weights: Vec<_> = c.possibilities().iter().map(|p| p.weight()).collect();
+                assert_eq!(weights, [0.25, 0.75]);
+            }
+            other => panic!("Expected a chance, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn map_state_defers_into_each_continuation() {
+        let node = DecisionBuilder::new("Add", Side::Us)
+            .choices(vec![1, 2])
+            .build(Counter(0), add)
+            .map_state(|state| Counter(state.0 * 10));
+
+        assert_eq!(node.state(), &Counter(0));
+        match node.branches() {
+            Branches::Decision(d) => assert_eq!(d.choices().len(), 2),
+            other => panic!("Expected a decision, got {:?}", other),
+        }
+
+        let node = node.resolve_decision(1).unwrap();
+        assert!(matches!(node.branches(), Branches::Pending));
+        assert_eq!(node.state(), &Counter(20));
+    }
+
+    #[test]
+    fn map_state_transforms_ended_states() {
+        let node = Node::end(Counter(1)).map_state(|state| Counter(state.0 + 1));
+
+        assert!(is_end(&node));
+        assert_eq!(node.state(), &Counter(2));
     }
 }
@@ -0,0 +1,19 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Status {
+    Healthy,
+    Burn,
+    Poison,
+    BadlyPoisoned {
+        counter: u8,
+    },
+    Paralysis,
+    /// `turns` is how many more turns the pokemon fails to move before waking up.
+    Sleep {
+        turns: u8,
+    },
+    Freeze,
+}
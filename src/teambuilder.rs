@@ -0,0 +1,175 @@
+use std::{collections::HashMap, rc::Rc};
+
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{
+    damage,
+    pokemon::{self, PokeType, PokemonBuilder, PokemonSpecies, Team, TypeEffectiveness},
+    pokemove::{MoveCategory, MoveRegistry},
+};
+
+/// For every defending type, the best effectiveness any damaging move on the team achieves.
+pub fn offensive_coverage(team: &Team) -> HashMap<PokeType, TypeEffectiveness> {
+    PokeType::values()
+        .iter()
+        .map(|defender| {
+            let best = team
+                .iter()
+                .flat_map(|p| p.moves.iter())
+                .filter(|m| m.category != MoveCategory::Status)
+                .map(|m| m.poke_type.effectiveness_on(defender))
+                .max()
+                .unwrap_or(TypeEffectiveness::NoEffect);
+
+            (*defender, best)
+        })
+        .collect()
+}
+
+/// For every attacking type, the average damage multiplier it has against the team's members.
+///
+/// Values above 1.0 point to a type the team is collectively weak to.
+pub fn defensive_weaknesses(team: &Team) -> HashMap<PokeType, f64> {
+    member_multipliers(team)
+        .map(|(attacker, multipliers)| {
+            let total: f64 = multipliers.iter().sum();
+            (attacker, total / team.len().max(1) as f64)
+        })
+        .collect()
+}
+
+/// For every attacking type, how many of the team's members it hits super effectively.
+pub fn team_weaknesses(team: &Team) -> HashMap<PokeType, u32> {
+    count_members(team, |multiplier| multiplier > 1.0)
+}
+
+/// For every attacking type, how many of the team's members resist it or are immune to it.
+pub fn team_resistances(team: &Team) -> HashMap<PokeType, u32> {
+    count_members(team, |multiplier| multiplier < 1.0)
+}
+
+fn count_members<F>(team: &Team, f: F) -> HashMap<PokeType, u32>
+where
+    F: Fn(f64) -> bool,
+{
+    member_multipliers(team)
+        .map(|(attacker, multipliers)| {
+            let count = multipliers.into_iter().filter(|&m| f(m)).count();
+            (attacker, count as u32)
+        })
+        .collect()
+}
+
+/// Every attacking type with the damage multiplier it has against each of the team's members.
+fn member_multipliers(team: &Team) -> impl Iterator<Item = (PokeType, Vec<f64>)> + '_ {
+    PokeType::values().iter().map(move |&attacker| {
+        let multipliers = team
+            .iter()
+            .map(|p| damage::effectiveness_against(attacker, &p.form))
+            .collect();
+
+        (attacker, multipliers)
+    })
+}
+
+/// A team of up to `size` random pokemon at `level`, each a random form of one of `species` with
+/// a rolled gender and shininess, knowing four random moves from `moves`.
+pub fn random_team<R: Rng>(
+    species: &[Rc<PokemonSpecies>],
+    moves: &MoveRegistry,
+    level: u8,
+    size: usize,
+    rng: &mut R,
+) -> Team {
+    let movepool = moves.callable_moves();
+    let mut team = Team::new();
+
+    while team.len() < size.min(team.capacity()) {
+        let form = match species.choose(rng).and_then(|s| s.forms.choose(rng)) {
+            Some(form) => form.clone(),
+            None => break,
+        };
+
+        let pokemon = movepool
+            .choose_multiple(rng, 4)
+            .fold(PokemonBuilder::new(form.clone()), |builder, m| {
+                builder.with_move(m.clone())
+            })
+            .level(level)
+            .gender(form.genders.roll(rng))
+            .shiny(pokemon::roll_shiny(rng, false))
+            .build();
+
+        team.push(pokemon);
+    }
+
+    team
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::{
+        pokemon::{AllowedGenders, Stats},
+        testing,
+    };
+
+    fn electric_weak_team() -> Team {
+        testing::team(vec![
+            testing::builder("Gyarados", &[PokeType::Water, PokeType::Flying], &[]).build(),
+            testing::builder("Pelipper", &[PokeType::Water, PokeType::Flying], &[]).build(),
+            testing::builder("Skarmory", &[PokeType::Steel, PokeType::Flying], &[]).build(),
+            testing::builder("Garchomp", &[PokeType::Dragon, PokeType::Ground], &[]).build(),
+        ])
+    }
+
+    #[test]
+    fn counts_a_triple_electric_weakness() {
+        let team = electric_weak_team();
+
+        assert_eq!(team_weaknesses(&team)[&PokeType::Electric], 3);
+        assert_eq!(team_resistances(&team)[&PokeType::Electric], 1);
+    }
+
+    #[test]
+    fn averages_multipliers_across_the_team() {
+        let team = electric_weak_team();
+
+        // 4x, 4x, 2x and 0x.
+        assert_eq!(defensive_weaknesses(&team)[&PokeType::Electric], 2.5);
+    }
+
+    #[test]
+    fn random_teams_roll_legal_pokemon() {
+        let form = testing::form_with(
+            "Eevee",
+            &[PokeType::Normal],
+            AllowedGenders::from_female_eighths(1),
+            Stats::uniform(55),
+        );
+        let species = [Rc::new(PokemonSpecies {
+            forms: vec![form.clone()],
+            ..(*form.species).clone()
+        })];
+        let mut rng = StdRng::seed_from_u64(107);
+
+        let team = random_team(&species, MoveRegistry::builtin(), 50, 6, &mut rng);
+
+        assert_eq!(team.len(), 6);
+        for pokemon in &team {
+            assert!(pokemon.validate_gender().is_ok());
+            assert_eq!(pokemon.moves.len(), 4);
+            assert_eq!(pokemon.level, 50);
+            assert!(!pokemon.is_fainted());
+        }
+    }
+
+    #[test]
+    fn random_teams_need_species() {
+        let mut rng = StdRng::seed_from_u64(107);
+
+        assert!(random_team(&[], MoveRegistry::builtin(), 50, 6, &mut rng).is_empty());
+    }
+}
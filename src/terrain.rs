@@ -0,0 +1,50 @@
+use std::fmt::{self, Display, Formatter};
+
+use crate::pokemon::PokeType;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A field-wide effect which only applies to grounded pokemon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Terrain {
+    None,
+    Electric,
+    Grassy,
+    Misty,
+    Psychic,
+}
+
+impl Terrain {
+    /// The damage multiplier for a move of type `poke_type` under this terrain.
+    pub fn damage_multiplier(
+        self,
+        poke_type: PokeType,
+        attacker_grounded: bool,
+        defender_grounded: bool,
+    ) -> f64 {
+        match (self, poke_type) {
+            (Terrain::Electric, PokeType::Electric)
+            | (Terrain::Grassy, PokeType::Grass)
+            | (Terrain::Psychic, PokeType::Psychic)
+                if attacker_grounded =>
+            {
+                1.3
+            }
+            (Terrain::Misty, PokeType::Dragon) if defender_grounded => 0.5,
+            _ => 1.0,
+        }
+    }
+}
+
+impl Display for Terrain {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Terrain::None => write!(f, "No terrain"),
+            Terrain::Electric => write!(f, "Electric Terrain"),
+            Terrain::Grassy => write!(f, "Grassy Terrain"),
+            Terrain::Misty => write!(f, "Misty Terrain"),
+            Terrain::Psychic => write!(f, "Psychic Terrain"),
+        }
+    }
+}
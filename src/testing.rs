@@ -0,0 +1,138 @@
+//! Fixtures shared by the unit tests.
+
+use std::rc::Rc;
+
+use crate::{
+    pokemon::{
+        AllowedGenders, PokeType, Pokemon, PokemonBuilder, PokemonForm, PokemonSpecies, Stats, Team,
+    },
+    pokemove::PokeMove,
+    state::{Branches, Node, PlayerBase, PlayerStateBase, StateBase},
+};
+
+/// A single-form, genderless species with the given types and base stats of 80 across the board.
+pub fn form(name: &str, types: &[PokeType]) -> Rc<PokemonForm> {
+    form_with(name, types, AllowedGenders::NoGender, Stats::uniform(80))
+}
+
+pub fn form_with(
+    name: &str,
+    types: &[PokeType],
+    genders: AllowedGenders,
+    base_stats: Stats,
+) -> Rc<PokemonForm> {
+    let species = Rc::new(PokemonSpecies {
+        national_dex_no: 1,
+        name: name.to_owned(),
+        forms: Vec::new(),
+    });
+
+    Rc::new(PokemonForm {
+        species,
+        name: None,
+        types: types.to_vec(),
+        genders,
+        base_stats,
+        weight_hg: 500,
+        evolutions: Vec::new(),
+    })
+}
+
+/// Starts a level 50 pokemon of `form(name, types)` knowing the given built-in moves.
+pub fn builder(name: &str, types: &[PokeType], moves: &[&str]) -> PokemonBuilder {
+    moves.iter().fold(
+        PokemonBuilder::new(form(name, types)).level(50),
+        |builder, m| builder.with_move(m.parse::<PokeMove>().unwrap()),
+    )
+}
+
+/// A state that's just a number, shared by both sides, for exercising the generic tree machinery.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Counter(pub u32);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Side {
+    Us,
+    Them,
+}
+
+impl PlayerBase for Side {
+    fn values() -> &'static [Self] {
+        &[Side::Us, Side::Them]
+    }
+}
+
+impl PlayerStateBase for Counter {}
+
+impl StateBase for Counter {
+    type Player = Side;
+    type PlayerState = Counter;
+
+    fn player(&self, _player: Side) -> &Counter {
+        self
+    }
+
+    fn player_mut(&mut self, _player: Side) -> &mut Counter {
+        self
+    }
+}
+
+pub fn team(pokemon: Vec<Pokemon>) -> Team {
+    pokemon.into_iter().collect()
+}
+
+/// Follows `node`, taking each decision's choice from the next name in `script`.
+///
+/// Chances take the possibility named next in `script` if there is one, and otherwise their most
+/// likely possibility, so that scripts only need to spell out the luck they care about. Stops at
+/// a leaf, or at the first decision once `script` runs out.
+pub fn play<S: StateBase + 'static>(node: Node<S>, script: &[&str]) -> Node<S> {
+    let mut script = script.iter().peekable();
+    let mut node = node;
+
+    loop {
+        node = match node.branches() {
+            Branches::Decision(d) => {
+                let name = match script.next() {
+                    Some(name) => name,
+                    None => return node,
+                };
+                let idx = d
+                    .choices()
+                    .iter()
+                    .position(|c| c.name().to_string() == *name)
+                    .unwrap_or_else(|| panic!("No choice {} in {:?}", name, d));
+
+                node.resolve_decision(idx)
+                    .unwrap_or_else(|node| panic!("Can't choose {} in {:?}", name, node))
+            }
+            Branches::Chance(c) => {
+                let possibilities = c.possibilities();
+                let named = script
+                    .peek()
+                    .and_then(|name| {
+                        possibilities
+                            .iter()
+                            .position(|p| p.name().to_string() == **name)
+                    })
+                    .inspect(|_| {
+                        script.next();
+                    });
+                let idx = named.unwrap_or_else(|| {
+                    (0..possibilities.len())
+                        .rev()
+                        .max_by(|&a, &b| {
+                            possibilities[a]
+                                .weight()
+                                .partial_cmp(&possibilities[b].weight())
+                                .unwrap()
+                        })
+                        .unwrap()
+                });
+
+                node.resolve_chance(idx).unwrap()
+            }
+            Branches::Pending | Branches::End => return node,
+        };
+    }
+}
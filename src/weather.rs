@@ -0,0 +1,35 @@
+use crate::pokemon::PokeType;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Weather {
+    Clear,
+    Rain,
+    Sun,
+    Sandstorm,
+    Hail,
+}
+
+impl Weather {
+    /// Whether this weather damages a pokemon of the given types at the end of each turn.
+    pub fn damages(self, types: &[PokeType]) -> bool {
+        match self {
+            Weather::Sandstorm => !types
+                .iter()
+                .any(|t| matches!(t, PokeType::Rock | PokeType::Ground | PokeType::Steel)),
+            Weather::Hail => !types.contains(&PokeType::Ice),
+            Weather::Clear | Weather::Rain | Weather::Sun => false,
+        }
+    }
+
+    /// Multiplies the damage of moves of `poke_type` while this weather is active.
+    pub fn damage_multiplier(self, poke_type: PokeType) -> f64 {
+        match (self, poke_type) {
+            (Weather::Rain, PokeType::Water) | (Weather::Sun, PokeType::Fire) => 1.5,
+            (Weather::Rain, PokeType::Fire) | (Weather::Sun, PokeType::Water) => 0.5,
+            _ => 1.0,
+        }
+    }
+}